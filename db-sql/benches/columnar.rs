@@ -0,0 +1,124 @@
+//! Compares the row and the columnar storage of a table on a generated dataset,
+//! run with `cargo bench --bench columnar`.
+use db_sql::{
+    exec::{ExecutionContext, Table},
+    parser::{ColumnDefinition, SqlDataType, SqlValue, TableStorage},
+};
+use std::{hint::black_box, time::Instant};
+
+const ROWS: usize = 200_000;
+const RUNS: u32 = 5;
+
+fn column(name: &str, data_type: SqlDataType) -> ColumnDefinition {
+    ColumnDefinition {
+        name: name.into(),
+        data_type,
+        constraint: None,
+        default: None,
+        auto_increment: false,
+        references: None,
+    }
+}
+
+/// Events sorted by day, of a few kinds, with amounts that rarely repeat
+fn generate(storage: TableStorage) -> Table {
+    let columns = vec![
+        column("day", SqlDataType::Integer),
+        column("kind", SqlDataType::String),
+        column("amount", SqlDataType::Integer),
+    ];
+    let mut tbl = Table::with_storage("events".into(), columns, storage);
+    let kinds = ["view", "click", "cart", "buy"];
+    for i in 0..ROWS {
+        tbl.insert(vec![
+            SqlValue::Integer((i / 1000) as i64),
+            SqlValue::String(kinds[i * 7 % kinds.len()].into()),
+            SqlValue::Integer((i * 7919 % 1000) as i64),
+        ])
+        .unwrap();
+    }
+    tbl
+}
+
+/// The same events in a table of each storage, `events_rows` and
+/// `events_columnar`, to be queried with SQL
+fn generate_context() -> ExecutionContext {
+    let mut ctx = ExecutionContext::new();
+    for storage in ["rows", "columnar"] {
+        let create = format!(
+            "CREATE TABLE events_{storage} (day INT, kind STRING, amount INT) USING {storage}"
+        );
+        ctx.run(&create).unwrap();
+    }
+    let kinds = ["view", "click", "cart", "buy"];
+    for i in 0..ROWS {
+        for storage in ["rows", "columnar"] {
+            let insert = format!(
+                "INSERT INTO events_{storage} VALUES ({}, '{}', {})",
+                i / 1000,
+                kinds[i * 7 % kinds.len()],
+                i * 7919 % 1000
+            );
+            ctx.run(&insert).unwrap();
+        }
+    }
+    ctx
+}
+
+/// The average time of the runs in milliseconds
+fn measure(mut f: impl FnMut()) -> f64 {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        f();
+    }
+    start.elapsed().as_secs_f64() * 1000.0 / RUNS as f64
+}
+
+fn main() {
+    println!("{ROWS} rows, average of {RUNS} runs");
+    println!("{:<24}{:>12}{:>12}", "", "rows", "columnar");
+    let tables = [
+        generate(TableStorage::Rows),
+        generate(TableStorage::Columnar),
+    ];
+
+    let scan = tables.each_ref().map(|tbl| {
+        measure(|| {
+            black_box(
+                tbl.iter()
+                    .filter(|x| x.values[1] == SqlValue::String("buy".into()))
+                    .count(),
+            );
+        })
+    });
+    println!("{:<24}{:>10.2}ms{:>10.2}ms", "scan", scan[0], scan[1]);
+
+    for (function, column) in [("count", "kind"), ("sum", "day"), ("max", "amount")] {
+        let times = tables.each_ref().map(|tbl| {
+            measure(|| {
+                black_box(tbl.aggregate(function, column).unwrap());
+            })
+        });
+        let name = format!("{function}({column})");
+        println!("{:<24}{:>10.2}ms{:>10.2}ms", name, times[0], times[1]);
+    }
+
+    let mut ctx = generate_context();
+    let queries = [
+        ("select where kind", "SELECT * FROM {} WHERE kind = 'buy'"),
+        ("select where amount", "SELECT * FROM {} WHERE amount > 990"),
+        (
+            "select aggregates",
+            "SELECT COUNT(*), SUM(day), MAX(amount) FROM {}",
+        ),
+    ];
+    for (name, query) in queries {
+        let times = ["events_rows", "events_columnar"].map(|table| {
+            let query = query.replace("{}", table);
+            measure(|| {
+                black_box(ctx.run(&query).unwrap());
+            })
+        });
+        println!("{:<24}{:>10.2}ms{:>10.2}ms", name, times[0], times[1]);
+    }
+}
@@ -0,0 +1,131 @@
+use db_sql::format::OutputFormat;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: db-sql [PATH] [OPTIONS]
+
+Opens the database file PATH, or an in-memory database without one, and runs
+the statements of the options in order. Without them the statements are read
+from the terminal, or from the standard input when it is not a terminal.
+
+Options:
+  -c SQL           Execute the SQL statements
+  -f FILE          Execute the statements and dot-commands in FILE
+  --readonly       Only run queries, the file of the database is not changed
+  --user NAME      Log in as the user NAME, whose password is read from the
+                   DB_SQL_PASSWORD variable or asked for. Without it the
+                   statements run as the owner of the database
+  --format MODE    Print the rows as table, csv, json or markdown
+  -h, --help       Show this message
+  --version        Show the version
+
+Exit status: 0 on success, 1 when a statement or the login failed, 2 for
+invalid arguments and 3 when the database or a file cannot be read.";
+
+/// A statement failed, the following ones still ran, or the login failed
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+/// The database or a script could not be opened or read
+pub const EXIT_IO: i32 = 3;
+/// The variable holding the password of `--user`
+pub const PASSWORD_VAR: &str = "DB_SQL_PASSWORD";
+
+/// What the options ask to execute, in the order they are given
+#[derive(Clone, Debug, PartialEq)]
+pub enum Input {
+    Sql(String),
+    File(PathBuf),
+}
+
+/// The command line arguments
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Args {
+    /// the file of the database, in memory without one
+    pub path: Option<PathBuf>,
+    pub inputs: Vec<Input>,
+    pub readonly: bool,
+    /// the user to log in as
+    pub user: Option<String>,
+    pub format: OutputFormat,
+    pub help: bool,
+    pub version: bool,
+}
+
+impl Args {
+    /// Reads the arguments after the name of the program
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |option: &str| {
+                args.next()
+                    .ok_or_else(|| format!("{option} expects a value"))
+            };
+            match arg.as_str() {
+                "-c" => parsed.inputs.push(Input::Sql(value("-c")?)),
+                "-f" => parsed.inputs.push(Input::File(value("-f")?.into())),
+                "--readonly" => parsed.readonly = true,
+                "--user" => parsed.user = Some(value("--user")?),
+                "--format" => parsed.format = OutputFormat::try_from(value("--format")?.as_str())?,
+                "-h" | "--help" => parsed.help = true,
+                "--version" => parsed.version = true,
+                x if x.starts_with("--format=") => {
+                    parsed.format = OutputFormat::try_from(&x["--format=".len()..])?
+                }
+                x if x.starts_with('-') && x != "-" => return Err(format!("unknown option {x}")),
+                _ if parsed.path.is_none() => parsed.path = Some(arg.into()),
+                _ => return Err(format!("unexpected argument {arg}")),
+            }
+        }
+        if parsed.readonly && parsed.path.is_none() {
+            return Err("--readonly needs the PATH of a database".into());
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_arguments() {
+        let parse = |args: &[&str]| Args::parse(args.iter().map(|x| x.to_string()));
+        assert_eq!(parse(&[]), Ok(Args::default()));
+        assert_eq!(
+            parse(&[
+                "app.db",
+                "-c",
+                "SELECT 1",
+                "-f",
+                "seed.sql",
+                "--readonly",
+                "--user",
+                "alice",
+                "--format",
+                "csv"
+            ]),
+            Ok(Args {
+                path: Some("app.db".into()),
+                inputs: vec![
+                    Input::Sql("SELECT 1".into()),
+                    Input::File("seed.sql".into())
+                ],
+                readonly: true,
+                user: Some("alice".into()),
+                format: OutputFormat::Csv,
+                ..Args::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--format=json"]).map(|x| x.format),
+            Ok(OutputFormat::Json)
+        );
+        assert!(parse(&["-c"]).is_err());
+        assert!(parse(&["--user"]).is_err());
+        assert!(parse(&["--format", "xml"]).is_err());
+        assert!(parse(&["--readonly"]).is_err());
+        assert!(parse(&["a.db", "b.db"]).is_err());
+        assert!(parse(&["-x"]).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+mod row;
+mod statement;
+
+pub use row::*;
+pub use statement::*;
+
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::{ExecutionContext, ExecutionResult, ResultSet},
+};
+use std::path::Path;
+
+/// An embeddable database, kept in memory or backed by a file.
+///
+/// ```
+/// use db_sql::{Database, errors::ExecutionError};
+///
+/// let mut db = Database::open_in_memory();
+/// db.execute("CREATE TABLE users (id INT, name STRING)")?;
+/// let mut insert = db.prepare("INSERT INTO users VALUES (?, ?)")?;
+/// insert.bind(1, 1)?.bind(2, "alice")?.execute(&mut db)?;
+///
+/// let mut query = db.prepare("SELECT name FROM users WHERE id = ?")?;
+/// for row in query.bind(1, 1)?.query(&mut db)?.iter() {
+///     let name: String = row.get("name")?;
+///     println!("{name}");
+/// }
+/// # Ok::<(), ExecutionError>(())
+/// ```
+#[derive(Debug)]
+pub struct Database {
+    ctx: ExecutionContext,
+}
+
+impl Database {
+    /// Opens the database stored in the file, which is created when it is missing.
+    pub fn open(path: impl AsRef<Path>) -> DbResult<Self> {
+        Ok(Self {
+            ctx: ExecutionContext::open(path)?,
+        })
+    }
+    /// Opens the database stored in the file for queries only, it must exist
+    pub fn open_readonly(path: impl AsRef<Path>) -> DbResult<Self> {
+        Ok(Self {
+            ctx: ExecutionContext::open_readonly(path)?,
+        })
+    }
+    pub fn open_in_memory() -> Self {
+        Self {
+            ctx: ExecutionContext::new(),
+        }
+    }
+    pub fn context(&self) -> &ExecutionContext {
+        &self.ctx
+    }
+    /// Logs in as a user created by `CREATE USER`, whose privileges are checked for
+    /// the later statements
+    pub fn authenticate(&mut self, user: &str, password: &str) -> DbResult<(), ExecutionError> {
+        self.ctx.authenticate(user, password)
+    }
+    pub fn prepare(&self, sql: &str) -> DbResult<Statement, ExecutionError> {
+        Statement::new(sql)
+    }
+    /// Executes a statement without parameters
+    pub fn execute(&mut self, sql: &str) -> DbResult<ExecutionResult, ExecutionError> {
+        self.ctx.run(sql)
+    }
+    /// Executes a query without parameters and returns its rows
+    pub fn query(&mut self, sql: &str) -> DbResult<ResultSet, ExecutionError> {
+        let stmt = Statement::new(sql).map_err(|e| self.ctx.failed(e))?;
+        stmt.query(self)
+    }
+    pub(crate) fn context_mut(&mut self) -> &mut ExecutionContext {
+        &mut self.ctx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Date, SqlValue, Timestamp};
+
+    #[test]
+    fn should_open_readonly() {
+        let path = std::env::temp_dir().join(format!("db_sql_{}.sql", uuid::Uuid::new_v4()));
+        let mut db = Database::open(&path).unwrap();
+        db.execute("CREATE TABLE t (id INT)").unwrap();
+        db.execute("INSERT INTO t VALUES (1)").unwrap();
+        drop(db);
+        let script = std::fs::read_to_string(&path).unwrap();
+
+        let mut db = Database::open_readonly(&path).unwrap();
+        assert!(db.context().is_readonly());
+        assert_eq!(db.query("SELECT id FROM t").unwrap().rows.len(), 1);
+        assert_eq!(
+            db.execute("INSERT INTO t VALUES (2)"),
+            Err(ExecutionError::ReadOnly)
+        );
+        assert_eq!(db.execute("ANALYZE"), Err(ExecutionError::ReadOnly));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), script);
+        std::fs::remove_file(&path).unwrap();
+        assert!(Database::open_readonly(&path).is_err());
+    }
+
+    #[test]
+    fn should_count_the_queries_which_fail_to_parse() {
+        let mut db = Database::open_in_memory();
+        assert!(db.query("SELEC x").is_err());
+        assert!(db.execute("SELEC x").is_err());
+        let stats = db.context().statistics();
+        assert_eq!((stats.statements, stats.errors), (2, 2));
+    }
+
+    #[test]
+    fn should_reopen_after_rejecting_non_finite_floats() {
+        let path = std::env::temp_dir().join(format!("db_sql_{}.sql", uuid::Uuid::new_v4()));
+        let mut db = Database::open(&path).unwrap();
+        db.execute("CREATE TABLE o (x FLOAT, s STRING)").unwrap();
+        db.execute("INSERT INTO o VALUES (1e308, 'a')").unwrap();
+        for sql in [
+            "INSERT INTO o VALUES (1e308 * 10, 'b')",
+            "INSERT INTO o VALUES (CAST('NaN' AS FLOAT), 'b')",
+            "INSERT INTO o VALUES (1e999, 'b')",
+            "UPDATE o SET x = x * 10",
+        ] {
+            assert!(db.execute(sql).is_err(), "{sql}");
+        }
+        let mut insert = db.prepare("INSERT INTO o VALUES (?, 'b')").unwrap();
+        let bound = insert.bind(1, f64::NAN).unwrap().execute(&mut db);
+        assert!(matches!(bound, Err(ExecutionError::ValueOutOfRange { .. })));
+        drop(db);
+
+        let mut db = Database::open(&path).unwrap();
+        let rs = db.query("SELECT x, s FROM o").unwrap();
+        assert_eq!(rs.rows.len(), 1);
+        assert_eq!(rs.rows[0][0], SqlValue::Float(1e308));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn should_reopen_after_rejecting_dates_out_of_range() {
+        let path = std::env::temp_dir().join(format!("db_sql_{}.sql", uuid::Uuid::new_v4()));
+        let mut db = Database::open(&path).unwrap();
+        db.execute("CREATE TABLE d (at DATE, ts TIMESTAMP)")
+            .unwrap();
+        db.execute("INSERT INTO d VALUES (CAST('9999-12-30' AS DATE) + 1, nil)")
+            .unwrap();
+        let out_of_range = |x: &str| Err(format!("value {x} is out of range for DATE"));
+        for (sql, expected) in [
+            (
+                "INSERT INTO d VALUES (CAST('0000-01-01' AS DATE) - 1, nil)",
+                out_of_range("DATE '0000-01-01' - 1"),
+            ),
+            (
+                "INSERT INTO d VALUES (CAST('9999-12-31' AS DATE) + 1, nil)",
+                out_of_range("DATE '9999-12-31' + 1"),
+            ),
+            (
+                "UPDATE d SET at = at + 1",
+                out_of_range("DATE '9999-12-31' + 1"),
+            ),
+        ] {
+            assert_eq!(db.execute(sql).map_err(|e| e.to_string()), expected);
+        }
+        let mut insert = db.prepare("INSERT INTO d VALUES (?, ?)").unwrap();
+        let bound = insert
+            .bind(1, Date(Date::MAX.0 + 1))
+            .unwrap()
+            .bind(2, Timestamp(i64::MAX))
+            .unwrap()
+            .execute(&mut db);
+        assert!(matches!(bound, Err(ExecutionError::ValueOutOfRange { .. })));
+        drop(db);
+
+        let mut db = Database::open(&path).unwrap();
+        let rs = db.query("SELECT at FROM d").unwrap();
+        assert_eq!(rs.rows, vec![vec![SqlValue::Date(Date::MAX)]]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn should_replay_the_values_of_updates() {
+        let path = std::env::temp_dir().join(format!("db_sql_{}.sql", uuid::Uuid::new_v4()));
+        let mut db = Database::open(&path).unwrap();
+        db.execute("CREATE TABLE t (id INT, u UUID)").unwrap();
+        for id in 1..=4 {
+            db.execute(&format!("INSERT INTO t VALUES ({id}, nil)"))
+                .unwrap();
+        }
+        db.execute("DELETE t WHERE id = 1").unwrap();
+        drop(db);
+
+        // the rows are renumbered by the compaction, but keep their positions
+        let mut db = Database::open(&path).unwrap();
+        db.execute("INSERT INTO t VALUES (5, nil)").unwrap();
+        db.execute("UPDATE t SET u = gen_random_uuid() WHERE id > 2")
+            .unwrap();
+        db.execute("UPDATE t SET id = id * 10").unwrap();
+        let before = db.query("SELECT id, u FROM t").unwrap().rows;
+        drop(db);
+        let journal = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            journal.contains("UPDATE t SET id = 20 AT ROW 0;"),
+            "{journal}"
+        );
+
+        let mut db = Database::open(&path).unwrap();
+        assert_eq!(db.query("SELECT id, u FROM t").unwrap().rows, before);
+        assert_eq!(before[0], vec![SqlValue::Integer(20), SqlValue::Nil]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
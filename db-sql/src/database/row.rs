@@ -0,0 +1,201 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::ResultSet,
+    parser::SqlValue,
+    types::{Date, Decimal, Json, Timestamp, Uuid},
+};
+
+/// A row of a result set, which reads its values as Rust types
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResultRow<'a> {
+    columns: &'a [String],
+    values: &'a [SqlValue],
+}
+
+impl<'a> ResultRow<'a> {
+    pub fn values(&self) -> &'a [SqlValue] {
+        self.values
+    }
+    pub fn get<T: FromSqlValue>(&self, column: impl ColumnIndex) -> DbResult<T, ExecutionError> {
+        let idx = column
+            .index(self.columns)
+            .ok_or_else(|| ExecutionError::ColumnNotFound {
+                table: String::new(),
+                column: column.to_string(),
+            })?;
+        T::from_sql_value(&self.values[idx])
+    }
+}
+
+impl ResultSet {
+    pub fn iter(&self) -> impl Iterator<Item = ResultRow<'_>> {
+        self.rows.iter().map(|values| ResultRow {
+            columns: &self.columns,
+            values,
+        })
+    }
+}
+
+/// Finds a column of a row either by its position or by its name
+pub trait ColumnIndex: ToString {
+    fn index(&self, columns: &[String]) -> Option<usize>;
+}
+impl ColumnIndex for usize {
+    fn index(&self, columns: &[String]) -> Option<usize> {
+        (*self < columns.len()).then_some(*self)
+    }
+}
+impl ColumnIndex for &str {
+    fn index(&self, columns: &[String]) -> Option<usize> {
+        columns.iter().position(|x| x == self)
+    }
+}
+
+/// Converts the stored values to Rust types
+pub trait FromSqlValue: Sized {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError>;
+}
+
+fn invalid<T>(value: &SqlValue) -> DbResult<T, ExecutionError> {
+    Err(ExecutionError::TypeMismatch {
+        expected: std::any::type_name::<T>().to_string(),
+        found: value.type_name().to_string(),
+    })
+}
+
+impl FromSqlValue for SqlValue {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        Ok(value.clone())
+    }
+}
+impl FromSqlValue for i32 {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        match value {
+            SqlValue::Integer(x) => {
+                i32::try_from(*x).map_err(|_| ExecutionError::ValueOutOfRange {
+                    data_type: "i32".into(),
+                    value: x.to_string(),
+                })
+            }
+            _ => invalid(value),
+        }
+    }
+}
+impl FromSqlValue for i64 {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        match value {
+            SqlValue::Integer(x) => Ok(*x),
+            _ => invalid(value),
+        }
+    }
+}
+impl FromSqlValue for f64 {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        match value {
+            SqlValue::Integer(x) => Ok(*x as f64),
+            SqlValue::Float(x) => Ok(*x),
+            SqlValue::Decimal(x) => Ok(x.to_f64()),
+            _ => invalid(value),
+        }
+    }
+}
+impl FromSqlValue for Decimal {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        match value {
+            SqlValue::Integer(x) => Ok(Decimal::from(*x)),
+            SqlValue::Decimal(x) => Ok(*x),
+            _ => invalid(value),
+        }
+    }
+}
+impl FromSqlValue for Date {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        match value {
+            SqlValue::Date(x) => Ok(*x),
+            _ => invalid(value),
+        }
+    }
+}
+impl FromSqlValue for Timestamp {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        match value {
+            SqlValue::Date(x) => Ok(x.to_timestamp()),
+            SqlValue::Timestamp(x) => Ok(*x),
+            _ => invalid(value),
+        }
+    }
+}
+impl FromSqlValue for Uuid {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        match value {
+            SqlValue::Uuid(x) => Ok(*x),
+            _ => invalid(value),
+        }
+    }
+}
+impl FromSqlValue for Json {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        match value {
+            SqlValue::Json(x) => Ok(x.clone()),
+            _ => invalid(value),
+        }
+    }
+}
+impl FromSqlValue for Vec<u8> {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        match value {
+            SqlValue::Blob(x) => Ok(x.clone()),
+            _ => invalid(value),
+        }
+    }
+}
+impl FromSqlValue for bool {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        match value {
+            SqlValue::Boolean(x) => Ok(*x),
+            _ => invalid(value),
+        }
+    }
+}
+impl FromSqlValue for String {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        match value {
+            SqlValue::String(x) => Ok(x.clone()),
+            _ => invalid(value),
+        }
+    }
+}
+impl<T: FromSqlValue> FromSqlValue for Option<T> {
+    fn from_sql_value(value: &SqlValue) -> DbResult<Self, ExecutionError> {
+        match value {
+            SqlValue::Nil => Ok(None),
+            _ => T::from_sql_value(value).map(Some),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_typed_values() {
+        let rs = ResultSet {
+            columns: vec!["id".into(), "name".into(), "score".into()],
+            rows: vec![vec![
+                SqlValue::Integer(7),
+                SqlValue::Nil,
+                SqlValue::Float(0.5),
+            ]],
+        };
+        let row = rs.iter().next().unwrap();
+        assert_eq!(row.get::<i32>("id"), Ok(7));
+        assert_eq!(row.get::<Option<String>>("name"), Ok(None));
+        assert_eq!(row.get::<f64>(2), Ok(0.5));
+        assert!(row.get::<String>("id").is_err());
+        assert_eq!(
+            row.get::<i32>("nope").unwrap_err().to_string(),
+            "no such column: nope"
+        );
+    }
+}
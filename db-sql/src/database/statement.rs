@@ -0,0 +1,153 @@
+use crate::{
+    database::Database,
+    errors::{DbResult, ExecutionError},
+    exec::{ExecutionResult, ResultSet},
+    parser::{SqlStatement, SqlValue},
+    types::{Date, Decimal, Json, Timestamp, Uuid},
+};
+
+/// A parsed statement whose `?` parameters are bound before each execution
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    stmt: SqlStatement,
+    params: Vec<Option<SqlValue>>,
+}
+
+impl Statement {
+    pub fn new(sql: &str) -> DbResult<Self, ExecutionError> {
+        let stmt = SqlStatement::try_from(sql).map_err(ExecutionError::ParserError)?;
+        let params = vec![None; stmt.placeholder_count()];
+        Ok(Self { stmt, params })
+    }
+    pub fn parameter_count(&self) -> usize {
+        self.params.len()
+    }
+    /// Binds the value to the parameter at the index, which starts from 1
+    pub fn bind(
+        &mut self,
+        index: usize,
+        value: impl Into<SqlValue>,
+    ) -> DbResult<&mut Self, ExecutionError> {
+        let param = index
+            .checked_sub(1)
+            .and_then(|i| self.params.get_mut(i))
+            .ok_or(ExecutionError::InvalidParameter(index))?;
+        *param = Some(value.into());
+        Ok(self)
+    }
+    pub fn clear_bindings(&mut self) {
+        self.params.iter_mut().for_each(|x| *x = None);
+    }
+    pub fn execute(&self, db: &mut Database) -> DbResult<ExecutionResult, ExecutionError> {
+        let params = self
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, x)| x.clone().ok_or(ExecutionError::UnboundParameter(i + 1)))
+            .collect::<Result<Vec<_>, _>>()?;
+        db.context_mut().execute(&self.stmt.bind_values(&params))
+    }
+    /// Executes the statement and returns the rows, which are empty for statements
+    /// other than queries.
+    pub fn query(&self, db: &mut Database) -> DbResult<ResultSet, ExecutionError> {
+        match self.execute(db)? {
+            ExecutionResult::Select(rs) => Ok(rs),
+            _ => Ok(ResultSet::default()),
+        }
+    }
+}
+
+impl From<i32> for SqlValue {
+    fn from(value: i32) -> Self {
+        SqlValue::Integer(value as i64)
+    }
+}
+impl From<i64> for SqlValue {
+    fn from(value: i64) -> Self {
+        SqlValue::Integer(value)
+    }
+}
+impl From<f64> for SqlValue {
+    fn from(value: f64) -> Self {
+        SqlValue::Float(value)
+    }
+}
+impl From<bool> for SqlValue {
+    fn from(value: bool) -> Self {
+        SqlValue::Boolean(value)
+    }
+}
+impl From<&str> for SqlValue {
+    fn from(value: &str) -> Self {
+        SqlValue::String(value.to_string())
+    }
+}
+impl From<String> for SqlValue {
+    fn from(value: String) -> Self {
+        SqlValue::String(value)
+    }
+}
+impl From<Decimal> for SqlValue {
+    fn from(value: Decimal) -> Self {
+        SqlValue::Decimal(value)
+    }
+}
+impl From<Date> for SqlValue {
+    fn from(value: Date) -> Self {
+        SqlValue::Date(value)
+    }
+}
+impl From<Timestamp> for SqlValue {
+    fn from(value: Timestamp) -> Self {
+        SqlValue::Timestamp(value)
+    }
+}
+impl From<Uuid> for SqlValue {
+    fn from(value: Uuid) -> Self {
+        SqlValue::Uuid(value)
+    }
+}
+impl From<Json> for SqlValue {
+    fn from(value: Json) -> Self {
+        SqlValue::Json(value)
+    }
+}
+impl From<Vec<u8>> for SqlValue {
+    fn from(value: Vec<u8>) -> Self {
+        SqlValue::Blob(value)
+    }
+}
+impl<T: Into<SqlValue>> From<Option<T>> for SqlValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(SqlValue::Nil, Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_bind_parameters() {
+        let mut db = Database::open_in_memory();
+        db.execute("CREATE TABLE users (id INT, name STRING)")
+            .unwrap();
+        let mut insert = db.prepare("INSERT INTO users VALUES (?, ?)").unwrap();
+        assert_eq!(insert.parameter_count(), 2);
+        insert.bind(1, 1).unwrap().bind(2, "alice").unwrap();
+        insert.execute(&mut db).unwrap();
+        insert.bind(1, 2).unwrap().bind(2, None::<&str>).unwrap();
+        insert.execute(&mut db).unwrap();
+
+        let mut query = db.prepare("SELECT name FROM users WHERE id = ?").unwrap();
+        assert_eq!(query.bind(3, 1), Err(ExecutionError::InvalidParameter(3)));
+        assert_eq!(
+            query.query(&mut db),
+            Err(ExecutionError::UnboundParameter(1))
+        );
+
+        let rows = query.bind(1, 1).unwrap().query(&mut db).unwrap();
+        assert_eq!(rows.columns, vec!["name".to_string()]);
+        assert_eq!(rows.rows, vec![vec![SqlValue::String("alice".into())]]);
+    }
+}
@@ -1,11 +1,150 @@
-use std::error::Error;
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+};
 
 pub type DbError = Box<dyn Error>;
 pub type DbResult<T = (), E = DbError> = Result<T, E>;
 
+/// The errors of parsing and executing the statements
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExecutionError {
-    TableNotFound,
-    TableAlreadyExists,
+    TableNotFound(String),
+    TableAlreadyExists(String),
+    ViewNotFound(String),
+    ViewAlreadyExists(String),
+    TriggerNotFound(String),
+    TriggerAlreadyExists(String),
+    IndexNotFound(String),
+    IndexAlreadyExists(String),
+    UserNotFound(String),
+    UserAlreadyExists(String),
+    /// the user name or the password is wrong, which of them is not told
+    AuthenticationFailed(String),
+    /// the current user lacks the privilege, e.g. `SELECT on users`
+    PermissionDenied {
+        user: String,
+        action: String,
+    },
+    ColumnNotFound {
+        table: String,
+        column: String,
+    },
+    /// the number of the values does not match the columns of the table
+    ColumnCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// a value of the `found` type was given where `expected` was required
+    TypeMismatch {
+        expected: String,
+        found: String,
+    },
+    /// a row breaks a constraint, e.g. `NOT NULL`, of a column
+    ConstraintViolation {
+        table: String,
+        column: String,
+        constraint: String,
+    },
+    /// the value does not fit the type, e.g. a too long string for a `VARCHAR(n)`
+    ValueOutOfRange {
+        data_type: String,
+        value: String,
+    },
+    /// the text cannot be read as a value of the type
+    InvalidValue {
+        data_type: String,
+        value: String,
+    },
+    DivisionByZero,
+    UnknownFunction(String),
+    /// a function was called with the wrong number of arguments
+    ArgumentCountMismatch {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
     ParserError(String),
+    /// the statement is well formed but cannot apply, e.g. to the table it names
+    InvalidStatement(String),
+    IoError(String),
+    /// a row of an imported file is invalid, the line starts from 1
+    ImportError {
+        line: usize,
+        reason: String,
+    },
+    /// the parameter of a prepared statement, starting from 1, has no value
+    UnboundParameter(usize),
+    /// the prepared statement has no parameter at the index
+    InvalidParameter(usize),
+    /// a statement would change a database opened read-only
+    ReadOnly,
+}
+
+impl Display for ExecutionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutionError::TableNotFound(x) => write!(f, "no such table: {x}"),
+            ExecutionError::TableAlreadyExists(x) => write!(f, "table {x} already exists"),
+            ExecutionError::ViewNotFound(x) => write!(f, "no such view: {x}"),
+            ExecutionError::ViewAlreadyExists(x) => write!(f, "view {x} already exists"),
+            ExecutionError::TriggerNotFound(x) => write!(f, "no such trigger: {x}"),
+            ExecutionError::TriggerAlreadyExists(x) => write!(f, "trigger {x} already exists"),
+            ExecutionError::IndexNotFound(x) => write!(f, "no such index: {x}"),
+            ExecutionError::IndexAlreadyExists(x) => write!(f, "index {x} already exists"),
+            ExecutionError::UserNotFound(x) => write!(f, "no such user: {x}"),
+            ExecutionError::UserAlreadyExists(x) => write!(f, "user {x} already exists"),
+            ExecutionError::AuthenticationFailed(x) => {
+                write!(f, "password authentication failed for user {x}")
+            }
+            ExecutionError::PermissionDenied { user, action } => {
+                write!(f, "permission denied for user {user}: {action}")
+            }
+            ExecutionError::ColumnNotFound { table, column } if table.is_empty() => {
+                write!(f, "no such column: {column}")
+            }
+            ExecutionError::ColumnNotFound { table, column } => {
+                write!(f, "no such column: {table}.{column}")
+            }
+            ExecutionError::ColumnCountMismatch { expected, found } => {
+                write!(f, "expected {expected} values but got {found}")
+            }
+            ExecutionError::TypeMismatch { expected, found } => {
+                write!(f, "type mismatch: expected {expected} but found {found}")
+            }
+            ExecutionError::ConstraintViolation {
+                table,
+                column,
+                constraint,
+            } => write!(f, "{constraint} constraint failed: {table}.{column}"),
+            ExecutionError::ValueOutOfRange { data_type, value } => {
+                write!(f, "value {value} is out of range for {data_type}")
+            }
+            ExecutionError::InvalidValue { data_type, value } => {
+                write!(f, "invalid value for {data_type}: '{value}'")
+            }
+            ExecutionError::DivisionByZero => write!(f, "division by zero"),
+            ExecutionError::UnknownFunction(x) => write!(f, "no such function: {x}"),
+            ExecutionError::ArgumentCountMismatch {
+                function,
+                expected,
+                found,
+            } => write!(f, "{function}() takes {expected} arguments but got {found}"),
+            ExecutionError::ParserError(x) => write!(f, "parser error: {x}"),
+            ExecutionError::InvalidStatement(x) => write!(f, "{x}"),
+            ExecutionError::IoError(x) => write!(f, "io error: {x}"),
+            ExecutionError::ImportError { line, reason } => write!(f, "line {line}: {reason}"),
+            ExecutionError::UnboundParameter(x) => write!(f, "parameter {x} is not bound"),
+            ExecutionError::InvalidParameter(x) => write!(f, "there is no parameter {x}"),
+            ExecutionError::ReadOnly => write!(f, "attempt to write a read-only database"),
+        }
+    }
+}
+
+impl Error for ExecutionError {}
+
+impl From<std::io::Error> for ExecutionError {
+    fn from(value: std::io::Error) -> Self {
+        ExecutionError::IoError(value.to_string())
+    }
 }
@@ -0,0 +1,583 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::{BTreeIndex, ExecutionContext, Table, fulltext::text_search},
+    parser::{Condition, Expr, Identifier, LogicalOperator, Operator, SqlValue},
+};
+use std::{borrow::Cow, cmp::Ordering};
+
+/// How many buckets the histogram of a column has at most
+const HISTOGRAM_BUCKETS: usize = 16;
+/// The fraction of the rows an equality is assumed to match without statistics
+const DEFAULT_EQUAL: f64 = 0.1;
+/// The same for the other comparisons and the conditions that can't be estimated
+const DEFAULT_RANGE: f64 = 1.0 / 3.0;
+/// How many rows of a scan reading a row through an index costs as much as
+pub(super) const INDEX_ROW_COST: f64 = 4.0;
+
+/// The statistics of the columns of a table, collected by `ANALYZE`. They are a
+/// snapshot, the later changes are only seen by the next `ANALYZE`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableStatistics {
+    pub rows: usize,
+    /// in the order of the table columns
+    pub columns: Vec<ColumnStatistics>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStatistics {
+    pub distinct: usize,
+    pub nulls: usize,
+    /// nil when the column has only nil values
+    pub min: SqlValue,
+    pub max: SqlValue,
+    /// the bounds of buckets holding about as many values each, from the smallest
+    /// value to the largest
+    pub histogram: Vec<SqlValue>,
+}
+
+impl TableStatistics {
+    pub fn collect(tbl: &Table) -> Self {
+        let columns = (0..tbl.columns().len())
+            .map(|i| {
+                let mut values: Vec<_> = tbl
+                    .iter()
+                    .map(|x| x.values[i].clone())
+                    .filter(|x| *x != SqlValue::Nil)
+                    .collect();
+                values.sort_by(|a, b| a.compare(b).unwrap_or(Ordering::Equal));
+                ColumnStatistics::new(values, tbl.len())
+            })
+            .collect();
+        Self {
+            rows: tbl.len(),
+            columns,
+        }
+    }
+}
+
+impl ColumnStatistics {
+    /// The statistics of the sorted values which are not nil, of `rows` rows
+    fn new(values: Vec<SqlValue>, rows: usize) -> Self {
+        let distinct = match values.len() {
+            0 => 0,
+            _ => {
+                1 + values
+                    .windows(2)
+                    .filter(|x| x[0].compare(&x[1]) != Some(Ordering::Equal))
+                    .count()
+            }
+        };
+        let buckets = HISTOGRAM_BUCKETS.min(values.len().saturating_sub(1)).max(1);
+        let histogram = match values.len() {
+            0 => vec![],
+            n => (0..=buckets)
+                .map(|j| values[j * (n - 1) / buckets].clone())
+                .collect(),
+        };
+        Self {
+            distinct,
+            nulls: rows - values.len(),
+            min: values.first().cloned().unwrap_or(SqlValue::Nil),
+            max: values.last().cloned().unwrap_or(SqlValue::Nil),
+            histogram,
+        }
+    }
+    /// The fraction of the `rows` rows for which `column operator value` holds
+    pub fn selectivity(&self, rows: usize, operator: Operator, value: &SqlValue) -> f64 {
+        if rows == 0 || self.distinct == 0 || *value == SqlValue::Nil {
+            return 0.0;
+        }
+        let present = (rows - self.nulls) as f64 / rows as f64;
+        let outside = value.compare(&self.min) == Some(Ordering::Less)
+            || value.compare(&self.max) == Some(Ordering::Greater);
+        let equal = if outside {
+            0.0
+        } else {
+            present / self.distinct as f64
+        };
+        let below = present * self.fraction_below(value);
+        let fraction = match operator {
+            Operator::Equal => equal,
+            Operator::NotEqual => present - equal,
+            Operator::LessThan => below,
+            Operator::LessThanOrEqual => below + equal,
+            Operator::GreaterThan => present - below - equal,
+            Operator::GreaterThanOrEqual => present - below,
+        };
+        fraction.clamp(0.0, 1.0)
+    }
+    /// The fraction of the values smaller than the value, read from the histogram
+    fn fraction_below(&self, value: &SqlValue) -> f64 {
+        let (Some(first), Some(last)) = (self.histogram.first(), self.histogram.last()) else {
+            return 0.0;
+        };
+        if value.compare(first) != Some(Ordering::Greater) {
+            return 0.0;
+        }
+        if value.compare(last) == Some(Ordering::Greater) {
+            return 1.0;
+        }
+        let buckets = self.histogram.len() - 1;
+        let j = self
+            .histogram
+            .windows(2)
+            .position(|x| value.compare(&x[1]) != Some(Ordering::Greater))
+            .unwrap_or(buckets - 1);
+        let (low, high) = (&self.histogram[j], &self.histogram[j + 1]);
+        // numbers are interpolated within their bucket, other values take its middle
+        let within = match (number(low), number(high), number(value)) {
+            (Some(low), Some(high), Some(x)) if high > low => (x - low) / (high - low),
+            _ => 0.5,
+        };
+        (j as f64 + within) / buckets as f64
+    }
+}
+
+fn number(value: &SqlValue) -> Option<f64> {
+    match value {
+        SqlValue::Integer(x) => Some(*x as f64),
+        SqlValue::Float(x) => Some(*x),
+        SqlValue::Decimal(x) => Some(x.to_f64()),
+        _ => None,
+    }
+}
+
+impl ExecutionContext {
+    /// Collects the statistics of the table, or of all of them without one
+    pub(super) fn analyze(&mut self, table: Option<&Identifier>) -> DbResult<(), ExecutionError> {
+        let names: Vec<Identifier> = match table {
+            Some(name) => vec![self.get_table(name)?.name().clone()],
+            None => self.table_names().into_iter().cloned().collect(),
+        };
+        for name in names {
+            let tbl = self.get_table_mut(&name)?;
+            let statistics = TableStatistics::collect(tbl);
+            tbl.set_statistics(statistics);
+        }
+        Ok(())
+    }
+    /// The estimated fraction of the rows of the table the condition matches. The
+    /// comparisons of a column to a literal are estimated from the statistics of
+    /// an analyzed table, the other conditions take default fractions.
+    pub fn selectivity(&self, table: &str, condition: &Condition) -> f64 {
+        let Some(tbl) = self.table(table) else {
+            return DEFAULT_RANGE;
+        };
+        selectivity(tbl, tbl.name(), condition)
+    }
+    /// The estimated number of rows of the table the condition matches
+    pub fn estimate_rows(&self, table: &str, condition: Option<&Condition>) -> usize {
+        let Some(tbl) = self.table(table) else {
+            return 0;
+        };
+        let rows = tbl.statistics().map_or(tbl.len(), |x| x.rows);
+        let fraction = condition.map_or(1.0, |x| self.selectivity(table, x));
+        (rows as f64 * fraction).round() as usize
+    }
+    /// The B-tree index to read the rows of the table through instead of a scan, with
+    /// the comparison of its column to a literal among the conditions it answers.
+    /// The comparison estimated to match the fewest rows is taken, if reading them
+    /// through the index costs less than scanning the table.
+    pub(super) fn choose_index<'a>(
+        &self,
+        tbl: &'a Table,
+        alias: &Identifier,
+        conditions: &[&'a Condition],
+    ) -> Option<(&'a BTreeIndex, Operator, &'a SqlValue)> {
+        let rows = tbl.statistics().map_or(tbl.len(), |x| x.rows) as f64;
+        conditions
+            .iter()
+            .filter_map(|x| {
+                let (column, operator, value) = comparison(tbl, alias, x)?;
+                let index = tbl.btree_index(column)?;
+                Some((selectivity(tbl, alias, x), (index, operator, value)))
+            })
+            .filter(|(fraction, _)| rows * fraction * INDEX_ROW_COST < rows)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|x| x.1)
+    }
+    /// Reorders the conditions joined by `AND` at the top of a `WHERE` of an analyzed
+    /// table so the comparisons estimated to match the fewest rows are checked first.
+    /// Only the comparisons of its columns to literals move, they can't fail.
+    pub(crate) fn plan<'a>(
+        &self,
+        table: &Identifier,
+        alias: &Identifier,
+        condition: &'a Condition,
+    ) -> Cow<'a, Condition> {
+        let Condition::Logical {
+            operator: LogicalOperator::And,
+            conditions,
+        } = condition
+        else {
+            return Cow::Borrowed(condition);
+        };
+        let hidden = self.ctes.borrow().iter().any(|x| &x.0 == table);
+        let Some(tbl) = self
+            .table(&table.0)
+            .filter(|x| !hidden && x.statistics().is_some())
+        else {
+            return Cow::Borrowed(condition);
+        };
+        let (mut first, rest): (Vec<_>, Vec<_>) = conditions
+            .iter()
+            .partition(|x| comparison(tbl, alias, x).is_some());
+        first.sort_by(|a, b| selectivity(tbl, alias, a).total_cmp(&selectivity(tbl, alias, b)));
+        Cow::Owned(Condition::Logical {
+            operator: LogicalOperator::And,
+            conditions: first.into_iter().chain(rest).cloned().collect(),
+        })
+    }
+}
+
+/// The estimated number of rows of the table all the conditions match
+pub(super) fn estimate_rows(tbl: &Table, alias: &Identifier, conditions: &[&Condition]) -> f64 {
+    let rows = tbl.statistics().map_or(tbl.len(), |x| x.rows) as f64;
+    conditions
+        .iter()
+        .fold(rows, |rows, x| rows * selectivity(tbl, alias, x))
+}
+
+/// The estimated number of distinct values of the column, taken as many as the
+/// rows, like a key, without statistics
+pub(super) fn distinct_values(tbl: &Table, column: usize) -> f64 {
+    let distinct = match tbl.statistics() {
+        Some(statistics) => statistics.columns[column].distinct,
+        None => tbl.len(),
+    };
+    distinct.max(1) as f64
+}
+
+/// An equality between the columns of two tables of a join, with the fraction of
+/// the pairs of their rows it is estimated to keep
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct JoinEdge {
+    pub tables: (usize, usize),
+    pub selectivity: f64,
+}
+
+/// The order in which to join the tables of the estimated numbers of rows. It is
+/// built greedily from the smallest table, adding the one which keeps the fewest
+/// joined rows each time, so the tables the equalities narrow the most come first
+/// and a cross join comes last.
+pub(super) fn join_order(rows: &[f64], edges: &[JoinEdge]) -> Vec<usize> {
+    let mut order: Vec<usize> = vec![];
+    let mut joined = 1.0;
+    while order.len() < rows.len() {
+        let estimate = |next: usize| {
+            let fraction: f64 = edges
+                .iter()
+                .filter(|x| {
+                    let (a, b) = x.tables;
+                    (a == next && order.contains(&b)) || (b == next && order.contains(&a))
+                })
+                .map(|x| x.selectivity)
+                .product();
+            joined * rows[next] * fraction
+        };
+        let next = (0..rows.len())
+            .filter(|x| !order.contains(x))
+            .min_by(|a, b| estimate(*a).total_cmp(&estimate(*b)))
+            .unwrap_or_default();
+        joined = estimate(next);
+        order.push(next);
+    }
+    order
+}
+
+/// The position of the column and the operator and literal of a comparison of a
+/// column of the table, whose name or alias is `alias`, to a literal
+fn comparison<'a>(
+    tbl: &Table,
+    alias: &Identifier,
+    condition: &'a Condition,
+) -> Option<(usize, Operator, &'a SqlValue)> {
+    let Condition::Comparison {
+        left,
+        operator,
+        right,
+    } = condition
+    else {
+        return None;
+    };
+    let (column, operator, value) = match (left, right) {
+        (Expr::Column(column), Expr::Literal(value)) => (column, *operator, value),
+        (Expr::Literal(value), Expr::Column(column)) => {
+            let flipped = match operator {
+                Operator::LessThan => Operator::GreaterThan,
+                Operator::LessThanOrEqual => Operator::GreaterThanOrEqual,
+                Operator::GreaterThan => Operator::LessThan,
+                Operator::GreaterThanOrEqual => Operator::LessThanOrEqual,
+                x => *x,
+            };
+            (column, flipped, value)
+        }
+        _ => return None,
+    };
+    if column.qualifier().is_some_and(|x| x != alias.0) {
+        return None;
+    }
+    Some((tbl.column_index(column.column())?, operator, value))
+}
+
+fn selectivity(tbl: &Table, alias: &Identifier, condition: &Condition) -> f64 {
+    match condition {
+        Condition::Logical {
+            operator,
+            conditions,
+        } => {
+            let fractions = conditions.iter().map(|x| selectivity(tbl, alias, x));
+            match operator {
+                LogicalOperator::And => fractions.product(),
+                LogicalOperator::Or => fractions.fold(0.0, |a, b| a + b - a * b),
+            }
+        }
+        Condition::Comparison { operator, .. } => {
+            let statistics = tbl.statistics();
+            match (comparison(tbl, alias, condition), statistics) {
+                (Some((i, operator, value)), Some(statistics)) => {
+                    statistics.columns[i].selectivity(statistics.rows, operator, value)
+                }
+                _ if *operator == Operator::Equal => DEFAULT_EQUAL,
+                _ => DEFAULT_RANGE,
+            }
+        }
+        Condition::In { .. } | Condition::Exists { .. } => DEFAULT_RANGE,
+        // the rows of an indexed column are counted from the index
+        Condition::Match { expr, query } => match text_search(tbl, alias, expr, query) {
+            Some((index, query)) if !tbl.is_empty() => {
+                index.search(query).len() as f64 / tbl.len() as f64
+            }
+            _ => DEFAULT_EQUAL,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        exec::{
+            ExecutionResult,
+            test_util::{context_with, rows},
+        },
+        parser::SqlStatement,
+    };
+
+    /// 1001 orders, one in a hundred open and the last one without a status, whose
+    /// totals go from 0 to 499 twice
+    fn orders() -> ExecutionContext {
+        let mut ctx =
+            context_with(&["CREATE TABLE orders (id INT PRIMARY KEY, status STRING, total INT)"]);
+        for i in 0..1000 {
+            let status = if i % 100 == 0 { "open" } else { "closed" };
+            ctx.run(&format!(
+                "INSERT INTO orders VALUES ({i}, '{status}', {})",
+                i % 500
+            ))
+            .unwrap();
+        }
+        ctx.run("INSERT INTO orders (id) VALUES (1000)").unwrap();
+        ctx
+    }
+    fn analyzed() -> ExecutionContext {
+        let mut ctx = orders();
+        assert_eq!(ctx.run("ANALYZE orders"), Ok(ExecutionResult::Analyze));
+        ctx
+    }
+    fn condition(sql: &str) -> Condition {
+        let SqlStatement::Delete { condition, .. } =
+            SqlStatement::try_from(format!("DELETE orders WHERE {sql}").as_str()).unwrap()
+        else {
+            panic!("expected a DELETE");
+        };
+        condition.unwrap()
+    }
+
+    #[test]
+    fn should_take_defaults_without_statistics() {
+        let ctx = orders();
+        assert_eq!(ctx.estimate_rows("orders", Some(&condition("id = 5"))), 100);
+    }
+
+    #[test]
+    fn should_collect_statistics() {
+        let ctx = analyzed();
+        let stats = ctx.table("orders").unwrap().statistics().unwrap();
+        assert_eq!(stats.rows, 1001);
+        assert_eq!(stats.columns[1].distinct, 2);
+        assert_eq!(stats.columns[1].nulls, 1);
+        assert_eq!(stats.columns[2].max, SqlValue::Integer(499));
+        assert_eq!(stats.columns[0].histogram.len(), HISTOGRAM_BUCKETS + 1);
+    }
+
+    #[test]
+    fn should_estimate_equalities() {
+        let ctx = analyzed();
+        assert_eq!(ctx.estimate_rows("orders", Some(&condition("id = 5"))), 1);
+        assert_eq!(
+            ctx.estimate_rows("orders", Some(&condition("id = 5000"))),
+            0
+        );
+    }
+
+    #[test]
+    fn should_estimate_ranges_from_the_histogram() {
+        let ctx = analyzed();
+        assert_eq!(
+            ctx.estimate_rows("orders", Some(&condition("id < 250"))),
+            250
+        );
+        assert_eq!(
+            ctx.estimate_rows("orders", Some(&condition("250 <= total"))),
+            498
+        );
+    }
+
+    #[test]
+    fn should_estimate_logical_conditions() {
+        let ctx = analyzed();
+        assert_eq!(
+            ctx.estimate_rows("orders", Some(&condition("status != 'open' OR id = 1"))),
+            501
+        );
+    }
+
+    #[test]
+    fn should_check_the_most_selective_comparison_first() {
+        let ctx = analyzed();
+        let where_ = condition("o.total > 10 AND id IN (SELECT id FROM orders) AND o.id = 3");
+        let planned = ctx.plan(&"orders".into(), &"o".into(), &where_);
+        assert_eq!(
+            planned.to_string(),
+            "o.id = 3 AND o.total > 10 AND id IN (SELECT id FROM orders)"
+        );
+    }
+
+    #[test]
+    fn should_query_the_column_statistics() {
+        let mut ctx = analyzed();
+        let string = |x: &str| SqlValue::String(x.into());
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT table_name, column_name, distinct_values, nulls FROM db_column_stats WHERE column_name = 'status'"
+            ),
+            Ok(vec![vec![
+                string("orders"),
+                string("status"),
+                SqlValue::Integer(2),
+                SqlValue::Integer(1)
+            ]])
+        );
+    }
+
+    /// Same as `orders` with B-tree indexes of the status and the total
+    fn indexed() -> ExecutionContext {
+        let mut ctx = orders();
+        ctx.run("CREATE INDEX orders_status ON orders USING btree(status)")
+            .unwrap();
+        ctx.run("CREATE INDEX orders_total ON orders USING btree(total)")
+            .unwrap();
+        ctx
+    }
+    fn chosen_index(ctx: &ExecutionContext, sql: &str) -> Option<String> {
+        let tbl = ctx.table("orders").unwrap();
+        let condition = condition(sql);
+        let index = ctx.choose_index(tbl, tbl.name(), &condition.conjuncts());
+        index.map(|x| x.0.name().0.clone())
+    }
+
+    #[test]
+    fn should_choose_an_index_from_the_statistics() {
+        let mut ctx = indexed();
+        let status = Some("orders_status".to_string());
+        let total = Some("orders_total".to_string());
+        // an equality is taken to be selective without statistics
+        assert_eq!(chosen_index(&ctx, "status = 'closed'"), status);
+        // the statistics tell there are two statuses, of about half the rows each
+        ctx.run("ANALYZE orders").unwrap();
+        assert_eq!(chosen_index(&ctx, "status = 'closed'"), None);
+        assert_eq!(chosen_index(&ctx, "status = 'open'"), None);
+        assert_eq!(chosen_index(&ctx, "total = 10"), total);
+        assert_eq!(chosen_index(&ctx, "total > 10"), None);
+        assert_eq!(chosen_index(&ctx, "10 > total"), total);
+        assert_eq!(chosen_index(&ctx, "total < 10 AND status = 'open'"), total);
+        assert_eq!(chosen_index(&ctx, "total < 10 OR status = 'open'"), None);
+    }
+
+    #[test]
+    fn should_read_the_rows_through_the_chosen_index() {
+        let mut ctx = indexed();
+        ctx.run("ANALYZE orders").unwrap();
+        let int = SqlValue::Integer;
+        assert_eq!(
+            rows(&mut ctx, "SELECT id FROM orders WHERE total = 7"),
+            Ok(vec![vec![int(7)], vec![int(507)]])
+        );
+        assert_eq!(ctx.statistics().index_hits, 1);
+        assert_eq!(ctx.statistics().rows_scanned, 0);
+        let closed = rows(&mut ctx, "SELECT id FROM orders WHERE status = 'closed'").unwrap();
+        assert_eq!(closed.len(), 990);
+        assert_eq!(ctx.statistics().index_hits, 1);
+        assert_eq!(ctx.statistics().rows_scanned, 1001);
+    }
+
+    #[test]
+    fn should_order_joins_by_their_estimates() {
+        let edges = [
+            JoinEdge {
+                tables: (0, 1),
+                selectivity: 0.001,
+            },
+            JoinEdge {
+                tables: (1, 2),
+                selectivity: 0.5,
+            },
+        ];
+        // the smallest table first, then the one its equality narrows the most
+        assert_eq!(join_order(&[1000.0, 1000.0, 10.0], &edges), vec![2, 1, 0]);
+        assert_eq!(join_order(&[10.0, 2000.0, 100.0], &edges), vec![0, 1, 2]);
+        // a table joined by no equality comes last
+        assert_eq!(
+            join_order(&[1000.0, 2000.0, 100.0], &edges[..1]),
+            vec![2, 0, 1]
+        );
+        assert_eq!(join_order(&[5.0, 5.0], &[]), vec![0, 1]);
+    }
+
+    #[test]
+    fn should_look_joined_rows_up_in_an_index() {
+        let mut ctx = indexed();
+        for sql in [
+            "ANALYZE orders",
+            "CREATE TABLE limits (total INT, label STRING)",
+            "INSERT INTO limits VALUES (3, 'low')",
+            "INSERT INTO limits VALUES (400, 'high')",
+        ] {
+            ctx.run(sql).unwrap();
+        }
+        let query = "SELECT o.id FROM orders o JOIN limits l ON o.total = l.total \
+                     WHERE l.label = 'low'";
+        let int = SqlValue::Integer;
+        assert_eq!(
+            rows(&mut ctx, query),
+            Ok(vec![vec![int(3)], vec![int(503)]])
+        );
+        // only the small table is scanned, the orders are looked up
+        assert_eq!(ctx.statistics().rows_scanned, 2);
+        assert_eq!(ctx.statistics().index_hits, 1);
+
+        ctx.run("DROP INDEX orders_total").unwrap();
+        let mut scanned = rows(&mut ctx, query).unwrap();
+        scanned.sort_by(|a, b| a[0].compare(&b[0]).unwrap());
+        assert_eq!(scanned, vec![vec![int(3)], vec![int(503)]]);
+        assert_eq!(ctx.statistics().rows_scanned, 2 + 2 + 1001);
+    }
+
+    #[test]
+    fn should_reject_unknown_tables() {
+        let mut ctx = orders();
+        assert!(ctx.run("ANALYZE nope").is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use crate::parser::{Identifier, Operator, SqlValue};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    ops::Bound,
+};
+
+/// A value ordered like `compare` does, the values which can't be compared are
+/// ordered by their types so that they can be keys
+#[derive(Debug, Clone)]
+pub(crate) struct Key(pub SqlValue);
+
+impl Key {
+    fn rank(&self) -> u8 {
+        match self.0 {
+            SqlValue::Integer(_) | SqlValue::Float(_) | SqlValue::Decimal(_) => 0,
+            SqlValue::String(_) => 1,
+            SqlValue::Uuid(_) => 2,
+            SqlValue::Boolean(_) => 3,
+            SqlValue::Date(_) | SqlValue::Timestamp(_) => 4,
+            SqlValue::Blob(_) => 5,
+            SqlValue::Json(_) => 6,
+            SqlValue::Nil | SqlValue::Placeholder => 7,
+        }
+    }
+}
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank()
+            .cmp(&other.rank())
+            .then_with(|| self.0.compare(&other.0).unwrap_or(Ordering::Equal))
+    }
+}
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Key {}
+
+/// The rows of a column by their values in order, which answers the comparisons
+/// of the column to a value without reading the other rows. Nil is not indexed,
+/// no comparison matches it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BTreeIndex {
+    name: Identifier,
+    column: usize,
+    entries: BTreeMap<Key, BTreeSet<usize>>,
+}
+
+impl BTreeIndex {
+    pub fn new(name: Identifier, column: usize) -> Self {
+        Self {
+            name,
+            column,
+            entries: BTreeMap::new(),
+        }
+    }
+    pub fn name(&self) -> &Identifier {
+        &self.name
+    }
+    /// The position of the indexed column
+    pub fn column(&self) -> usize {
+        self.column
+    }
+    /// The number of distinct values
+    pub fn keys(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn insert(&mut self, id: usize, value: &SqlValue) {
+        if *value != SqlValue::Nil {
+            self.entries
+                .entry(Key(value.clone()))
+                .or_default()
+                .insert(id);
+        }
+    }
+    /// Removes the row, `value` is the one it was indexed with
+    pub fn remove(&mut self, id: usize, value: &SqlValue) {
+        let key = Key(value.clone());
+        if let Some(ids) = self.entries.get_mut(&key) {
+            ids.remove(&id);
+            if ids.is_empty() {
+                self.entries.remove(&key);
+            }
+        }
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+    /// The rows for which `column operator value` holds, in their order
+    pub fn lookup(&self, operator: Operator, value: &SqlValue) -> Vec<usize> {
+        if *value == SqlValue::Nil {
+            return vec![];
+        }
+        let key = Key(value.clone());
+        let (low, high) = match operator {
+            Operator::Equal => (Bound::Included(&key), Bound::Included(&key)),
+            Operator::LessThan => (Bound::Unbounded, Bound::Excluded(&key)),
+            Operator::LessThanOrEqual => (Bound::Unbounded, Bound::Included(&key)),
+            Operator::GreaterThan => (Bound::Excluded(&key), Bound::Unbounded),
+            Operator::GreaterThanOrEqual => (Bound::Included(&key), Bound::Unbounded),
+            Operator::NotEqual => (Bound::Unbounded, Bound::Unbounded),
+        };
+        let mut ids: Vec<usize> = self
+            .entries
+            .range((low, high))
+            // a comparison only holds between values of comparable types
+            .filter(|(x, _)| match operator {
+                Operator::NotEqual => x.0.compare(value).is_some_and(|x| x.is_ne()),
+                _ => x.0.compare(value).is_some(),
+            })
+            .flat_map(|x| x.1.iter().copied())
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_look_up_comparisons() {
+        let int = SqlValue::Integer;
+        let mut index = BTreeIndex::new("t_x".into(), 0);
+        for (id, x) in [(0, 5), (1, 3), (2, 5), (3, 8), (4, 1)] {
+            index.insert(id, &int(x));
+        }
+        index.insert(5, &SqlValue::Nil);
+        assert_eq!(index.keys(), 4);
+        assert_eq!(index.lookup(Operator::Equal, &int(5)), vec![0, 2]);
+        assert_eq!(index.lookup(Operator::LessThan, &int(5)), vec![1, 4]);
+        assert_eq!(
+            index.lookup(Operator::GreaterThanOrEqual, &int(5)),
+            vec![0, 2, 3]
+        );
+        assert_eq!(index.lookup(Operator::NotEqual, &int(5)), vec![1, 3, 4]);
+        assert_eq!(
+            index.lookup(Operator::Equal, &SqlValue::Float(3.0)),
+            vec![1]
+        );
+        assert!(index.lookup(Operator::Equal, &SqlValue::Nil).is_empty());
+        assert!(index.lookup(Operator::LessThan, &"x".into()).is_empty());
+
+        index.remove(0, &int(5));
+        assert_eq!(index.lookup(Operator::Equal, &int(5)), vec![2]);
+        index.remove(2, &int(5));
+        assert_eq!(index.keys(), 3);
+    }
+}
@@ -0,0 +1,320 @@
+use crate::{
+    exec::{ExecutionContext, ResultSet},
+    parser::{Identifier, SqlStatement, SqlValue},
+};
+
+/// The names of the system tables, which describe the schema and are read only
+const CATALOG_TABLES: &[&str] = &[
+    "db_tables",
+    "db_columns",
+    "db_constraints",
+    "db_indexes",
+    "db_stats",
+    "db_column_stats",
+];
+
+/// Whether the name is the one of a system table
+pub(crate) fn is_catalog(name: &Identifier) -> bool {
+    CATALOG_TABLES
+        .iter()
+        .any(|x| x.eq_ignore_ascii_case(&name.0))
+}
+
+impl ExecutionContext {
+    /// The rows of the system table of the name, computed from the current schema
+    pub(crate) fn catalog(&self, name: &Identifier) -> Option<ResultSet> {
+        let string = |x: &str| SqlValue::String(x.to_string());
+        let optional = |x: Option<String>| x.map_or(SqlValue::Nil, SqlValue::String);
+        let columns =
+            |names: &[&str]| ResultSet::new(names.iter().map(|x| x.to_string()).collect());
+
+        let rs = match name.0.to_lowercase().as_str() {
+            "db_tables" => {
+                let mut rs = columns(&["name", "type", "sql"]);
+                for name in self.table_names() {
+                    let Some(tbl) = self.table(&name.0) else {
+                        continue;
+                    };
+                    let create = SqlStatement::Create {
+                        table: name.clone(),
+                        columns: tbl.columns().clone(),
+                        storage: tbl.storage(),
+                    };
+                    rs.rows.push(vec![
+                        string(&name.0),
+                        string("table"),
+                        string(&create.to_string()),
+                    ]);
+                }
+                for name in self.view_names() {
+                    let Some(query) = self.view(&name.0) else {
+                        continue;
+                    };
+                    let create = SqlStatement::CreateView {
+                        name: name.clone(),
+                        query: Box::new(query.clone()),
+                    };
+                    rs.rows.push(vec![
+                        string(&name.0),
+                        string("view"),
+                        string(&create.to_string()),
+                    ]);
+                }
+                for trigger in &self.triggers {
+                    rs.rows.push(vec![
+                        string(&trigger.name.0),
+                        string("trigger"),
+                        string(&trigger.to_string()),
+                    ]);
+                }
+                rs
+            }
+            "db_columns" => {
+                let mut rs = columns(&[
+                    "table_name",
+                    "name",
+                    "position",
+                    "type",
+                    "nullable",
+                    "default",
+                    "auto_increment",
+                ]);
+                for name in self.table_names() {
+                    let Some(tbl) = self.table(&name.0) else {
+                        continue;
+                    };
+                    for (i, col) in tbl.columns().iter().enumerate() {
+                        rs.rows.push(vec![
+                            string(&name.0),
+                            string(&col.name.0),
+                            SqlValue::Integer(i as i64 + 1),
+                            string(&col.data_type.to_string()),
+                            SqlValue::Boolean(!col.is_not_null()),
+                            optional(col.default.as_ref().map(|x| x.to_string())),
+                            SqlValue::Boolean(col.auto_increment),
+                        ]);
+                    }
+                }
+                rs
+            }
+            "db_constraints" => {
+                let mut rs = columns(&[
+                    "table_name",
+                    "column_name",
+                    "type",
+                    "referenced_table",
+                    "referenced_column",
+                    "on_delete",
+                ]);
+                for name in self.table_names() {
+                    let Some(tbl) = self.table(&name.0) else {
+                        continue;
+                    };
+                    for col in tbl.columns() {
+                        if let Some(constraint) = &col.constraint {
+                            rs.rows.push(vec![
+                                string(&name.0),
+                                string(&col.name.0),
+                                string(constraint),
+                                SqlValue::Nil,
+                                SqlValue::Nil,
+                                SqlValue::Nil,
+                            ]);
+                        }
+                        if let Some(fk) = &col.references {
+                            rs.rows.push(vec![
+                                string(&name.0),
+                                string(&col.name.0),
+                                string("FOREIGN KEY"),
+                                string(&fk.table.0),
+                                string(&fk.column.0),
+                                string(&fk.on_delete.to_string()),
+                            ]);
+                        }
+                    }
+                }
+                rs
+            }
+            "db_indexes" => {
+                // the indexes made by `CREATE INDEX`, the unique columns are checked by
+                // a scan and listed in `db_constraints` only
+                let mut rs = columns(&["name", "table_name", "column_name", "type"]);
+                for name in self.table_names() {
+                    let Some(tbl) = self.table(&name.0) else {
+                        continue;
+                    };
+                    for index in tbl.index_statements() {
+                        if let SqlStatement::CreateIndex {
+                            name, column, kind, ..
+                        } = index
+                        {
+                            rs.rows.push(vec![
+                                string(&name.0),
+                                string(&tbl.name().0),
+                                string(&column.0),
+                                string(&kind.to_string()),
+                            ]);
+                        }
+                    }
+                }
+                rs
+            }
+            "db_stats" => self.stats.borrow().to_result_set(),
+            "db_column_stats" => {
+                // the statistics collected by `ANALYZE`, with the bounds of the histogram
+                let mut rs = columns(&[
+                    "table_name",
+                    "column_name",
+                    "rows",
+                    "distinct_values",
+                    "nulls",
+                    "min",
+                    "max",
+                    "histogram",
+                ]);
+                for name in self.table_names() {
+                    let Some(tbl) = self.table(&name.0) else {
+                        continue;
+                    };
+                    let Some(statistics) = tbl.statistics() else {
+                        continue;
+                    };
+                    for (col, stats) in tbl.columns().iter().zip(&statistics.columns) {
+                        let bounds: Vec<_> =
+                            stats.histogram.iter().map(|x| x.to_string()).collect();
+                        rs.rows.push(vec![
+                            string(&name.0),
+                            string(&col.name.0),
+                            SqlValue::Integer(statistics.rows as i64),
+                            SqlValue::Integer(stats.distinct as i64),
+                            SqlValue::Integer(stats.nulls as i64),
+                            stats.min.clone(),
+                            stats.max.clone(),
+                            string(&bounds.join(", ")),
+                        ]);
+                    }
+                }
+                rs
+            }
+            _ => return None,
+        };
+        Some(rs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        errors::ExecutionError,
+        exec::test_util::{context_with, rows},
+    };
+
+    fn string(x: &str) -> SqlValue {
+        SqlValue::String(x.into())
+    }
+
+    /// Users and their orders, a view of the users and an index of their names
+    fn schema() -> ExecutionContext {
+        context_with(&[
+            "CREATE TABLE users (id INT PRIMARY KEY, email STRING UNIQUE, name STRING DEFAULT 'x')",
+            "CREATE TABLE orders (id SERIAL, user_id INT REFERENCES users(id) ON DELETE CASCADE)",
+            "CREATE VIEW names AS SELECT name FROM users",
+            "CREATE INDEX users_name ON users USING fulltext(name)",
+        ])
+    }
+
+    #[test]
+    fn should_list_tables_and_views() {
+        let mut ctx = schema();
+        assert_eq!(
+            rows(&mut ctx, "SELECT name, type FROM db_tables"),
+            Ok(vec![
+                vec![string("orders"), string("table")],
+                vec![string("users"), string("table")],
+                vec![string("names"), string("view")],
+            ])
+        );
+        assert_eq!(
+            rows(&mut ctx, "SELECT sql FROM db_tables WHERE name = 'names'"),
+            Ok(vec![vec![string(
+                "CREATE VIEW names AS SELECT name FROM users"
+            )]])
+        );
+    }
+
+    #[test]
+    fn should_list_columns() {
+        let mut ctx = schema();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT name, type, nullable, default, auto_increment FROM db_columns WHERE table_name = 'users' OR name = 'id' AND table_name = 'orders'"
+            ),
+            Ok(vec![
+                vec![
+                    string("id"),
+                    string("INT"),
+                    SqlValue::Boolean(true),
+                    SqlValue::Nil,
+                    SqlValue::Boolean(true)
+                ],
+                vec![
+                    string("id"),
+                    string("INT"),
+                    SqlValue::Boolean(false),
+                    SqlValue::Nil,
+                    SqlValue::Boolean(false)
+                ],
+                vec![
+                    string("email"),
+                    string("STRING"),
+                    SqlValue::Boolean(true),
+                    SqlValue::Nil,
+                    SqlValue::Boolean(false)
+                ],
+                vec![
+                    string("name"),
+                    string("STRING"),
+                    SqlValue::Boolean(true),
+                    string("'x'"),
+                    SqlValue::Boolean(false)
+                ],
+            ])
+        );
+    }
+
+    #[test]
+    fn should_list_constraints() {
+        let mut ctx = schema();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT referenced_table, on_delete FROM db_constraints WHERE type = 'FOREIGN KEY'"
+            ),
+            Ok(vec![vec![string("users"), string("CASCADE")]])
+        );
+    }
+
+    #[test]
+    fn should_list_only_the_created_indexes() {
+        let mut ctx = schema();
+        assert_eq!(
+            rows(&mut ctx, "SELECT name, column_name, type FROM db_indexes"),
+            Ok(vec![vec![
+                string("users_name"),
+                string("name"),
+                string("fulltext")
+            ]])
+        );
+    }
+
+    #[test]
+    fn should_reserve_the_names_of_system_tables() {
+        let mut ctx = schema();
+        assert_eq!(
+            ctx.run("CREATE TABLE db_tables (x INT)"),
+            Err(ExecutionError::TableAlreadyExists("db_tables".into()))
+        );
+    }
+}
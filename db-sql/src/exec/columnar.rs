@@ -0,0 +1,411 @@
+use crate::{exec::StoredRow, parser::SqlValue};
+use std::iter::repeat_n;
+
+/// How many rows a scan decodes at a time
+pub const BATCH_SIZE: usize = 1024;
+/// The most distinct values a dictionary holds, a column with more is stored plainly
+const MAX_DICTIONARY: usize = 256;
+/// How long a column stays run-length encoded when its values rarely repeat
+const MIN_ENCODED: usize = 64;
+
+/// The values of a column stored contiguously, encoded after how they repeat
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    /// runs of a repeated value with their lengths, e.g. of a sorted or constant column
+    RunLength(Vec<(SqlValue, usize)>),
+    /// the distinct values and the position of the value of each row among them
+    Dictionary {
+        values: Vec<SqlValue>,
+        codes: Vec<u8>,
+    },
+    Plain(Vec<SqlValue>),
+}
+
+impl Column {
+    /// Encodes the values in the way that stores them the most compactly
+    pub fn encode(values: Vec<SqlValue>) -> Column {
+        let runs = 1 + values.windows(2).filter(|x| x[0] != x[1]).count();
+        if values.len() < MIN_ENCODED || runs * 2 <= values.len() {
+            let mut column = Column::RunLength(vec![]);
+            values.into_iter().for_each(|x| column.append(x));
+            return column;
+        }
+        let mut distinct: Vec<&SqlValue> = vec![];
+        for value in &values {
+            if !distinct.contains(&value) {
+                if distinct.len() == MAX_DICTIONARY {
+                    return Column::Plain(values);
+                }
+                distinct.push(value);
+            }
+        }
+        let mut column = Column::Dictionary {
+            values: vec![],
+            codes: vec![],
+        };
+        values.into_iter().for_each(|x| column.append(x));
+        column
+    }
+    pub fn len(&self) -> usize {
+        match self {
+            Column::RunLength(runs) => runs.iter().map(|x| x.1).sum(),
+            Column::Dictionary { codes, .. } => codes.len(),
+            Column::Plain(values) => values.len(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Adds a value after the last one, a run-length encoding which no longer
+    /// pays off, or a full dictionary, is encoded again
+    pub fn append(&mut self, value: SqlValue) {
+        match self {
+            Column::RunLength(runs) => {
+                match runs.last_mut() {
+                    Some(last) if last.0 == value => last.1 += 1,
+                    _ => runs.push((value, 1)),
+                }
+                let len: usize = runs.iter().map(|x| x.1).sum();
+                if len >= MIN_ENCODED && runs.len() * 2 > len {
+                    *self = Column::encode(self.iter().cloned().collect());
+                }
+            }
+            Column::Dictionary { values, codes } => match values.iter().position(|x| x == &value) {
+                Some(code) => codes.push(code as u8),
+                None if values.len() < MAX_DICTIONARY => {
+                    codes.push(values.len() as u8);
+                    values.push(value);
+                }
+                None => {
+                    let mut plain: Vec<_> = self.iter().cloned().collect();
+                    plain.push(value);
+                    *self = Column::Plain(plain);
+                }
+            },
+            Column::Plain(values) => values.push(value),
+        }
+    }
+    /// The value of the row at the position
+    pub fn get(&self, i: usize) -> Option<&SqlValue> {
+        match self {
+            Column::RunLength(runs) => {
+                let mut start = 0;
+                for (value, n) in runs {
+                    if i < start + n {
+                        return Some(value);
+                    }
+                    start += n;
+                }
+                None
+            }
+            Column::Dictionary { values, codes } => codes.get(i).map(|x| &values[*x as usize]),
+            Column::Plain(values) => values.get(i),
+        }
+    }
+    /// Replaces the value of the row at the position
+    pub fn set(&mut self, i: usize, value: SqlValue) {
+        if self.get(i) == Some(&value) || i >= self.len() {
+            return;
+        }
+        match self {
+            Column::RunLength(runs) => {
+                // the run is split around the row
+                let (mut r, mut start) = (0, 0);
+                while start + runs[r].1 <= i {
+                    start += runs[r].1;
+                    r += 1;
+                }
+                let (old, n) = runs[r].clone();
+                let (before, after) = (i - start, n - (i - start) - 1);
+                let split = [(old.clone(), before), (value, 1), (old, after)];
+                runs.splice(r..=r, split.into_iter().filter(|x| x.1 > 0));
+            }
+            Column::Dictionary { values, codes } => match values.iter().position(|x| x == &value) {
+                Some(code) => codes[i] = code as u8,
+                None if values.len() < MAX_DICTIONARY => {
+                    codes[i] = values.len() as u8;
+                    values.push(value);
+                }
+                None => {
+                    let mut plain: Vec<_> = self.iter().cloned().collect();
+                    plain[i] = value;
+                    *self = Column::Plain(plain);
+                }
+            },
+            Column::Plain(values) => values[i] = value,
+        }
+    }
+    /// Removes the value of the row at the position
+    pub fn remove(&mut self, i: usize) -> Option<SqlValue> {
+        match self {
+            Column::RunLength(runs) => {
+                let (mut r, mut start) = (0, 0);
+                while start + runs.get(r)?.1 <= i {
+                    start += runs[r].1;
+                    r += 1;
+                }
+                runs[r].1 -= 1;
+                let value = runs[r].0.clone();
+                if runs[r].1 == 0 {
+                    runs.remove(r);
+                    // the runs around the removed one may now join
+                    if r > 0 && r < runs.len() && runs[r - 1].0 == runs[r].0 {
+                        runs[r - 1].1 += runs.remove(r).1;
+                    }
+                }
+                Some(value)
+            }
+            Column::Dictionary { values, codes } => {
+                (i < codes.len()).then(|| values[codes.remove(i) as usize].clone())
+            }
+            Column::Plain(values) => (i < values.len()).then(|| values.remove(i)),
+        }
+    }
+    /// The values in the order of the rows
+    pub fn iter(&self) -> Box<dyn Iterator<Item = &SqlValue> + '_> {
+        match self {
+            Column::RunLength(runs) => Box::new(runs.iter().flat_map(|(x, n)| repeat_n(x, *n))),
+            Column::Dictionary { values, codes } => {
+                Box::new(codes.iter().map(|x| &values[*x as usize]))
+            }
+            Column::Plain(values) => Box::new(values.iter()),
+        }
+    }
+    /// The distinct values with how many rows have them, in no particular order,
+    /// which aggregates read without decoding each row
+    pub fn runs(&self) -> Vec<(&SqlValue, usize)> {
+        match self {
+            Column::RunLength(runs) => runs.iter().map(|(x, n)| (x, *n)).collect(),
+            Column::Dictionary { values, codes } => {
+                let mut counts = vec![0; values.len()];
+                codes.iter().for_each(|x| counts[*x as usize] += 1);
+                values.iter().zip(counts).collect()
+            }
+            Column::Plain(values) => values.iter().map(|x| (x, 1)).collect(),
+        }
+    }
+}
+
+/// The rows of a table stored column by column, ordered by their ids
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStore {
+    ids: Vec<usize>,
+    columns: Vec<Column>,
+}
+
+impl ColumnStore {
+    pub fn new(width: usize) -> Self {
+        Self {
+            ids: vec![],
+            columns: vec![Column::RunLength(vec![]); width],
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+    pub fn ids(&self) -> &[usize] {
+        &self.ids
+    }
+    pub fn column(&self, i: usize) -> &Column {
+        &self.columns[i]
+    }
+    /// Adds a row whose id is larger than those stored
+    pub fn push(&mut self, id: usize, row: StoredRow) {
+        self.ids.push(id);
+        for (column, value) in self.columns.iter_mut().zip(row) {
+            column.append(value);
+        }
+    }
+    pub fn get(&self, id: usize) -> Option<StoredRow> {
+        let i = self.ids.binary_search(&id).ok()?;
+        self.columns.iter().map(|x| x.get(i).cloned()).collect()
+    }
+    pub fn set(&mut self, id: usize, row: StoredRow) {
+        if let Ok(i) = self.ids.binary_search(&id) {
+            for (column, value) in self.columns.iter_mut().zip(row) {
+                column.set(i, value);
+            }
+        }
+    }
+    pub fn remove(&mut self, id: usize) -> Option<StoredRow> {
+        let i = self.ids.binary_search(&id).ok()?;
+        self.ids.remove(i);
+        self.columns.iter_mut().map(|x| x.remove(i)).collect()
+    }
+    pub fn clear(&mut self) {
+        *self = Self::new(self.columns.len());
+    }
+    /// The rows decoded [`BATCH_SIZE`] at a time, a column after the other
+    pub fn batches(&self) -> Batches<'_> {
+        let positions: Vec<_> = (0..self.columns.len()).collect();
+        self.batches_of(&positions)
+    }
+    /// Same as `batches` with only the columns at the positions, in their order
+    pub fn batches_of(&self, positions: &[usize]) -> Batches<'_> {
+        Batches {
+            ids: &self.ids,
+            columns: positions.iter().map(|x| self.columns[*x].iter()).collect(),
+        }
+    }
+    /// The rows whose values of the columns at the positions pass the filter. The
+    /// filter reads them a batch at a time, the other columns are only decoded for
+    /// the rows it keeps.
+    pub fn filter<E>(
+        &self,
+        positions: &[usize],
+        mut keep: impl FnMut(&[SqlValue]) -> Result<bool, E>,
+    ) -> Result<Vec<StoredRow>, E> {
+        let mut kept = vec![];
+        let mut values = Vec::with_capacity(positions.len());
+        let mut start = 0;
+        for batch in self.batches_of(positions) {
+            let mut columns: Vec<_> = batch.columns.into_iter().map(|x| x.into_iter()).collect();
+            for i in start..start + batch.ids.len() {
+                values.clear();
+                values.extend(columns.iter_mut().filter_map(|x| x.next()));
+                if keep(&values)? {
+                    kept.push(i);
+                }
+            }
+            start += batch.ids.len();
+        }
+        let mut rows: Vec<StoredRow> = kept
+            .iter()
+            .map(|_| Vec::with_capacity(self.columns.len()))
+            .collect();
+        for column in &self.columns {
+            let mut values = column.iter();
+            let mut next = 0;
+            for (row, i) in rows.iter_mut().zip(&kept) {
+                row.extend(values.nth(i - next).cloned());
+                next = i + 1;
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// The ids of a slice of the rows and their values, a vector per column
+pub struct Batch<'a> {
+    pub ids: &'a [usize],
+    pub columns: Vec<Vec<SqlValue>>,
+}
+impl Batch<'_> {
+    /// The values of the rows, a vector per row
+    pub fn into_rows(self) -> impl Iterator<Item = (usize, StoredRow)> {
+        let mut columns: Vec<_> = self.columns.into_iter().map(|x| x.into_iter()).collect();
+        self.ids
+            .iter()
+            .map(move |id| (*id, columns.iter_mut().filter_map(|x| x.next()).collect()))
+    }
+}
+
+pub struct Batches<'a> {
+    ids: &'a [usize],
+    columns: Vec<Box<dyn Iterator<Item = &'a SqlValue> + 'a>>,
+}
+impl<'a> Iterator for Batches<'a> {
+    type Item = Batch<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ids.is_empty() {
+            return None;
+        }
+        let (ids, rest) = self.ids.split_at(BATCH_SIZE.min(self.ids.len()));
+        self.ids = rest;
+        let columns = self
+            .columns
+            .iter_mut()
+            .map(|x| x.take(ids.len()).cloned().collect())
+            .collect();
+        Some(Batch { ids, columns })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_encode_columns() {
+        let int = SqlValue::Integer;
+        let values: Vec<_> = (0..300).map(|x| int(x / 100)).collect();
+        let mut column = Column::encode(values.clone());
+        assert_eq!(
+            column,
+            Column::RunLength(vec![(int(0), 100), (int(1), 100), (int(2), 100)])
+        );
+        column.set(150, int(7));
+        column.remove(0);
+        assert_eq!(column.len(), 299);
+        assert_eq!(column.get(149), Some(&int(7)));
+        assert_eq!(column.runs().len(), 5);
+
+        // alternating values are kept in a dictionary, distinct ones plainly
+        let mut column = Column::encode((0..100).map(|x| int(x % 3)).collect());
+        assert!(matches!(&column, Column::Dictionary { values, .. } if values.len() == 3));
+        column.append(int(5));
+        assert_eq!(column.runs().iter().map(|x| x.1).sum::<usize>(), 101);
+        let column = Column::encode((0..300).map(int).collect());
+        assert!(matches!(column, Column::Plain(_)));
+
+        // a run-length column is encoded again once its values stop repeating
+        let mut column = Column::RunLength(vec![]);
+        (0..100).for_each(|x| column.append(int(x % 2)));
+        assert!(matches!(column, Column::Dictionary { .. }));
+        assert_eq!(
+            column.iter().cloned().collect::<Vec<_>>()[..3],
+            [int(0), int(1), int(0)]
+        );
+
+        let mut store = ColumnStore::new(2);
+        for id in 0..2500 {
+            store.push(id, vec![int(id as i64), SqlValue::String("x".into())]);
+        }
+        store.remove(3);
+        assert_eq!(
+            store.get(4),
+            Some(vec![int(4), SqlValue::String("x".into())])
+        );
+        assert_eq!(store.get(3), None);
+        let batches: Vec<_> = store.batches().collect();
+        assert_eq!(
+            batches.iter().map(|x| x.ids.len()).collect::<Vec<_>>(),
+            vec![BATCH_SIZE, BATCH_SIZE, 2499 - 2 * BATCH_SIZE]
+        );
+        let rows: Vec<_> = store.batches().flat_map(|x| x.into_rows()).collect();
+        assert_eq!(rows[3], (4, vec![int(4), SqlValue::String("x".into())]));
+        assert_eq!(rows.len(), 2499);
+    }
+
+    #[test]
+    fn should_filter_rows_by_some_columns() {
+        let int = SqlValue::Integer;
+        let mut store = ColumnStore::new(3);
+        for id in 0..3000 {
+            let kind = SqlValue::String(["a", "b", "c"][id % 3].into());
+            store.push(id, vec![int(id as i64), kind, int(id as i64 / 100)]);
+        }
+        let rows = store
+            .filter(&[2, 1], |x| {
+                Ok::<_, ()>(x[0] == int(29) && x[1] == SqlValue::String("c".into()))
+            })
+            .unwrap();
+        let ids: Vec<_> = rows.iter().map(|x| x[0].clone()).collect();
+        assert_eq!(
+            ids,
+            (2900..3000)
+                .filter(|x| x % 3 == 2)
+                .map(int)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            rows[0],
+            vec![int(2900), SqlValue::String("c".into()), int(29)]
+        );
+        assert_eq!(store.filter(&[0], |_| Err("failed")), Err("failed"));
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::{StoredRow, Table},
+    format::csv,
+    parser::SqlValue,
+};
+use std::path::Path;
+
+/// Reads the rows of a CSV file converted to the column types of the table, along
+/// with their line numbers. With a header the fields are matched to the columns by
+/// name, otherwise by position.
+pub fn read_csv(
+    tbl: &Table,
+    path: impl AsRef<Path>,
+    header: bool,
+) -> DbResult<Vec<(usize, StoredRow)>, ExecutionError> {
+    let input = std::fs::read_to_string(path)?;
+    let mut records = csv::parse(&input)
+        .map_err(|(line, reason)| ExecutionError::ImportError { line, reason })?
+        .into_iter();
+
+    let columns = tbl.columns();
+    let mut positions: Vec<usize> = (0..columns.len()).collect();
+    if header && let Some((line, fields)) = records.next() {
+        positions = fields
+            .iter()
+            .map(|x| {
+                let name = x.as_deref().unwrap_or_default();
+                tbl.column_index(name).ok_or(ExecutionError::ImportError {
+                    line,
+                    reason: format!("unknown column '{name}'"),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+    }
+
+    let mut rows = vec![];
+    for (line, fields) in records {
+        if fields.len() != positions.len() {
+            return Err(ExecutionError::ImportError {
+                line,
+                reason: format!(
+                    "expected {} fields but got {}",
+                    positions.len(),
+                    fields.len()
+                ),
+            });
+        }
+        let mut row: StoredRow = vec![SqlValue::Nil; columns.len()];
+        for (field, pos) in fields.iter().zip(positions.iter()) {
+            row[*pos] = columns[*pos]
+                .data_type
+                .parse_value(field.as_deref())
+                .map_err(|reason| ExecutionError::ImportError { line, reason })?;
+        }
+        rows.push((line, row));
+    }
+
+    Ok(rows)
+}
+
+/// Writes all the rows of the table to a CSV file and returns the number of rows.
+pub fn write_csv(
+    tbl: &Table,
+    path: impl AsRef<Path>,
+    header: bool,
+) -> DbResult<usize, ExecutionError> {
+    let mut lines = vec![];
+    if header {
+        let names: Vec<_> = tbl
+            .columns()
+            .iter()
+            .map(|x| csv::escape(&x.name.0))
+            .collect();
+        lines.push(names.join(","));
+    }
+    for row in tbl.iter() {
+        lines.push(csv::format_row(&row.values));
+    }
+    let mut content = lines.join("\n");
+    content.push('\n');
+    std::fs::write(path, content)?;
+
+    Ok(tbl.len())
+}
@@ -0,0 +1,190 @@
+use crate::{
+    errors::DbResult,
+    exec::ExecutionContext,
+    parser::{AlterAction, Identifier, Password, SqlStatement, split_statements},
+};
+
+impl ExecutionContext {
+    /// Serializes the whole database as a SQL script of `CREATE TABLE`, `INSERT`,
+    /// `ALTER TABLE`, `CREATE INDEX`, `CREATE VIEW`, `CREATE TRIGGER`, `CREATE USER`
+    /// and `GRANT` statements, which [`ExecutionContext::restore`] loads back.
+    pub fn dump(&self) -> String {
+        let mut script = String::new();
+        for name in self.creation_order() {
+            let Some(tbl) = self.table(&name.0) else {
+                continue;
+            };
+            let create = SqlStatement::Create {
+                table: name.clone(),
+                columns: tbl.columns().clone(),
+                storage: tbl.storage(),
+            };
+            script.push_str(&format!("{create};\n"));
+            for row in tbl.iter() {
+                let insert = SqlStatement::insert_values(name.clone(), &row.values);
+                script.push_str(&format!("{insert};\n"));
+            }
+            // the sequence is kept, even past the values of deleted rows
+            if tbl.auto_increment_column().is_some() {
+                let alter = SqlStatement::Alter {
+                    table: name.clone(),
+                    action: AlterAction::AutoIncrement(tbl.sequence().saturating_add(1)),
+                };
+                script.push_str(&format!("{alter};\n"));
+            }
+            // the indexes are built again from the restored rows
+            for create in tbl.index_statements() {
+                script.push_str(&format!("{create};\n"));
+            }
+            // the statistics are collected again from the restored rows
+            if tbl.statistics().is_some() {
+                let analyze = SqlStatement::Analyze {
+                    table: Some(name.clone()),
+                };
+                script.push_str(&format!("{analyze};\n"));
+            }
+        }
+        for name in self.view_order() {
+            let Some(query) = self.view(&name.0) else {
+                continue;
+            };
+            let view = SqlStatement::CreateView {
+                name: name.clone(),
+                query: Box::new(query.clone()),
+            };
+            script.push_str(&format!("{view};\n"));
+        }
+        // the triggers come last, their changes are already in the rows
+        for trigger in &self.triggers {
+            script.push_str(&format!("{trigger};\n"));
+        }
+        // the users with the hashes of their passwords, and what they are granted
+        for user in self.users() {
+            let create = SqlStatement::CreateUser {
+                name: user.name.clone(),
+                password: Password::Hashed(user.password_hash().to_string()),
+            };
+            script.push_str(&format!("{create};\n"));
+            let mut tables: Vec<&Identifier> = vec![];
+            for (table, _) in user.grants() {
+                if !tables.contains(&table) {
+                    tables.push(table);
+                }
+            }
+            for table in tables {
+                let privileges: Vec<_> = user
+                    .grants()
+                    .iter()
+                    .filter(|x| &x.0 == table)
+                    .map(|x| x.1)
+                    .collect();
+                let grant = SqlStatement::Grant {
+                    privileges,
+                    table: table.clone(),
+                    user: user.name.clone(),
+                };
+                script.push_str(&format!("{grant};\n"));
+            }
+        }
+        script
+    }
+
+    /// Creates an in-memory database from a SQL script, e.g. one made by `dump`.
+    pub fn restore(script: &str) -> DbResult<Self> {
+        let mut ctx = Self::new();
+        ctx.restoring = true;
+        let (statements, rest) = split_statements(script);
+        for stmt in statements
+            .iter()
+            .chain(Some(&rest).filter(|x| !x.is_empty()))
+        {
+            ctx.run(stmt)
+                .map_err(|e| format!("cannot restore '{stmt}': {e}"))?;
+        }
+        ctx.restoring = false;
+        ctx.reset_statistics();
+        Ok(ctx)
+    }
+    /// The tables by name, but the referenced ones before those referencing them
+    fn creation_order(&self) -> Vec<&Identifier> {
+        let mut pending = self.table_names();
+        let mut ordered: Vec<&Identifier> = vec![];
+        while !pending.is_empty() {
+            let ready = |name: &&Identifier| {
+                self.table(&name.0).is_none_or(|tbl| {
+                    tbl.columns()
+                        .iter()
+                        .filter_map(|x| x.references.as_ref())
+                        .all(|x| &x.table == *name || ordered.contains(&&x.table))
+                })
+            };
+            // the references never form a cycle, a table is created after those it references
+            let i = pending.iter().position(ready).unwrap_or(0);
+            ordered.push(pending.remove(i));
+        }
+        ordered
+    }
+    /// The views by name, but those read by other views first
+    fn view_order(&self) -> Vec<&Identifier> {
+        let mut pending = self.view_names();
+        let mut ordered: Vec<&Identifier> = vec![];
+        while !pending.is_empty() {
+            let ready = |name: &&Identifier| {
+                self.view(&name.0).is_none_or(|query| {
+                    query
+                        .tables()
+                        .iter()
+                        .all(|x| self.view(&x.0).is_none() || ordered.contains(x))
+                })
+            };
+            let i = pending.iter().position(ready).unwrap_or(0);
+            ordered.push(pending.remove(i));
+        }
+        ordered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::test_util::context_with;
+
+    #[test]
+    fn should_round_trip_dump() {
+        let ctx = context_with(&[
+            "CREATE TABLE users (id INT PRIMARY KEY, name STRING, score FLOAT, active BIT)",
+            "CREATE TABLE empty (id INT)",
+            "CREATE TABLE typed (id BIGINT, price DECIMAL(10,2), day DATE, at TIMESTAMP, data BLOB, code VARCHAR(3), key UUID DEFAULT gen_random_uuid())",
+            "INSERT INTO typed VALUES (-9000000000, 12.5, '2024-02-29', '2024-02-29 23:59:59.5', X'00ff', 'it''')",
+            "INSERT INTO users VALUES (1, 'it''s; ok', 1.5, true)",
+            "INSERT INTO users VALUES (2, '', 100000000000000000000.0, nil)",
+            "CREATE TABLE items (id SERIAL PRIMARY KEY, name STRING)",
+            "CREATE VIEW a_view AS SELECT id, name FROM items",
+            "INSERT INTO items (name) VALUES ('a')",
+            "INSERT INTO items (name) VALUES ('b')",
+            "DELETE items WHERE id = 2",
+            "CREATE TABLE a_tags (item INT REFERENCES items(id), parent INT REFERENCES a_tags(id), id INT UNIQUE)",
+            "INSERT INTO a_tags VALUES (1, nil, 1)",
+            "INSERT INTO a_tags VALUES (1, 1, 2)",
+            "UPDATE a_tags SET parent = 2 WHERE id = 1",
+            "CREATE VIEW cheap AS SELECT id FROM a_view WHERE id < 2",
+            "CREATE INDEX users_name ON users USING fulltext(name)",
+            "CREATE INDEX items_name ON items USING btree(name)",
+            "CREATE VIEW tagged AS SELECT i.name, t.id FROM items i JOIN a_tags t ON t.item = i.id",
+            "CREATE TRIGGER tag AFTER INSERT ON items BEGIN INSERT INTO a_tags VALUES (NEW.id, nil, NEW.id + 10); END",
+        ]);
+
+        let script = ctx.dump();
+        let restored = ExecutionContext::restore(&script).unwrap();
+        assert_eq!(restored.table("users"), ctx.table("users"));
+        assert_eq!(restored.table("empty"), ctx.table("empty"));
+        assert_eq!(restored.table("typed"), ctx.table("typed"));
+        assert_eq!(restored.table("items"), ctx.table("items"));
+        assert_eq!(restored.table("items").unwrap().sequence(), 2);
+        assert_eq!(restored.table("a_tags"), ctx.table("a_tags"));
+        assert_eq!(restored.view("cheap"), ctx.view("cheap"));
+        assert_eq!(restored.view("tagged"), ctx.view("tagged"));
+        assert_eq!(restored.triggers(), ctx.triggers());
+        assert_eq!(restored.dump(), script);
+    }
+}
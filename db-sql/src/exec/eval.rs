@@ -0,0 +1,349 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::{ExecutionContext, ResultSet},
+    exec::{fulltext, function},
+    parser::{ArithmeticOperator, Condition, Expr, Identifier, LogicalOperator, Operator, Window},
+    types::{Date, Decimal, SqlValue},
+};
+use std::cmp::Ordering;
+
+/// The row being evaluated, with the names of its columns, and the row of the
+/// enclosing query a correlated subquery may read.
+#[derive(Clone, Copy)]
+pub struct Scope<'a> {
+    /// the table or alias which qualifies the columns
+    pub table: &'a Identifier,
+    pub columns: &'a [Identifier],
+    pub values: &'a [SqlValue],
+    pub outer: Option<&'a Scope<'a>>,
+}
+
+impl Scope<'_> {
+    /// The value of the column, looked up from the innermost query outwards
+    pub fn resolve(&self, column: &Identifier) -> DbResult<SqlValue, ExecutionError> {
+        let mut scope = Some(self);
+        while let Some(x) = scope {
+            if let Some(i) = x.position(column)? {
+                return Ok(x.values[i].clone());
+            }
+            scope = x.outer;
+        }
+        Err(ExecutionError::ColumnNotFound {
+            table: column.qualifier().unwrap_or(&self.table.0).to_string(),
+            column: column.column().to_string(),
+        })
+    }
+    /// The position of the column among those of this scope, not the outer ones.
+    /// The columns of a join are qualified by their tables, a name two of them
+    /// have must be qualified too.
+    pub fn position(&self, column: &Identifier) -> DbResult<Option<usize>, ExecutionError> {
+        let found = |c: &Identifier| match c.qualifier() {
+            Some(table) => {
+                column.qualifier().is_none_or(|q| q == table) && c.column() == column.column()
+            }
+            None => column.qualifier().is_none_or(|q| q == self.table.0) && c.0 == column.column(),
+        };
+        let Some(i) = self.columns.iter().position(found) else {
+            return Ok(None);
+        };
+        if column.qualifier().is_none() && self.columns[i + 1..].iter().any(found) {
+            return Err(ExecutionError::InvalidStatement(format!(
+                "column {column} is ambiguous"
+            )));
+        }
+        Ok(Some(i))
+    }
+    /// The value of the window function computed for the row, a column named after
+    /// the window when the select list has one
+    pub fn window(&self, window: &Window) -> Option<SqlValue> {
+        self.computed(&window.to_string())
+    }
+    /// Same as `window` for an aggregate of the rows of the query
+    pub fn aggregate(&self, aggregate: &Expr) -> Option<SqlValue> {
+        self.computed(&aggregate.to_string())
+    }
+    fn computed(&self, name: &str) -> Option<SqlValue> {
+        let i = self.columns.iter().position(|c| c.0 == name)?;
+        Some(self.values[i].clone())
+    }
+}
+
+/// Checks whether the row satisfies the condition. Comparisons with nil are never
+/// satisfied.
+pub fn matches(
+    condition: &Condition,
+    ctx: &ExecutionContext,
+    scope: &Scope,
+) -> DbResult<bool, ExecutionError> {
+    match condition {
+        Condition::Comparison {
+            left,
+            operator,
+            right,
+        } => {
+            let left = evaluate(left, ctx, scope)?;
+            let right = evaluate(right, ctx, scope)?;
+            let Some(ord) = left.compare(&right) else {
+                return Ok(false);
+            };
+            Ok(match operator {
+                Operator::Equal => ord == Ordering::Equal,
+                Operator::NotEqual => ord != Ordering::Equal,
+                Operator::LessThan => ord == Ordering::Less,
+                Operator::LessThanOrEqual => ord != Ordering::Greater,
+                Operator::GreaterThan => ord == Ordering::Greater,
+                Operator::GreaterThanOrEqual => ord != Ordering::Less,
+            })
+        }
+        Condition::Logical {
+            operator,
+            conditions,
+        } => {
+            for condition in conditions {
+                let res = matches(condition, ctx, scope)?;
+                match operator {
+                    LogicalOperator::And if !res => return Ok(false),
+                    LogicalOperator::Or if res => return Ok(true),
+                    _ => {}
+                }
+            }
+            Ok(*operator == LogicalOperator::And)
+        }
+        Condition::In {
+            expr,
+            query,
+            negated,
+        } => {
+            let value = evaluate(expr, ctx, scope)?;
+            if value == SqlValue::Nil {
+                return Ok(false);
+            }
+            let rs = single_column(ctx.query(query, Some(scope))?)?;
+            let mut found = false;
+            let mut nil = false;
+            for row in &rs.rows {
+                found |= value.compare(&row[0]) == Some(Ordering::Equal);
+                nil |= row[0] == SqlValue::Nil;
+            }
+            // like a comparison with nil, `NOT IN` is not satisfied when nil is among
+            // the values
+            Ok(if *negated { !found && !nil } else { found })
+        }
+        Condition::Exists { query, negated } => {
+            let rs = ctx.query(query, Some(scope))?;
+            Ok(rs.rows.is_empty() == *negated)
+        }
+        Condition::Match { expr, query } => {
+            match (evaluate(expr, ctx, scope)?, evaluate(query, ctx, scope)?) {
+                (SqlValue::Nil, _) | (_, SqlValue::Nil) => Ok(false),
+                (SqlValue::String(text), SqlValue::String(query)) => {
+                    Ok(fulltext::matches(&text, &query))
+                }
+                (SqlValue::String(_), found) | (found, _) => Err(ExecutionError::TypeMismatch {
+                    expected: "STRING".into(),
+                    found: found.type_name().to_string(),
+                }),
+            }
+        }
+    }
+}
+
+/// Computes the value of the expression for the row
+pub fn evaluate(
+    expr: &Expr,
+    ctx: &ExecutionContext,
+    scope: &Scope,
+) -> DbResult<SqlValue, ExecutionError> {
+    evaluate_with(expr, Some(ctx), Some(scope))
+}
+
+/// Computes the value of an expression without columns, e.g. a default
+pub fn evaluate_constant(expr: &Expr) -> DbResult<SqlValue, ExecutionError> {
+    evaluate_with(expr, None, None)
+}
+
+/// Computes the value of the expression, the columns are read from the scope and
+/// subqueries are run in the context, both fail without them.
+fn evaluate_with(
+    expr: &Expr,
+    ctx: Option<&ExecutionContext>,
+    scope: Option<&Scope>,
+) -> DbResult<SqlValue, ExecutionError> {
+    let eval = |x: &Expr| evaluate_with(x, ctx, scope);
+    Ok(match expr {
+        Expr::Literal(x) => x.clone(),
+        Expr::Column(x) => match scope {
+            Some(scope) => scope.resolve(x)?,
+            None => {
+                return Err(ExecutionError::ColumnNotFound {
+                    table: String::new(),
+                    column: x.0.clone(),
+                });
+            }
+        },
+        Expr::Negate(x) => arithmetic(
+            &SqlValue::Integer(0),
+            ArithmeticOperator::Subtract,
+            &eval(x)?,
+        )?,
+        Expr::Binary {
+            left,
+            operator,
+            right,
+        } => arithmetic(&eval(left)?, *operator, &eval(right)?)?,
+        Expr::Cast { expr, data_type } => data_type.cast(eval(expr)?)?,
+        Expr::Function { name, args } => {
+            let args = args.iter().map(eval).collect::<Result<_, _>>()?;
+            function::call(&name.0, args)?
+        }
+        Expr::JsonField { expr, key, as_text } => {
+            function::json_field(eval(expr)?, eval(key)?, *as_text)?
+        }
+        Expr::Window(w) => match scope.and_then(|x| x.window(w)) {
+            Some(x) => x,
+            None => {
+                return Err(ExecutionError::InvalidStatement(format!(
+                    "window function {} is only allowed in the select list",
+                    w.function
+                )));
+            }
+        },
+        Expr::Aggregate { function, .. } => match scope.and_then(|x| x.aggregate(expr)) {
+            Some(x) => x,
+            None => {
+                return Err(ExecutionError::InvalidStatement(format!(
+                    "aggregate function {function} is only allowed in the select list"
+                )));
+            }
+        },
+        Expr::Subquery(query) => {
+            let Some(ctx) = ctx else {
+                return Err(ExecutionError::InvalidStatement(
+                    "subqueries are not allowed here".into(),
+                ));
+            };
+            let rs = single_column(ctx.query(query, scope)?)?;
+            match &rs.rows[..] {
+                [] => SqlValue::Nil,
+                [row] => row[0].clone(),
+                _ => {
+                    return Err(ExecutionError::InvalidStatement(
+                        "more than one row returned by a subquery used as an expression".into(),
+                    ));
+                }
+            }
+        }
+    })
+}
+
+/// Checks that the rows of a subquery compared with a value have a single column
+fn single_column(rs: ResultSet) -> DbResult<ResultSet, ExecutionError> {
+    if rs.columns.len() != 1 {
+        return Err(ExecutionError::InvalidStatement(format!(
+            "subquery must return a single column, not {}",
+            rs.columns.len()
+        )));
+    }
+    Ok(rs)
+}
+
+/// Applies the operator to the values. Integers stay integers unless mixed with
+/// decimals or floats, and days can be added to or subtracted from dates. Nil
+/// operands give nil, a float which overflows is out of range.
+pub(super) fn arithmetic(
+    left: &SqlValue,
+    operator: ArithmeticOperator,
+    right: &SqlValue,
+) -> DbResult<SqlValue, ExecutionError> {
+    use ArithmeticOperator::*;
+
+    let overflow = |data_type: &str| ExecutionError::ValueOutOfRange {
+        data_type: data_type.to_string(),
+        value: format!("{} {} {}", left.to_literal(), operator, right.to_literal()),
+    };
+    Ok(match (left, right) {
+        (SqlValue::Nil, _) | (_, SqlValue::Nil) => SqlValue::Nil,
+        (SqlValue::Integer(a), SqlValue::Integer(b)) => {
+            if *b == 0 && matches!(operator, Divide | Modulo) {
+                return Err(ExecutionError::DivisionByZero);
+            }
+            let res = match operator {
+                Add => a.checked_add(*b),
+                Subtract => a.checked_sub(*b),
+                Multiply => a.checked_mul(*b),
+                Divide => a.checked_div(*b),
+                Modulo => a.checked_rem(*b),
+            };
+            SqlValue::Integer(res.ok_or_else(|| overflow("BIGINT"))?)
+        }
+        (SqlValue::Float(_), x) | (x, SqlValue::Float(_)) if x.is_numeric() => {
+            let (a, b) = (to_f64(left), to_f64(right));
+            if b == 0.0 && matches!(operator, Divide | Modulo) {
+                return Err(ExecutionError::DivisionByZero);
+            }
+            let res = match operator {
+                Add => a + b,
+                Subtract => a - b,
+                Multiply => a * b,
+                Divide => a / b,
+                Modulo => a % b,
+            };
+            if !res.is_finite() {
+                return Err(overflow("FLOAT"));
+            }
+            SqlValue::Float(res)
+        }
+        (SqlValue::Decimal(_), x) | (x, SqlValue::Decimal(_)) if x.is_numeric() => {
+            let (a, b) = (to_decimal(left), to_decimal(right));
+            if b.mantissa() == 0 && matches!(operator, Divide | Modulo) {
+                return Err(ExecutionError::DivisionByZero);
+            }
+            let res = match operator {
+                Add => a.checked_add(&b),
+                Subtract => a.checked_sub(&b),
+                Multiply => a.checked_mul(&b),
+                Divide => a.checked_div(&b),
+                Modulo => a.checked_rem(&b),
+            };
+            SqlValue::Decimal(res.ok_or_else(|| overflow("DECIMAL"))?)
+        }
+        (SqlValue::Date(a), SqlValue::Integer(b)) if matches!(operator, Add | Subtract) => {
+            let days = match operator {
+                Add => (a.0 as i64).checked_add(*b),
+                _ => (a.0 as i64).checked_sub(*b),
+            };
+            let date = days.and_then(|x| i32::try_from(x).ok()).map(Date);
+            SqlValue::Date(
+                date.filter(Date::is_in_range)
+                    .ok_or_else(|| overflow("DATE"))?,
+            )
+        }
+        (SqlValue::Date(a), SqlValue::Date(b)) if operator == Subtract => {
+            SqlValue::Integer(a.0 as i64 - b.0 as i64)
+        }
+        _ => {
+            let found = if left.is_numeric() { right } else { left };
+            return Err(ExecutionError::TypeMismatch {
+                expected: "a number".into(),
+                found: found.type_name().to_string(),
+            });
+        }
+    })
+}
+
+fn to_f64(value: &SqlValue) -> f64 {
+    match value {
+        SqlValue::Integer(x) => *x as f64,
+        SqlValue::Float(x) => *x,
+        SqlValue::Decimal(x) => x.to_f64(),
+        _ => f64::NAN,
+    }
+}
+
+fn to_decimal(value: &SqlValue) -> Decimal {
+    match value {
+        SqlValue::Integer(x) => Decimal::from(*x),
+        SqlValue::Decimal(x) => *x,
+        _ => Decimal::new(0, 0),
+    }
+}
@@ -1,103 +1,646 @@
 use crate::{
     errors::{DbResult, ExecutionError},
-    exec::{Row, Table},
-    parser::{Identifier, SqlStatement},
+    exec::{
+        ExecutionResult, ResultSet, Statistics, Table, TimedResult, User, catalog, copy, eval,
+        eval::Scope,
+    },
+    parser::{
+        AlterAction, CopyDirection, Expr, Identifier, IndexKind, Password, Query, SqlStatement,
+        SqlValue, Trigger, TriggerEvent, TriggerTiming,
+    },
+};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::Path,
+    time::{Duration, Instant},
 };
-use std::{collections::HashMap, fmt::Display};
-
-#[derive(Debug, Clone)]
-pub enum ExecutionResult<'a> {
-    Select(Vec<Row<'a>>),
-    Insert,
-    Create,
-    Delete,
-}
-
-impl Display for ExecutionResult<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let ExecutionResult::Select(rows) = self {
-            for (i, row) in rows.iter().enumerate() {
-                if i == 0 {
-                    for col in row.columns.iter() {
-                        write!(f, "\t{}\t| ", col.name)?;
-                    }
-                }
-                write!(f, "\n")?;
-                for val in row.values.iter() {
-                    write!(f, "\t{}\t| ", val.1)?;
-                }
-            }
-        } else {
-            write!(f, "{self:?}")?;
-        }
 
-        Ok(())
-    }
-}
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ExecutionContext {
     tables: HashMap<Identifier, Table>,
+    views: HashMap<Identifier, Query>,
+    journal: Option<File>,
+    /// whether a dump is being replayed, whose statements were valid when recorded
+    pub(super) restoring: bool,
+    /// the rows of the CTEs of the running queries, the innermost last
+    pub(super) ctes: RefCell<Vec<(Identifier, ResultSet)>>,
+    /// in the order they were created, which is the order they fire in
+    pub(super) triggers: Vec<Trigger>,
+    /// how many triggers are running, one inside the other
+    pub(super) trigger_depth: usize,
+    /// the id of the row of the last `INSERT`, which is recorded in the journal
+    last_insert: Option<usize>,
+    /// the positions of the rows changed by the last `UPDATE`, recorded likewise
+    last_update: Vec<usize>,
+    pub(super) users: HashMap<Identifier, User>,
+    /// the user who runs the statements, the owner when none has logged in
+    pub(super) current_user: Option<Identifier>,
+    pub(super) stats: RefCell<Statistics>,
+    /// whether only the queries may run, e.g. on a file opened read-only
+    readonly: bool,
 }
 
 impl ExecutionContext {
     pub fn new() -> Self {
-        Self {
-            tables: HashMap::new(),
-        }
+        Self::default()
     }
+    /// Opens a file backed context. The statements already recorded in the file are
+    /// replayed and compacted into a dump, and every later change is appended to it.
+    pub fn open(path: impl AsRef<Path>) -> DbResult<Self> {
+        let path = path.as_ref();
+        let mut ctx = match std::fs::read_to_string(path) {
+            Ok(script) => Self::restore(&script)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => Self::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, ctx.dump())?;
+        std::fs::rename(&tmp, path)?;
+        ctx.journal = Some(OpenOptions::new().append(true).open(path)?);
+
+        Ok(ctx)
+    }
+    /// Opens a file backed context which only runs queries. The file is replayed
+    /// but neither compacted nor changed.
+    pub fn open_readonly(path: impl AsRef<Path>) -> DbResult<Self> {
+        let mut ctx = Self::restore(&std::fs::read_to_string(path)?)?;
+        ctx.readonly = true;
+        Ok(ctx)
+    }
+    pub fn is_readonly(&self) -> bool {
+        self.readonly
+    }
+    /// Parses and executes the input, printing is up to the caller.
     pub fn run(&mut self, input: &str) -> DbResult<ExecutionResult, ExecutionError> {
-        let stmt = SqlStatement::try_from(input);
-        if let Err(e) = stmt {
-            eprintln!("PARSER ERR: {:?}", e);
-            return Err(ExecutionError::ParserError(e.to_string()));
+        self.run_timed(input).result
+    }
+    /// Same as `run`, with how long the statement took to execute. The parsing is
+    /// not timed, an input which fails to parse took no time but counts as an error.
+    pub fn run_timed(&mut self, input: &str) -> TimedResult {
+        match SqlStatement::try_from(input) {
+            Ok(stmt) => self.execute_timed(&stmt),
+            Err(e) => TimedResult {
+                result: Err(self.failed(ExecutionError::ParserError(e))),
+                elapsed: Duration::ZERO,
+            },
+        }
+    }
+    /// Counts a statement which failed before it ran, e.g. to parse
+    pub(crate) fn failed(&mut self, error: ExecutionError) -> ExecutionError {
+        let stats = self.stats.get_mut();
+        stats.record(&Err(error.clone()), Duration::ZERO);
+        error
+    }
+    /// Executes the statement and counts it in the statistics
+    pub fn execute(&mut self, stmt: &SqlStatement) -> DbResult<ExecutionResult, ExecutionError> {
+        self.execute_timed(stmt).result
+    }
+    /// Same as `execute`, with how long the statement took
+    pub fn execute_timed(&mut self, stmt: &SqlStatement) -> TimedResult {
+        let started = Instant::now();
+        let result = self.apply(stmt);
+        let elapsed = started.elapsed();
+        self.stats.get_mut().record(&result, elapsed);
+        TimedResult { result, elapsed }
+    }
+    fn apply(&mut self, stmt: &SqlStatement) -> DbResult<ExecutionResult, ExecutionError> {
+        if stmt.placeholder_count() > 0 {
+            return Err(ExecutionError::UnboundParameter(1));
         }
-        let res = self.exec(&stmt.unwrap())?;
-        println!("{}", res);
+        // an export reads the rows only
+        let reads = matches!(
+            stmt,
+            SqlStatement::Select(_)
+                | SqlStatement::Copy {
+                    direction: CopyDirection::To,
+                    ..
+                }
+        );
+        if self.readonly && !reads {
+            return Err(ExecutionError::ReadOnly);
+        }
+        self.authorize(stmt)?;
+        let res = self.exec(stmt)?;
+        // only the changes are recorded, and only once they have been applied
+        match (stmt, &res) {
+            (SqlStatement::Select(_), _) => {}
+            (
+                SqlStatement::Copy {
+                    table,
+                    direction: CopyDirection::From,
+                    ..
+                },
+                ExecutionResult::Copy(n),
+            ) => {
+                // the copied rows are recorded as the file might be gone on replay
+                let tbl = &self.tables[table];
+                let inserts: Vec<_> = tbl
+                    .iter()
+                    .skip(tbl.len() - n)
+                    .map(|row| SqlStatement::insert_values(table.clone(), &row.values).to_string())
+                    .collect();
+                for insert in inserts {
+                    self.record(&insert)?;
+                }
+            }
+            (SqlStatement::Copy { .. }, _) => {}
+            (SqlStatement::Insert { table, .. }, _) => {
+                // the stored row is recorded, as defaults like `gen_random_uuid()` would
+                // differ on replay
+                if let Some(row) = self.last_insert.and_then(|id| self.tables[table].get(id)) {
+                    let insert = SqlStatement::insert_values(table.clone(), &row);
+                    self.record(&insert.to_string())?;
+                }
+            }
+            (
+                SqlStatement::Update {
+                    table, assignments, ..
+                },
+                _,
+            ) => {
+                // the new values are recorded row by row, as functions like
+                // `gen_random_uuid()` would give others on replay
+                let tbl = &self.tables[table];
+                let mut updates = vec![];
+                let mut changed = self.last_update.iter().peekable();
+                for (position, row) in tbl.iter().enumerate() {
+                    if changed.next_if_eq(&&position).is_none() {
+                        continue;
+                    }
+                    let assignments = assignments
+                        .iter()
+                        .filter_map(|(column, _)| {
+                            let value = row.values[tbl.column_index(&column.0)?].clone();
+                            Some((column.clone(), Expr::Literal(value)))
+                        })
+                        .collect();
+                    let update = SqlStatement::Update {
+                        table: table.clone(),
+                        assignments,
+                        row: Some(position),
+                        condition: None,
+                    };
+                    updates.push(update.to_string());
+                }
+                for update in updates {
+                    self.record(&update)?;
+                }
+            }
+            (SqlStatement::CreateUser { name, .. }, _) => {
+                // only the hash of the password is recorded
+                let create = SqlStatement::CreateUser {
+                    name: name.clone(),
+                    password: Password::Hashed(self.users[name].password_hash().to_string()),
+                };
+                self.record(&create.to_string())?;
+            }
+            _ => self.record(&stmt.to_string())?,
+        }
+
         Ok(res)
     }
+    pub fn table_names(&self) -> Vec<&Identifier> {
+        let mut names: Vec<_> = self.tables.keys().collect();
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+        names
+    }
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.get(&Identifier::from(name))
+    }
+    pub fn view_names(&self) -> Vec<&Identifier> {
+        let mut names: Vec<_> = self.views.keys().collect();
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+        names
+    }
+    /// The query of the view
+    pub fn view(&self, name: &str) -> Option<&Query> {
+        self.views.get(&Identifier::from(name))
+    }
+    /// The triggers in the order they were created
+    pub fn triggers(&self) -> &[Trigger] {
+        &self.triggers
+    }
 
-    pub fn exec(&mut self, cmd: &SqlStatement) -> DbResult<ExecutionResult, ExecutionError> {
+    pub(super) fn exec(&mut self, cmd: &SqlStatement) -> DbResult<ExecutionResult, ExecutionError> {
         match cmd {
-            SqlStatement::Select { table, .. } => {
-                let tbl = self
-                    .tables
-                    .get(table)
-                    .ok_or(ExecutionError::TableNotFound)?;
-
-                Ok(ExecutionResult::Select(tbl.iter().collect()))
-            }
-            SqlStatement::Insert { table, values } => {
-                let tbl = self
-                    .tables
-                    .get_mut(table)
-                    .ok_or(ExecutionError::TableNotFound)?;
-
-                tbl.insert(values.iter().map(|x| x.to_string()).collect());
-                Ok(ExecutionResult::Insert)
+            SqlStatement::Select(select) => Ok(ExecutionResult::Select(self.query(select, None)?)),
+            SqlStatement::Insert {
+                table,
+                columns,
+                values,
+                returning,
+            } => {
+                let values: Vec<_> = values
+                    .iter()
+                    .map(eval::evaluate_constant)
+                    .collect::<Result<_, _>>()?;
+                let id = if self.has_triggers(table, TriggerEvent::Insert) {
+                    // the row the BEFORE triggers see has no defaults yet
+                    let tbl = self.get_table(table)?;
+                    let mut new = vec![SqlValue::Nil; tbl.columns().len()];
+                    if columns.is_empty() {
+                        new.iter_mut()
+                            .zip(&values)
+                            .for_each(|(x, v)| *x = v.clone());
+                    } else {
+                        for (column, v) in columns.iter().zip(&values) {
+                            new[tbl.require_column(&column.0)?] = v.clone();
+                        }
+                    }
+                    self.atomically(table, |ctx| {
+                        ctx.fire(
+                            table,
+                            TriggerTiming::Before,
+                            TriggerEvent::Insert,
+                            None,
+                            Some(&new),
+                        )?;
+                        let id = ctx.insert_row(table, columns, values)?;
+                        let new = ctx.get_table(table)?.get(id).map(Cow::into_owned);
+                        ctx.fire(
+                            table,
+                            TriggerTiming::After,
+                            TriggerEvent::Insert,
+                            None,
+                            new.as_deref(),
+                        )?;
+                        Ok(id)
+                    })?
+                } else {
+                    self.insert_row(table, columns, values)?
+                };
+                if self.trigger_depth == 0 {
+                    self.last_insert = Some(id);
+                }
+                let tbl = self.get_table(table)?;
+                if returning.is_empty() {
+                    return Ok(ExecutionResult::Insert(1));
+                }
+                let row = tbl.get(id);
+                let rows: Vec<_> = row.as_deref().into_iter().collect();
+                let rs = self.project(table, &tbl.column_names(), returning, &rows, None)?;
+                Ok(ExecutionResult::Select(rs))
             }
-            SqlStatement::Create { table, columns } => {
-                if self.tables.get(table).is_some() {
-                    return Err(ExecutionError::TableAlreadyExists);
+            SqlStatement::Create {
+                table,
+                columns,
+                storage,
+            } => {
+                if self.tables.contains_key(table) || catalog::is_catalog(table) {
+                    return Err(ExecutionError::TableAlreadyExists(table.0.clone()));
                 };
+                if self.views.contains_key(table) {
+                    return Err(ExecutionError::ViewAlreadyExists(table.0.clone()));
+                }
+                // the defaults must not read columns and must fit their columns
+                for col in columns {
+                    if let Some(default) = &col.default {
+                        col.data_type.coerce(eval::evaluate_constant(default)?)?;
+                    }
+                }
+                self.check_foreign_keys(table, columns)?;
 
-                let t = Table::new(columns.clone());
+                let t = Table::with_storage(table.clone(), columns.clone(), *storage);
                 self.tables.insert(table.clone(), t);
 
                 Ok(ExecutionResult::Create)
             }
+            SqlStatement::CreateView { name, query } => {
+                if self.tables.contains_key(name) || catalog::is_catalog(name) {
+                    return Err(ExecutionError::TableAlreadyExists(name.0.clone()));
+                }
+                if self.views.contains_key(name) {
+                    return Err(ExecutionError::ViewAlreadyExists(name.0.clone()));
+                }
+                // the query runs once to check its tables and columns
+                self.query(query, None)?;
+                self.views.insert(name.clone(), query.as_ref().clone());
+                Ok(ExecutionResult::Create)
+            }
+            SqlStatement::DropView { name } => {
+                if !self.views.contains_key(name) {
+                    return Err(ExecutionError::ViewNotFound(name.0.clone()));
+                }
+                if let Some(other) = self
+                    .view_names()
+                    .into_iter()
+                    .find(|x| self.views[*x].tables().contains(&name))
+                {
+                    return Err(ExecutionError::InvalidStatement(format!(
+                        "cannot drop view {name}, view {other} depends on it"
+                    )));
+                }
+                self.views.remove(name);
+                self.revoke_all(name);
+                Ok(ExecutionResult::Drop)
+            }
+            SqlStatement::CreateIndex {
+                name,
+                table,
+                column,
+                kind,
+            } => {
+                if self.tables.values().any(|x| x.has_index(name)) {
+                    return Err(ExecutionError::IndexAlreadyExists(name.0.clone()));
+                }
+                let tbl = self.get_table_mut(table)?;
+                match kind {
+                    IndexKind::FullText => tbl.create_index(name.clone(), &column.0)?,
+                    IndexKind::BTree => tbl.create_btree_index(name.clone(), &column.0)?,
+                }
+                Ok(ExecutionResult::Create)
+            }
+            SqlStatement::DropIndex { name } => {
+                if !self.tables.values_mut().any(|x| x.drop_index(name)) {
+                    return Err(ExecutionError::IndexNotFound(name.0.clone()));
+                }
+                Ok(ExecutionResult::Drop)
+            }
+            SqlStatement::CreateTrigger(trigger) => {
+                if self.triggers.iter().any(|x| x.name == trigger.name) {
+                    return Err(ExecutionError::TriggerAlreadyExists(trigger.name.0.clone()));
+                }
+                self.get_table(&trigger.table)?;
+                self.triggers.push(trigger.as_ref().clone());
+                Ok(ExecutionResult::Create)
+            }
+            SqlStatement::DropTrigger { name } => {
+                let Some(i) = self.triggers.iter().position(|x| &x.name == name) else {
+                    return Err(ExecutionError::TriggerNotFound(name.0.clone()));
+                };
+                self.triggers.remove(i);
+                Ok(ExecutionResult::Drop)
+            }
             SqlStatement::Delete { table, condition } => {
-                let tbl = self
-                    .tables
-                    .get_mut(table)
-                    .ok_or(ExecutionError::TableNotFound)?;
+                let tbl = self.get_table(table)?;
+                let columns = tbl.column_names();
 
-                if condition.is_none() {
-                    tbl.clear_all();
+                let mut ids = vec![];
+                let condition = condition.as_ref().map(|x| self.plan(table, table, x));
+                self.scanned(tbl.len());
+                for row in tbl.iter() {
+                    let scope = Scope {
+                        table,
+                        columns: &columns,
+                        values: &row.values,
+                        outer: None,
+                    };
+                    match condition.as_deref() {
+                        Some(cond) if !eval::matches(cond, self, &scope)? => {}
+                        _ => ids.push(row.id),
+                    }
+                }
+                let old: Vec<_> = if self.has_triggers(table, TriggerEvent::Delete) {
+                    ids.iter()
+                        .filter_map(|x| tbl.get(*x).map(Cow::into_owned))
+                        .collect()
                 } else {
-                    tbl.delete(0);
+                    vec![]
+                };
+                // the rows of other tables may be deleted or changed as well
+                self.atomically(table, |ctx| {
+                    for row in &old {
+                        ctx.fire(
+                            table,
+                            TriggerTiming::Before,
+                            TriggerEvent::Delete,
+                            Some(row),
+                            None,
+                        )?;
+                    }
+                    ctx.delete_rows(table, &ids)?;
+                    for row in &old {
+                        ctx.fire(
+                            table,
+                            TriggerTiming::After,
+                            TriggerEvent::Delete,
+                            Some(row),
+                            None,
+                        )?;
+                    }
+                    Ok(())
+                })?;
+                Ok(ExecutionResult::Delete(ids.len()))
+            }
+            SqlStatement::Update {
+                table,
+                assignments,
+                row: at,
+                condition,
+            } => {
+                let tbl = self.get_table(table)?;
+                let columns = tbl.column_names();
+                let nils = vec![SqlValue::Nil; columns.len()];
+                let empty = Scope {
+                    table,
+                    columns: &columns,
+                    values: &nils,
+                    outer: None,
+                };
+                let mut positions = vec![];
+                for (column, expr) in assignments {
+                    positions.push(tbl.require_column(&column.0)?);
+                    for column in expr.columns() {
+                        empty.resolve(column)?;
+                    }
+                }
+
+                // all the new values are computed from the old rows
+                let mut changes = vec![];
+                let mut changed = vec![];
+                let condition = condition.as_ref().map(|x| self.plan(table, table, x));
+                self.scanned(tbl.len());
+                for (position, row) in tbl.iter().enumerate() {
+                    if at.is_some_and(|x| x != position) {
+                        continue;
+                    }
+                    let scope = Scope {
+                        values: &row.values,
+                        ..empty
+                    };
+                    match condition.as_deref() {
+                        Some(cond) if !eval::matches(cond, self, &scope)? => {}
+                        _ => {
+                            let mut values = row.values.to_vec();
+                            for (pos, (_, expr)) in positions.iter().zip(assignments) {
+                                values[*pos] = eval::evaluate(expr, self, &scope)?;
+                            }
+                            changes.push((row.id, row.values.to_vec(), values));
+                            changed.push(position);
+                        }
+                    }
                 }
-                Ok(ExecutionResult::Delete)
+                self.atomically(table, |ctx| {
+                    for (_, old, new) in &changes {
+                        let (old, new) = (Some(old.as_slice()), Some(new.as_slice()));
+                        ctx.fire(table, TriggerTiming::Before, TriggerEvent::Update, old, new)?;
+                    }
+                    let tbl = ctx.get_table_mut(table)?;
+                    for (id, _, values) in &changes {
+                        tbl.update(*id, values.clone())?;
+                    }
+                    for (id, ..) in &changes {
+                        ctx.check_references(table, *id)?;
+                    }
+                    let old: Vec<_> = changes.iter().map(|x| x.1.clone()).collect();
+                    ctx.check_referenced(table, &old)?;
+                    for (id, old, _) in &changes {
+                        let new = ctx.get_table(table)?.get(*id).map(Cow::into_owned);
+                        let (old, new) = (Some(old.as_slice()), new.as_deref());
+                        ctx.fire(table, TriggerTiming::After, TriggerEvent::Update, old, new)?;
+                    }
+                    Ok(())
+                })?;
+                if self.trigger_depth == 0 {
+                    self.last_update = changed;
+                }
+                Ok(ExecutionResult::Update(changes.len()))
+            }
+            SqlStatement::Copy {
+                table,
+                direction,
+                path,
+                header,
+            } => {
+                let tbl = self.get_table_mut(table)?;
+
+                match direction {
+                    CopyDirection::From => {
+                        let rows = copy::read_csv(tbl, path, *header)?;
+                        // the rows are checked on a copy to leave the table intact on errors
+                        let mut staged = tbl.clone();
+                        let mut ids = vec![];
+                        for (line, row) in rows.iter() {
+                            let id = staged.insert(row.clone()).map_err(|e| {
+                                ExecutionError::ImportError {
+                                    line: *line,
+                                    reason: e.to_string(),
+                                }
+                            })?;
+                            ids.push((*line, id));
+                        }
+                        let original = std::mem::replace(tbl, staged);
+                        // the rows may reference each other, so they are checked once all are in
+                        for (line, id) in ids {
+                            if let Err(e) = self.check_references(table, id) {
+                                self.tables.insert(table.clone(), original);
+                                return Err(ExecutionError::ImportError {
+                                    line,
+                                    reason: e.to_string(),
+                                });
+                            }
+                        }
+                        Ok(ExecutionResult::Copy(rows.len()))
+                    }
+                    CopyDirection::To => {
+                        Ok(ExecutionResult::Copy(copy::write_csv(tbl, path, *header)?))
+                    }
+                }
+            }
+            SqlStatement::Alter { table, action } => {
+                let tbl = self.get_table_mut(table)?;
+
+                match action {
+                    AlterAction::AutoIncrement(next) => tbl.restart_sequence(*next)?,
+                }
+                Ok(ExecutionResult::Alter)
+            }
+            SqlStatement::Analyze { table } => {
+                self.analyze(table.as_ref())?;
+                Ok(ExecutionResult::Analyze)
             }
+            SqlStatement::CreateUser { name, password } => {
+                self.create_user(name, password)?;
+                Ok(ExecutionResult::Create)
+            }
+            SqlStatement::DropUser { name } => {
+                self.drop_user(name)?;
+                Ok(ExecutionResult::Drop)
+            }
+            SqlStatement::Grant {
+                privileges,
+                table,
+                user,
+            } => {
+                self.grant(privileges, table, user, false)?;
+                Ok(ExecutionResult::Grant)
+            }
+            SqlStatement::Revoke {
+                privileges,
+                table,
+                user,
+            } => {
+                self.grant(privileges, table, user, true)?;
+                Ok(ExecutionResult::Revoke)
+            }
+        }
+    }
+    /// Inserts the values into the columns of the table, all of them when none are
+    /// named, and returns the id of the row.
+    fn insert_row(
+        &mut self,
+        table: &Identifier,
+        columns: &[Identifier],
+        values: Vec<SqlValue>,
+    ) -> DbResult<usize, ExecutionError> {
+        let tbl = self.get_table_mut(table)?;
+        let id = if columns.is_empty() {
+            tbl.insert(values)?
+        } else {
+            let positions = columns
+                .iter()
+                .map(|x| tbl.require_column(&x.0))
+                .collect::<Result<Vec<_>, _>>()?;
+            tbl.insert_into(&positions, values)?
+        };
+        if let Err(e) = self.check_references(table, id) {
+            self.get_table_mut(table)?.delete(id);
+            return Err(e);
+        }
+        Ok(id)
+    }
+    /// Applies a change of the table, the tables are left intact when it fails. Only
+    /// the tables the change may reach through cascades and triggers are copied.
+    fn atomically<T>(
+        &mut self,
+        table: &Identifier,
+        change: impl FnOnce(&mut Self) -> DbResult<T, ExecutionError>,
+    ) -> DbResult<T, ExecutionError> {
+        let snapshot: Vec<_> = self
+            .affected_tables(table)
+            .into_iter()
+            .filter_map(|x| self.tables.get(&x).cloned().map(|tbl| (x, tbl)))
+            .collect();
+        let res = change(self);
+        if res.is_err() {
+            self.tables.extend(snapshot);
         }
+        res
+    }
+    fn record(&mut self, input: &str) -> DbResult<(), ExecutionError> {
+        let Some(journal) = self.journal.as_mut() else {
+            return Ok(());
+        };
+        writeln!(journal, "{};", input.trim().trim_end_matches(';'))?;
+        Ok(())
+    }
+    pub(crate) fn get_table(&self, name: &Identifier) -> DbResult<&Table, ExecutionError> {
+        self.tables
+            .get(name)
+            .ok_or_else(|| ExecutionError::TableNotFound(name.0.clone()))
+    }
+    pub(crate) fn get_table_mut(
+        &mut self,
+        name: &Identifier,
+    ) -> DbResult<&mut Table, ExecutionError> {
+        self.tables
+            .get_mut(name)
+            .ok_or_else(|| ExecutionError::TableNotFound(name.0.clone()))
     }
 }
@@ -0,0 +1,299 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::{ExecutionContext, StoredRow},
+    parser::{ColumnDefinition, ForeignKey, Identifier, ReferentialAction, SqlValue},
+};
+use std::{borrow::Cow, cmp::Ordering};
+
+impl ExecutionContext {
+    /// Checks that the referenced columns of a new table exist, are unique and have
+    /// a type the referencing columns can equal. A table may reference itself.
+    pub(crate) fn check_foreign_keys(
+        &self,
+        table: &Identifier,
+        columns: &[ColumnDefinition],
+    ) -> DbResult<(), ExecutionError> {
+        for (child, fk) in columns
+            .iter()
+            .filter_map(|x| Some((x, x.references.as_ref()?)))
+        {
+            let column = if &fk.table == table {
+                columns.iter().find(|x| x.name == fk.column)
+            } else {
+                let parent = self.get_table(&fk.table)?;
+                parent
+                    .column_index(&fk.column.0)
+                    .map(|i| &parent.columns()[i])
+            };
+            let Some(column) = column else {
+                return Err(ExecutionError::ColumnNotFound {
+                    table: fk.table.0.clone(),
+                    column: fk.column.0.clone(),
+                });
+            };
+            if !column.is_unique() {
+                return Err(ExecutionError::InvalidStatement(format!(
+                    "{}.{} is neither UNIQUE nor a PRIMARY KEY, it cannot be referenced",
+                    fk.table, fk.column
+                )));
+            }
+            if !child.data_type.is_comparable(&column.data_type) {
+                return Err(ExecutionError::TypeMismatch {
+                    expected: column.data_type.to_string(),
+                    found: child.data_type.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+    /// Checks that the row of the table references present rows
+    pub(crate) fn check_references(
+        &self,
+        table: &Identifier,
+        id: usize,
+    ) -> DbResult<(), ExecutionError> {
+        // the rows of a restored dump may reference the ones after them
+        if self.restoring {
+            return Ok(());
+        }
+        let tbl = self.get_table(table)?;
+        let Some(values) = tbl.get(id) else {
+            return Ok(());
+        };
+        for (col, value) in tbl.columns().iter().zip(values.iter()) {
+            let Some(fk) = &col.references else {
+                continue;
+            };
+            if *value != SqlValue::Nil && self.find_referenced(fk, value)?.is_none() {
+                return Err(foreign_key_violation(table, &col.name));
+            }
+        }
+        Ok(())
+    }
+    /// Deletes the rows of the table and applies the `ON DELETE` actions of the rows
+    /// referencing them.
+    pub(crate) fn delete_rows(
+        &mut self,
+        table: &Identifier,
+        ids: &[usize],
+    ) -> DbResult<(), ExecutionError> {
+        let tbl = self.get_table_mut(table)?;
+        let removed: Vec<_> = ids.iter().filter_map(|id| tbl.delete(*id)).collect();
+
+        for (child, column, fk) in self.referencing(table) {
+            let ids = self.referencing_rows(table, &removed, &child, column, &fk)?;
+            if ids.is_empty() {
+                continue;
+            }
+            match fk.on_delete {
+                ReferentialAction::Restrict => {
+                    let name = &self.get_table(&child)?.columns()[column].name;
+                    return Err(foreign_key_violation(&child, name));
+                }
+                ReferentialAction::Cascade => self.delete_rows(&child, &ids)?,
+                ReferentialAction::SetNull => {
+                    let tbl = self.get_table_mut(&child)?;
+                    for id in ids {
+                        let Some(mut values) = tbl.get(id).map(Cow::into_owned) else {
+                            continue;
+                        };
+                        values[column] = SqlValue::Nil;
+                        tbl.update(id, values)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Fails when rows still reference the old values of updated rows, as the
+    /// updates of the referenced columns are restricted.
+    pub(crate) fn check_referenced(
+        &self,
+        table: &Identifier,
+        old: &[StoredRow],
+    ) -> DbResult<(), ExecutionError> {
+        for (child, column, fk) in self.referencing(table) {
+            if !self
+                .referencing_rows(table, old, &child, column, &fk)?
+                .is_empty()
+            {
+                let name = &self.get_table(&child)?.columns()[column].name;
+                return Err(foreign_key_violation(&child, name));
+            }
+        }
+        Ok(())
+    }
+
+    /// The tables, and the positions of their columns, referencing the table
+    pub(super) fn referencing(&self, table: &Identifier) -> Vec<(Identifier, usize, ForeignKey)> {
+        let mut found = vec![];
+        for name in self.table_names() {
+            let Some(tbl) = self.table(&name.0) else {
+                continue;
+            };
+            for (i, col) in tbl.columns().iter().enumerate() {
+                match &col.references {
+                    Some(fk) if &fk.table == table => found.push((name.clone(), i, fk.clone())),
+                    _ => {}
+                }
+            }
+        }
+        found
+    }
+    /// The rows of the child table referencing the keys of the `rows`, which are no
+    /// longer in the parent table.
+    fn referencing_rows(
+        &self,
+        table: &Identifier,
+        rows: &[StoredRow],
+        child: &Identifier,
+        column: usize,
+        fk: &ForeignKey,
+    ) -> DbResult<Vec<usize>, ExecutionError> {
+        let key = self.get_table(table)?.require_column(&fk.column.0)?;
+        let mut keys = vec![];
+        for row in rows {
+            let value = &row[key];
+            if *value != SqlValue::Nil && self.find_referenced(fk, value)?.is_none() {
+                keys.push(value);
+            }
+        }
+        if keys.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(self
+            .get_table(child)?
+            .iter()
+            .filter(|row| keys.iter().any(|x| equal(&row.values[column], x)))
+            .map(|row| row.id)
+            .collect())
+    }
+    /// The id of the row holding the value in the referenced column
+    fn find_referenced(
+        &self,
+        fk: &ForeignKey,
+        value: &SqlValue,
+    ) -> DbResult<Option<usize>, ExecutionError> {
+        let parent = self.get_table(&fk.table)?;
+        let key = parent.require_column(&fk.column.0)?;
+        Ok(parent
+            .iter()
+            .find(|row| equal(&row.values[key], value))
+            .map(|row| row.id))
+    }
+}
+
+fn equal(a: &SqlValue, b: &SqlValue) -> bool {
+    a.compare(b) == Some(Ordering::Equal)
+}
+
+fn foreign_key_violation(table: &Identifier, column: &Identifier) -> ExecutionError {
+    ExecutionError::ConstraintViolation {
+        table: table.0.clone(),
+        column: column.0.clone(),
+        constraint: "FOREIGN KEY".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::{ExecutionResult, test_util::context_with};
+
+    /// Users 1 and 2 with an order each, a note of the order of user 1 and an
+    /// audit of user 2
+    fn shop() -> ExecutionContext {
+        context_with(&[
+            "CREATE TABLE users (id INT PRIMARY KEY, name STRING)",
+            "CREATE TABLE orders (id INT PRIMARY KEY, user_id INT REFERENCES users(id) ON DELETE CASCADE)",
+            "CREATE TABLE notes (order_id INT REFERENCES orders(id) ON DELETE SET NULL)",
+            "CREATE TABLE audits (user_id INT REFERENCES users(id))",
+            "INSERT INTO users VALUES (1, 'a')",
+            "INSERT INTO users VALUES (2, 'b')",
+            "INSERT INTO orders VALUES (10, 1)",
+            "INSERT INTO orders VALUES (11, 2)",
+            "INSERT INTO notes VALUES (10)",
+            "INSERT INTO audits VALUES (2)",
+        ])
+    }
+    fn violation(table: &str, column: &str) -> DbResult<ExecutionResult, ExecutionError> {
+        Err(ExecutionError::ConstraintViolation {
+            table: table.into(),
+            column: column.into(),
+            constraint: "FOREIGN KEY".into(),
+        })
+    }
+
+    #[test]
+    fn should_reject_rows_referencing_nothing() {
+        let mut ctx = shop();
+        assert_eq!(
+            ctx.run("INSERT INTO orders VALUES (12, 3)"),
+            violation("orders", "user_id")
+        );
+        assert_eq!(ctx.table("orders").unwrap().len(), 2);
+        assert_eq!(
+            ctx.run("UPDATE orders SET user_id = 3 WHERE id = 10"),
+            violation("orders", "user_id")
+        );
+    }
+
+    #[test]
+    fn should_reject_updates_of_referenced_keys() {
+        let mut ctx = shop();
+        assert_eq!(
+            ctx.run("UPDATE users SET id = 3 WHERE id = 1"),
+            violation("orders", "user_id")
+        );
+    }
+
+    #[test]
+    fn should_cascade_deletes_and_set_nulls() {
+        let mut ctx = shop();
+        // the delete of user 1 cascades to its order and nulls the note of it
+        ctx.run("DELETE users WHERE id = 1").unwrap();
+        assert_eq!(ctx.table("orders").unwrap().len(), 1);
+        assert_eq!(
+            ctx.table("notes").unwrap().last().as_deref(),
+            Some(&vec![SqlValue::Nil])
+        );
+    }
+
+    #[test]
+    fn should_delete_nothing_when_a_row_is_still_referenced() {
+        let mut ctx = shop();
+        // user 2 is still referenced by an audit
+        assert_eq!(ctx.run("DELETE users"), violation("audits", "user_id"));
+        assert_eq!(ctx.table("users").unwrap().len(), 2);
+        assert_eq!(ctx.table("orders").unwrap().len(), 2);
+        assert_eq!(
+            ctx.table("notes").unwrap().last().as_deref(),
+            Some(&vec![SqlValue::Integer(10)])
+        );
+    }
+
+    #[test]
+    fn should_reference_only_primary_keys_of_tables() {
+        let mut ctx = shop();
+        assert!(
+            ctx.run("CREATE TABLE bad (name STRING REFERENCES users(name))")
+                .is_err()
+        );
+        assert!(
+            ctx.run("CREATE TABLE bad (x INT REFERENCES nope(id))")
+                .is_err()
+        );
+        assert_eq!(
+            ctx.run("CREATE TABLE bad (user_id STRING REFERENCES users(id))"),
+            Err(ExecutionError::TypeMismatch {
+                expected: "INT".into(),
+                found: "STRING".into(),
+            })
+        );
+        assert!(ctx.table("bad").is_none());
+        assert!(
+            ctx.run("CREATE TABLE good (user_id BIGINT REFERENCES users(id))")
+                .is_ok()
+        );
+    }
+}
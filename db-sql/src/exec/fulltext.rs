@@ -0,0 +1,195 @@
+use crate::{
+    exec::Table,
+    parser::{Condition, Expr, Identifier, LogicalOperator, SqlValue},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How quickly the score of a word stops growing with its occurrences in a text
+const K1: f64 = 1.2;
+
+/// The lowercase words of the text, split on anything but letters and digits
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Whether the text has all the words of the query, an empty query matches nothing
+pub fn matches(text: &str, query: &str) -> bool {
+    let words = tokenize(text);
+    let terms = tokenize(query);
+    !terms.is_empty() && terms.iter().all(|x| words.contains(x))
+}
+
+/// The relevance of the text to the query, 0 unless it matches. Each word of the
+/// query adds a score which grows with its occurrences but never reaches
+/// `K1 + 1`, so the text depends on nothing else and neither does its rank.
+pub fn rank(text: &str, query: &str) -> f64 {
+    if !matches(text, query) {
+        return 0.0;
+    }
+    let words = tokenize(text);
+    let mut terms = tokenize(query);
+    terms.sort();
+    terms.dedup();
+    terms
+        .iter()
+        .map(|term| {
+            let tf = words.iter().filter(|x| *x == term).count() as f64;
+            tf * (K1 + 1.0) / (tf + K1)
+        })
+        .sum()
+}
+
+/// The index and the words of a `MATCH` of an indexed column of the table, whose
+/// name or alias is `alias`, to a literal
+pub(super) fn text_search<'a>(
+    tbl: &'a Table,
+    alias: &Identifier,
+    expr: &Expr,
+    query: &'a Expr,
+) -> Option<(&'a FullTextIndex, &'a str)> {
+    let (Expr::Column(column), Expr::Literal(SqlValue::String(query))) = (expr, query) else {
+        return None;
+    };
+    if column.qualifier().is_some_and(|x| x != alias.0) {
+        return None;
+    }
+    Some((tbl.text_index(tbl.column_index(column.column())?)?, query))
+}
+
+/// Same as `text_search` for the `MATCH` of a condition, or of one of the
+/// conditions joined by `AND` at its top, all the rows it reads then satisfy
+pub(super) fn find_text_search<'a>(
+    tbl: &'a Table,
+    alias: &Identifier,
+    condition: &'a Condition,
+) -> Option<(&'a FullTextIndex, &'a str)> {
+    match condition {
+        Condition::Match { expr, query } => text_search(tbl, alias, expr, query),
+        Condition::Logical {
+            operator: LogicalOperator::And,
+            conditions,
+        } => conditions
+            .iter()
+            .find_map(|x| find_text_search(tbl, alias, x)),
+        _ => None,
+    }
+}
+
+/// An inverted index of the words of a text column, which answers `MATCH` without
+/// reading the rows
+#[derive(Debug, Clone, PartialEq)]
+pub struct FullTextIndex {
+    name: Identifier,
+    column: usize,
+    /// the rows of each word
+    postings: BTreeMap<String, BTreeSet<usize>>,
+}
+
+impl FullTextIndex {
+    pub fn new(name: Identifier, column: usize) -> Self {
+        Self {
+            name,
+            column,
+            postings: BTreeMap::new(),
+        }
+    }
+    pub fn name(&self) -> &Identifier {
+        &self.name
+    }
+    /// The position of the indexed column
+    pub fn column(&self) -> usize {
+        self.column
+    }
+    /// The number of distinct words
+    pub fn terms(&self) -> usize {
+        self.postings.len()
+    }
+    /// Adds the words of the value of the row, which is not indexed yet
+    pub fn insert(&mut self, id: usize, value: &SqlValue) {
+        let SqlValue::String(text) = value else {
+            return;
+        };
+        for word in tokenize(text) {
+            self.postings.entry(word).or_default().insert(id);
+        }
+    }
+    /// Removes the words of the row, `value` is the one it was indexed with
+    pub fn remove(&mut self, id: usize, value: &SqlValue) {
+        let SqlValue::String(text) = value else {
+            return;
+        };
+        for word in tokenize(text) {
+            if let Some(rows) = self.postings.get_mut(&word) {
+                rows.remove(&id);
+                if rows.is_empty() {
+                    self.postings.remove(&word);
+                }
+            }
+        }
+    }
+    pub fn clear(&mut self) {
+        self.postings.clear();
+    }
+    /// The rows having all the words of the query, in their order. How relevant
+    /// they are is left to `rank`, which gives the same with or without an index.
+    pub fn search(&self, query: &str) -> Vec<usize> {
+        let mut postings = vec![];
+        for term in tokenize(query) {
+            match self.postings.get(&term) {
+                Some(rows) => postings.push(rows),
+                None => return vec![],
+            }
+        }
+        // the rarest word bounds the rows to check
+        postings.sort_by_key(|x| x.len());
+        let Some((rarest, rest)) = postings.split_first() else {
+            return vec![];
+        };
+        rarest
+            .iter()
+            .filter(|id| rest.iter().all(|x| x.contains(id)))
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_search_and_rank_rows() {
+        let text = |x: &str| SqlValue::String(x.into());
+        assert_eq!(
+            tokenize("Rust's B-Tree, 2nd"),
+            ["rust", "s", "b", "tree", "2nd"]
+        );
+        assert!(matches("The Rust Book", "rust BOOK"));
+        assert!(!matches("The Rust Book", "rust guide"));
+        assert!(!matches("The Rust Book", " "));
+        assert_eq!(rank("The Rust Book", "rust"), 1.0);
+        assert_eq!(rank("The Rust Book", "rust book"), 2.0);
+        assert!(rank("rust, Rust and rust", "rust") > rank("Rust and C", "rust"));
+        assert!(rank("rust, Rust and rust", "rust") < K1 + 1.0);
+        assert_eq!(rank("The Rust Book", "rust guide"), 0.0);
+
+        let mut index = FullTextIndex::new("docs_body".into(), 1);
+        index.insert(0, &text("rust is a systems language"));
+        index.insert(1, &text("rust rust rust"));
+        index.insert(2, &text("a language for the web"));
+        index.insert(3, &SqlValue::Nil);
+        assert_eq!(index.search("Rust"), [0, 1]);
+        assert_eq!(index.search("language"), [0, 2]);
+        assert_eq!(index.search("rust language"), [0]);
+        assert_eq!(index.search("go"), [] as [usize; 0]);
+
+        index.remove(1, &text("rust rust rust"));
+        index.insert(1, &text("web"));
+        assert_eq!(index.search("rust"), [0]);
+        assert_eq!(index.search("web"), [1, 2]);
+        assert_eq!(index.terms(), 8);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::fulltext,
+    types::{Json, SqlDataType, SqlValue, Uuid},
+};
+
+/// Calls the built-in function, whose name is case insensitive, with the evaluated
+/// arguments.
+pub fn call(name: &str, args: Vec<SqlValue>) -> DbResult<SqlValue, ExecutionError> {
+    let arity = |expected: usize| {
+        if args.len() == expected {
+            Ok(())
+        } else {
+            Err(ExecutionError::ArgumentCountMismatch {
+                function: name.to_string(),
+                expected,
+                found: args.len(),
+            })
+        }
+    };
+    match name.to_lowercase().as_str() {
+        "gen_random_uuid" => {
+            arity(0)?;
+            Ok(SqlValue::Uuid(Uuid::new_v4()))
+        }
+        "json_extract" => {
+            arity(2)?;
+            let mut args = args.into_iter();
+            let (Some(json), Some(path)) = (json(args.next())?, args.next()) else {
+                return Ok(SqlValue::Nil);
+            };
+            Ok(at_path(&json, path)?.map_or(SqlValue::Nil, Json::to_value))
+        }
+        "json_array_length" => {
+            if args.len() != 2 {
+                arity(1)?;
+            }
+            let mut args = args.into_iter();
+            let Some(json) = json(args.next())? else {
+                return Ok(SqlValue::Nil);
+            };
+            let found = match args.next() {
+                Some(path) => at_path(&json, path)?,
+                None => Some(&json),
+            };
+            Ok(match found {
+                Some(Json::Array(items)) => SqlValue::Integer(items.len() as i64),
+                Some(_) => SqlValue::Integer(0),
+                None => SqlValue::Nil,
+            })
+        }
+        "rank" => {
+            arity(2)?;
+            match (&args[0], &args[1]) {
+                (SqlValue::String(text), SqlValue::String(query)) => {
+                    Ok(SqlValue::Float(fulltext::rank(text, query)))
+                }
+                (SqlValue::Nil, _) | (_, SqlValue::Nil) => Ok(SqlValue::Nil),
+                (SqlValue::String(_), x) | (x, _) => Err(ExecutionError::TypeMismatch {
+                    expected: "STRING".into(),
+                    found: x.type_name().to_string(),
+                }),
+            }
+        }
+        _ => Err(ExecutionError::UnknownFunction(name.to_string())),
+    }
+}
+
+/// `expr -> key` and `expr ->> key`, the key is the name of a field, the index of
+/// an item or a path like `$.a[0]`. The document is read as JSON by `->` and as a SQL
+/// value by `->>`, which gives arrays and objects as text.
+pub fn json_field(
+    value: SqlValue,
+    key: SqlValue,
+    as_text: bool,
+) -> DbResult<SqlValue, ExecutionError> {
+    let Some(json) = json(Some(value))? else {
+        return Ok(SqlValue::Nil);
+    };
+    Ok(match (lookup(&json, key)?, as_text) {
+        (None, _) => SqlValue::Nil,
+        (Some(x), false) => SqlValue::Json(x.clone()),
+        (Some(x @ (Json::Array(_) | Json::Object(_))), true) => SqlValue::String(x.to_string()),
+        (Some(x), true) => x.to_value(),
+    })
+}
+
+/// The argument read as a JSON document, strings are parsed and nil is `None`
+fn json(value: Option<SqlValue>) -> DbResult<Option<Json>, ExecutionError> {
+    match SqlDataType::Json.coerce(value.unwrap_or(SqlValue::Nil))? {
+        SqlValue::Json(x) => Ok(Some(x)),
+        _ => Ok(None),
+    }
+}
+/// The part of the document at the key, see [`json_field`]
+fn lookup(json: &Json, key: SqlValue) -> DbResult<Option<&Json>, ExecutionError> {
+    match key {
+        SqlValue::String(key) if !key.starts_with('$') => Ok(json.field(&key)),
+        SqlValue::Integer(i) => Ok(json.index(i)),
+        x => at_path(json, x),
+    }
+}
+/// The part of the document at a path like `$.a[0]`
+fn at_path(json: &Json, path: SqlValue) -> DbResult<Option<&Json>, ExecutionError> {
+    match path {
+        SqlValue::String(path) => json.path(&path).map_err(ExecutionError::InvalidStatement),
+        SqlValue::Nil => Ok(None),
+        x => Err(ExecutionError::TypeMismatch {
+            expected: "STRING".into(),
+            found: x.type_name().to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::{
+        ExecutionContext,
+        test_util::{context_with, rows},
+    };
+
+    #[test]
+    fn should_generate_random_uuids() {
+        let SqlValue::Uuid(a) = call("GEN_RANDOM_UUID", vec![]).unwrap() else {
+            panic!("expected a UUID");
+        };
+        assert_eq!(a.get_version_num(), 4);
+        assert_ne!(call("gen_random_uuid", vec![]), Ok(SqlValue::Uuid(a)));
+    }
+
+    #[test]
+    fn should_check_the_arguments() {
+        assert_eq!(
+            call("gen_random_uuid", vec![SqlValue::Nil]),
+            Err(ExecutionError::ArgumentCountMismatch {
+                function: "gen_random_uuid".into(),
+                expected: 0,
+                found: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn should_reject_unknown_functions() {
+        assert_eq!(
+            call("nope", vec![]),
+            Err(ExecutionError::UnknownFunction("nope".into()))
+        );
+    }
+
+    #[test]
+    fn should_rank_texts() {
+        let rank = |args: Vec<SqlValue>| call("RANK", args);
+        assert_eq!(
+            rank(vec![string("Rust and rust"), string("rust")]),
+            Ok(SqlValue::Float(2.0 * 2.2 / 3.2))
+        );
+        assert_eq!(
+            rank(vec![string("Rust"), string("go")]),
+            Ok(SqlValue::Float(0.0))
+        );
+        assert_eq!(rank(vec![SqlValue::Nil, string("go")]), Ok(SqlValue::Nil));
+        assert_eq!(
+            rank(vec![string("Rust"), SqlValue::Integer(1)]),
+            Err(ExecutionError::TypeMismatch {
+                expected: "STRING".into(),
+                found: "INT".into(),
+            })
+        );
+    }
+
+    /// A click with tags and a position, a view without tags and an event without payload
+    fn events() -> ExecutionContext {
+        context_with(&[
+            "CREATE TABLE events (id INT, payload JSON)",
+            r#"INSERT INTO events VALUES (1, '{"kind": "click", "tags": ["a", "b"], "at": {"x": 3}}')"#,
+            r#"INSERT INTO events VALUES (2, '{"kind": "view", "tags": []}')"#,
+            "INSERT INTO events VALUES (3, nil)",
+        ])
+    }
+    fn string(x: &str) -> SqlValue {
+        SqlValue::String(x.into())
+    }
+    fn json(x: &str) -> SqlValue {
+        SqlValue::Json(x.parse().unwrap())
+    }
+
+    #[test]
+    fn should_reject_invalid_json() {
+        let mut ctx = events();
+        assert_eq!(
+            ctx.run("INSERT INTO events VALUES (4, '{kind: 1}')"),
+            Err(ExecutionError::InvalidValue {
+                data_type: "JSON".into(),
+                value: "{kind: 1}".into(),
+            })
+        );
+        let nested = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(matches!(
+            ctx.run(&format!("INSERT INTO events VALUES (4, '{nested}')")),
+            Err(ExecutionError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn should_read_json_with_arrows() {
+        let mut ctx = events();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id, payload -> 'tags' -> 0, payload ->> 'tags', json_array_length(payload, '$.tags') FROM events WHERE payload ->> 'kind' = 'click'"
+            ),
+            Ok(vec![vec![
+                SqlValue::Integer(1),
+                json(r#""a""#),
+                string(r#"["a","b"]"#),
+                SqlValue::Integer(2),
+            ]])
+        );
+    }
+
+    #[test]
+    fn should_extract_json_paths() {
+        let mut ctx = events();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT json_extract(payload, '$.at.x') + 1, json_extract(payload, '$.at'), payload ->> '$.tags[#-1]' FROM events"
+            ),
+            Ok(vec![
+                vec![SqlValue::Integer(4), json(r#"{"x":3}"#), string("b")],
+                vec![SqlValue::Nil, SqlValue::Nil, SqlValue::Nil],
+                vec![SqlValue::Nil, SqlValue::Nil, SqlValue::Nil],
+            ])
+        );
+    }
+
+    #[test]
+    fn should_filter_by_json_array_length() {
+        let mut ctx = events();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id FROM events WHERE json_array_length(payload -> 'tags') = 0"
+            ),
+            Ok(vec![vec![SqlValue::Integer(2)]])
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_json_paths() {
+        let mut ctx = events();
+        assert!(
+            ctx.run("SELECT json_extract(payload, 'tags') FROM events")
+                .is_err()
+        );
+    }
+}
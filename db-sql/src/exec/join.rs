@@ -0,0 +1,424 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::{
+        ExecutionContext, StoredRow, Table,
+        analyze::{INDEX_ROW_COST, JoinEdge, distinct_values, estimate_rows, join_order},
+        btree::Key,
+        eval::{self, Scope},
+        query::Relation,
+    },
+    parser::{Condition, Expr, Identifier, LogicalOperator, Operator, SqlValue, TableRef},
+};
+use std::{borrow::Cow, collections::BTreeMap};
+
+/// A table of a join with the conditions which read only its columns
+struct Input<'a, 'c> {
+    from: &'c TableRef,
+    columns: Vec<Identifier>,
+    /// the table of a name, read when it is joined
+    table: Option<&'a Table>,
+    /// the rows of a view, CTE or derived table, which are read before the join
+    rows: Vec<Cow<'a, StoredRow>>,
+    conditions: Vec<&'c Condition>,
+    /// the number of rows the conditions are estimated to match
+    estimate: f64,
+}
+
+/// `a.x = b.y` of the columns of two tables of a join
+struct Equality<'c> {
+    condition: &'c Condition,
+    /// the position of each table with the position of its column
+    sides: [(usize, usize); 2],
+    selectivity: f64,
+}
+
+impl ExecutionContext {
+    /// The rows of the tables of the join which match its `ON` conditions and the
+    /// condition, the columns of the tables in their order qualified by their
+    /// names. The conditions which read a single table select its rows before they
+    /// are joined, in the order the planner estimates keeps the fewest rows. A
+    /// table is read through the index of the column an equality joins when the
+    /// lookups cost less than a scan.
+    pub(super) fn join(
+        &self,
+        from: &TableRef,
+        condition: Option<&Condition>,
+        outer: Option<&Scope>,
+    ) -> DbResult<Relation<'_>, ExecutionError> {
+        let leaves = from.leaves();
+        let mut inputs = vec![];
+        for (i, leaf) in leaves.iter().enumerate() {
+            let name = leaf.name();
+            if leaves[..i].iter().any(|x| x.name() == name) {
+                return Err(ExecutionError::InvalidStatement(format!(
+                    "table {name} is joined more than once, each needs its own alias"
+                )));
+            }
+            inputs.push(self.join_input(leaf, outer)?);
+        }
+
+        let mut equalities = vec![];
+        let mut others = vec![];
+        let conditions = from.conditions().into_iter().chain(condition);
+        for condition in conditions.flat_map(|x| x.conjuncts()) {
+            let tables = tables_of(condition, &inputs);
+            match tables[..] {
+                // a condition of the outer query only is checked with the first table
+                [] => inputs[0].conditions.push(condition),
+                [i] => inputs[i].conditions.push(condition),
+                _ => match equality(condition, &inputs) {
+                    Some(sides) => equalities.push(Equality {
+                        condition,
+                        sides,
+                        selectivity: 0.0,
+                    }),
+                    None => others.push((condition, tables)),
+                },
+            }
+        }
+        for input in &mut inputs {
+            match input.table {
+                Some(tbl) => {
+                    input.estimate = estimate_rows(tbl, input.from.name(), &input.conditions)
+                }
+                None => {
+                    let rows = std::mem::take(&mut input.rows);
+                    for values in rows {
+                        if self.matches_all(input, &values, outer)? {
+                            input.rows.push(values);
+                        }
+                    }
+                    input.estimate = input.rows.len() as f64;
+                }
+            }
+        }
+        for x in &mut equalities {
+            let distinct = x.sides.map(|(i, column)| match inputs[i].table {
+                Some(tbl) => distinct_values(tbl, column),
+                None => inputs[i].rows.len().max(1) as f64,
+            });
+            x.selectivity = 1.0 / distinct[0].max(distinct[1]);
+        }
+
+        let estimates: Vec<_> = inputs.iter().map(|x| x.estimate).collect();
+        let edges: Vec<_> = equalities
+            .iter()
+            .map(|x| JoinEdge {
+                tables: (x.sides[0].0, x.sides[1].0),
+                selectivity: x.selectivity,
+            })
+            .collect();
+        // the tables joined so far, the values of their columns one after the other
+        let mut joined: Vec<usize> = vec![];
+        let mut columns: Vec<Identifier> = vec![];
+        let mut rows: Vec<StoredRow> = vec![vec![]];
+        let mut applied = vec![false; equalities.len()];
+        for next in join_order(&estimates, &edges) {
+            let offset = |table: usize| {
+                let i = joined.iter().position(|x| *x == table).unwrap_or_default();
+                joined[..i]
+                    .iter()
+                    .map(|x| inputs[*x].columns.len())
+                    .sum::<usize>()
+            };
+            // the most selective equality with a joined table gives the key
+            let key = equalities
+                .iter()
+                .enumerate()
+                .filter_map(|(e, x)| {
+                    let [a, b] = x.sides;
+                    match (joined.contains(&a.0), joined.contains(&b.0)) {
+                        (true, false) if b.0 == next => Some((e, b.1, offset(a.0) + a.1)),
+                        (false, true) if a.0 == next => Some((e, a.1, offset(b.0) + b.1)),
+                        _ => None,
+                    }
+                })
+                .min_by(|a, b| {
+                    let selectivity = |x: &(usize, _, _)| equalities[x.0].selectivity;
+                    selectivity(a).total_cmp(&selectivity(b))
+                });
+            let input = &inputs[next];
+            rows = match key {
+                Some((e, column, value)) => {
+                    applied[e] = true;
+                    match self.lookup_index(input, column, rows.len()) {
+                        Some(tbl) => self.join_lookups(input, tbl, column, value, rows, outer)?,
+                        None => {
+                            let right = self.join_rows(input, outer)?;
+                            join_keyed(rows, &right, column, value)
+                        }
+                    }
+                }
+                None => {
+                    let right = self.join_rows(input, outer)?;
+                    let mut product = Vec::with_capacity(rows.len() * right.len());
+                    for left in &rows {
+                        for values in &right {
+                            product.push([left.as_slice(), values].concat());
+                        }
+                    }
+                    product
+                }
+            };
+            joined.push(next);
+            let name = &input.from.name().0;
+            columns.extend(
+                input
+                    .columns
+                    .iter()
+                    .map(|x| Identifier(format!("{name}.{}", x.column()))),
+            );
+
+            // the conditions of several tables are checked once all of them are joined
+            let mut checks: Vec<&Condition> = vec![];
+            for (e, x) in equalities.iter().enumerate() {
+                if !applied[e] && x.sides.iter().all(|x| joined.contains(&x.0)) {
+                    applied[e] = true;
+                    checks.push(x.condition);
+                }
+            }
+            others.retain(|(condition, tables)| {
+                let ready = tables.iter().all(|x| joined.contains(x));
+                if ready {
+                    checks.push(condition);
+                }
+                !ready
+            });
+            if !checks.is_empty() {
+                let table = inputs[joined[0]].from.name();
+                let mut matching = vec![];
+                for values in rows {
+                    let scope = Scope {
+                        table,
+                        columns: &columns,
+                        values: &values,
+                        outer,
+                    };
+                    if all(&checks, self, &scope)? {
+                        matching.push(values);
+                    }
+                }
+                rows = matching;
+            }
+        }
+
+        // the columns of the tables are put back in the order of the `FROM`
+        let mut positions = vec![];
+        for i in 0..inputs.len() {
+            let j = joined.iter().position(|x| *x == i).unwrap_or_default();
+            let start: usize = joined[..j].iter().map(|x| inputs[*x].columns.len()).sum();
+            positions.extend(start..start + inputs[i].columns.len());
+        }
+        Ok(Relation {
+            columns: positions.iter().map(|x| columns[*x].clone()).collect(),
+            rows: rows
+                .into_iter()
+                .map(|mut values| {
+                    let row = positions
+                        .iter()
+                        .map(|x| std::mem::replace(&mut values[*x], SqlValue::Nil))
+                        .collect();
+                    Cow::Owned(row)
+                })
+                .collect(),
+        })
+    }
+
+    fn join_input<'c>(
+        &self,
+        from: &'c TableRef,
+        outer: Option<&Scope>,
+    ) -> DbResult<Input<'_, 'c>, ExecutionError> {
+        let mut input = Input {
+            from,
+            columns: vec![],
+            table: None,
+            rows: vec![],
+            conditions: vec![],
+            estimate: 0.0,
+        };
+        match from {
+            // a CTE hides the table of its name
+            TableRef::Table { name, .. }
+                if !self.ctes.borrow().iter().any(|x| &x.0 == name)
+                    && let Some(tbl) = self.table(&name.0) =>
+            {
+                input.columns = tbl.column_names();
+                input.table = Some(tbl);
+            }
+            _ => {
+                let relation = self.relation(from, outer)?;
+                input.columns = relation.columns;
+                input.rows = relation.rows;
+            }
+        }
+        Ok(input)
+    }
+    /// The table of the input to look the rows up in by the index of the column,
+    /// for each of the `rows` joined so far, when it costs less than a scan
+    fn lookup_index<'a>(
+        &self,
+        input: &Input<'a, '_>,
+        column: usize,
+        rows: usize,
+    ) -> Option<&'a Table> {
+        let tbl = input.table?;
+        tbl.btree_index(column)?;
+        let size = tbl.statistics().map_or(tbl.len(), |x| x.rows) as f64;
+        let matches = size / distinct_values(tbl, column);
+        let lookups = rows as f64 * (1.0 + matches * INDEX_ROW_COST);
+        (lookups < size + rows as f64).then_some(tbl)
+    }
+    /// Joins the rows to those of the table whose column equals their value at the
+    /// position, looked up in the index of the column
+    fn join_lookups(
+        &self,
+        input: &Input,
+        tbl: &Table,
+        column: usize,
+        value: usize,
+        rows: Vec<StoredRow>,
+        outer: Option<&Scope>,
+    ) -> DbResult<Vec<StoredRow>, ExecutionError> {
+        let Some(index) = tbl.btree_index(column) else {
+            return Ok(vec![]);
+        };
+        self.index_hit();
+        let mut joined = vec![];
+        for left in rows {
+            for id in index.lookup(Operator::Equal, &left[value]) {
+                if let Some(values) = tbl.get(id)
+                    && self.matches_all(input, &values, outer)?
+                {
+                    joined.push([left.as_slice(), &values].concat());
+                }
+            }
+        }
+        Ok(joined)
+    }
+    /// The rows of the input which match its conditions, a table chooses between
+    /// its indexes and a scan
+    fn join_rows<'a>(
+        &'a self,
+        input: &Input<'a, '_>,
+        outer: Option<&Scope>,
+    ) -> DbResult<Vec<Cow<'a, StoredRow>>, ExecutionError> {
+        if input.table.is_none() {
+            return Ok(input.rows.clone());
+        }
+        let condition = match &input.conditions[..] {
+            [] => None,
+            [x] => Some((*x).clone()),
+            conditions => Some(Condition::Logical {
+                operator: LogicalOperator::And,
+                conditions: conditions.iter().map(|x| (*x).clone()).collect(),
+            }),
+        };
+        Ok(self.filter(input.from, condition.as_ref(), outer)?.rows)
+    }
+    /// Whether the values of a row of the input match all its conditions
+    fn matches_all(
+        &self,
+        input: &Input,
+        values: &[SqlValue],
+        outer: Option<&Scope>,
+    ) -> DbResult<bool, ExecutionError> {
+        let scope = Scope {
+            table: input.from.name(),
+            columns: &input.columns,
+            values,
+            outer,
+        };
+        all(&input.conditions, self, &scope)
+    }
+}
+
+fn all(
+    conditions: &[&Condition],
+    ctx: &ExecutionContext,
+    scope: &Scope,
+) -> DbResult<bool, ExecutionError> {
+    for condition in conditions {
+        if !eval::matches(condition, ctx, scope)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Joins the rows to those of the right table whose column equals their value at
+/// the position, found by the values of the column. Nil equals nothing.
+fn join_keyed(
+    rows: Vec<StoredRow>,
+    right: &[Cow<StoredRow>],
+    column: usize,
+    value: usize,
+) -> Vec<StoredRow> {
+    let mut keys: BTreeMap<Key, Vec<&StoredRow>> = BTreeMap::new();
+    for values in right {
+        if values[column] != SqlValue::Nil {
+            keys.entry(Key(values[column].clone()))
+                .or_default()
+                .push(values);
+        }
+    }
+    let mut joined = vec![];
+    for left in rows {
+        if left[value] == SqlValue::Nil {
+            continue;
+        }
+        for values in keys.get(&Key(left[value].clone())).into_iter().flatten() {
+            joined.push([left.as_slice(), values].concat());
+        }
+    }
+    joined
+}
+
+/// The tables of the join which have the column, with its position among theirs
+fn owners(column: &Identifier, inputs: &[Input]) -> Vec<(usize, usize)> {
+    inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| column.qualifier().is_none_or(|q| q == x.from.name().0))
+        .filter_map(|(i, x)| {
+            let j = x
+                .columns
+                .iter()
+                .position(|c| c.column() == column.column())?;
+            Some((i, j))
+        })
+        .collect()
+}
+
+/// The positions of the tables of the join the condition reads, all of them for a
+/// condition with subqueries, which may read any
+fn tables_of(condition: &Condition, inputs: &[Input]) -> Vec<usize> {
+    if !condition.subqueries().is_empty() {
+        return (0..inputs.len()).collect();
+    }
+    let mut tables: Vec<usize> = condition
+        .columns()
+        .into_iter()
+        .flat_map(|x| owners(x, inputs))
+        .map(|x| x.0)
+        .collect();
+    tables.sort_unstable();
+    tables.dedup();
+    tables
+}
+
+/// The columns of the two tables an equality of their columns joins
+fn equality(condition: &Condition, inputs: &[Input]) -> Option<[(usize, usize); 2]> {
+    let Condition::Comparison {
+        left: Expr::Column(left),
+        operator: Operator::Equal,
+        right: Expr::Column(right),
+    } = condition
+    else {
+        return None;
+    };
+    match (&owners(left, inputs)[..], &owners(right, inputs)[..]) {
+        ([a], [b]) if a.0 != b.0 => Some([*a, *b]),
+        _ => None,
+    }
+}
@@ -1,5 +1,32 @@
+mod analyze;
+mod btree;
+mod catalog;
+mod columnar;
+mod copy;
+mod dump;
+mod eval;
+#[allow(clippy::module_inception)]
 mod exec;
+mod foreign_key;
+mod fulltext;
+mod function;
+mod join;
+mod query;
+mod result;
+mod stats;
 mod table;
+#[cfg(test)]
+mod test_util;
+mod trigger;
+mod user;
+mod window;
 
+pub use analyze::{ColumnStatistics, TableStatistics};
+pub use btree::BTreeIndex;
+pub use columnar::{BATCH_SIZE, Batch, Batches, Column, ColumnStore};
 pub use exec::*;
+pub use fulltext::FullTextIndex;
+pub use result::*;
+pub use stats::Statistics;
 pub use table::*;
+pub use user::User;
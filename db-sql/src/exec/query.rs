@@ -0,0 +1,1062 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::{
+        ExecutionContext, ResultSet, StoredRow, eval, eval::Scope, fulltext, window::Aggregate,
+    },
+    parser::{
+        Condition, Cte, Expr, Identifier, Projection, Query, Select, SetOperator, SqlValue,
+        TableRef,
+    },
+};
+use std::{borrow::Cow, cmp::Ordering};
+
+/// How many times the recursive part of a CTE may run before it is given up
+const MAX_RECURSION: usize = 1000;
+
+/// The rows of a `FROM` source with the names of their columns
+pub(super) struct Relation<'a> {
+    pub columns: Vec<Identifier>,
+    pub rows: Vec<Cow<'a, StoredRow>>,
+}
+
+impl From<ResultSet> for Relation<'_> {
+    fn from(rs: ResultSet) -> Self {
+        Relation {
+            columns: rs.columns.into_iter().map(Identifier).collect(),
+            rows: rs.rows.into_iter().map(Cow::Owned).collect(),
+        }
+    }
+}
+
+impl ExecutionContext {
+    /// Runs the query, `outer` is the row of the enclosing query when it is a
+    /// correlated subquery.
+    pub(crate) fn query(
+        &self,
+        query: &Query,
+        outer: Option<&Scope>,
+    ) -> DbResult<ResultSet, ExecutionError> {
+        let (left, operator, right) = match query {
+            Query::Select(select) => return self.select(select, outer),
+            Query::Compound {
+                left,
+                operator,
+                right,
+            } => (left, *operator, right),
+            Query::With {
+                recursive,
+                ctes,
+                body,
+            } => {
+                let depth = self.ctes.borrow().len();
+                let rs = self.with(*recursive, ctes, body, outer);
+                self.ctes.borrow_mut().truncate(depth);
+                return rs;
+            }
+        };
+        let mut left = self.query(left, outer)?;
+        let right = self.select(right, outer)?;
+        check_compatible(operator, &left, &right)?;
+
+        let rows = std::mem::take(&mut left.rows);
+        left.rows = match operator {
+            SetOperator::UnionAll => [rows, right.rows].concat(),
+            SetOperator::Union => distinct([rows, right.rows].concat()),
+            SetOperator::Intersect => distinct(rows)
+                .into_iter()
+                .filter(|x| right.rows.iter().any(|y| same_row(x, y)))
+                .collect(),
+            SetOperator::Except => distinct(rows)
+                .into_iter()
+                .filter(|x| !right.rows.iter().any(|y| same_row(x, y)))
+                .collect(),
+        };
+        Ok(left)
+    }
+    /// Binds the rows of the CTEs in order, then runs the body. Two CTEs of a
+    /// clause cannot have the same name.
+    fn with(
+        &self,
+        recursive: bool,
+        ctes: &[Cte],
+        body: &Query,
+        outer: Option<&Scope>,
+    ) -> DbResult<ResultSet, ExecutionError> {
+        for (i, cte) in ctes.iter().enumerate() {
+            if ctes[..i].iter().any(|x| x.name == cte.name) {
+                return Err(ExecutionError::InvalidStatement(format!(
+                    "WITH query name {} specified more than once",
+                    cte.name
+                )));
+            }
+        }
+        for cte in ctes {
+            let rs = if recursive && cte.query.tables().contains(&&cte.name) {
+                self.recursive(cte, outer)?
+            } else {
+                rename(self.query(&cte.query, outer)?, cte)?
+            };
+            self.ctes.borrow_mut().push((cte.name.clone(), rs));
+        }
+        self.query(body, outer)
+    }
+    /// Runs the anchor of a recursive CTE, then its recursive part on the rows
+    /// found by the last run until it finds no new ones.
+    fn recursive(&self, cte: &Cte, outer: Option<&Scope>) -> DbResult<ResultSet, ExecutionError> {
+        let Query::Compound {
+            left,
+            operator: operator @ (SetOperator::Union | SetOperator::UnionAll),
+            right,
+        } = &cte.query
+        else {
+            return Err(ExecutionError::InvalidStatement(format!(
+                "recursive query {} must be an anchor query UNION [ALL] a recursive one",
+                cte.name
+            )));
+        };
+        let mut result = rename(self.query(left, outer)?, cte)?;
+        if *operator == SetOperator::Union {
+            result.rows = distinct(result.rows);
+        }
+        let mut working = result.clone();
+        for _ in 0..MAX_RECURSION {
+            self.ctes.borrow_mut().push((cte.name.clone(), working));
+            let step = self.select(right, outer);
+            self.ctes.borrow_mut().pop();
+            let mut step = step?;
+            check_compatible(*operator, &result, &step)?;
+            if *operator == SetOperator::Union {
+                step.rows = distinct(step.rows);
+                step.rows
+                    .retain(|x| !result.rows.iter().any(|y| same_row(x, y)));
+            }
+            if step.rows.is_empty() {
+                return Ok(result);
+            }
+            result.rows.extend(step.rows.iter().cloned());
+            working = ResultSet {
+                columns: result.columns.clone(),
+                rows: step.rows,
+            };
+        }
+        Err(ExecutionError::InvalidStatement(format!(
+            "recursive query {} did not end after {} iterations",
+            cte.name, MAX_RECURSION
+        )))
+    }
+    fn select(
+        &self,
+        select: &Select,
+        outer: Option<&Scope>,
+    ) -> DbResult<ResultSet, ExecutionError> {
+        if let Some(rs) = self.table_aggregates(select, outer)? {
+            return Ok(rs);
+        }
+        let relation = match &select.from {
+            TableRef::Join { .. } => self.join(&select.from, select.condition.as_ref(), outer)?,
+            from => self.filter(from, select.condition.as_ref(), outer)?,
+        };
+        let rows: Vec<_> = relation.rows.iter().map(|x| x.as_ref()).collect();
+        let table = select.from.name();
+        let mut rs = self.project(table, &relation.columns, &select.columns, &rows, outer)?;
+        if select.distinct {
+            rs.rows = distinct(rs.rows);
+        }
+        Ok(rs)
+    }
+    /// Computes the select list for the rows, in the order it was asked for.
+    pub(crate) fn project(
+        &self,
+        table: &Identifier,
+        columns: &[Identifier],
+        projections: &[Projection],
+        rows: &[&StoredRow],
+        outer: Option<&Scope>,
+    ) -> DbResult<ResultSet, ExecutionError> {
+        let mut names = vec![];
+        let mut exprs = vec![];
+        // unknown columns are reported even when there are no rows
+        let nils = vec![SqlValue::Nil; columns.len()];
+        let empty = Scope {
+            table,
+            columns,
+            values: &nils,
+            outer,
+        };
+        for col in projections {
+            match col {
+                Projection::All => {
+                    for c in columns {
+                        // the columns of a join are named without their tables
+                        names.push(c.column().to_string());
+                        exprs.push(Expr::Column(c.clone()));
+                    }
+                }
+                Projection::Expr { expr, .. } => {
+                    for column in expr.columns() {
+                        empty.resolve(column)?;
+                    }
+                    names.push(col.name());
+                    exprs.push(expr.clone());
+                }
+            }
+        }
+
+        // the window functions are computed over all the rows, then read as the
+        // columns after those of the rows
+        let mut windows = vec![];
+        for expr in &exprs {
+            for w in expr.windows() {
+                if !windows.contains(&w) {
+                    windows.push(w);
+                }
+            }
+        }
+        let mut aggregates = vec![];
+        for expr in &exprs {
+            for a in expr.aggregates() {
+                if !aggregates.contains(&a) {
+                    aggregates.push(a);
+                }
+            }
+        }
+        if !aggregates.is_empty() {
+            if !windows.is_empty() {
+                return Err(ExecutionError::InvalidStatement(
+                    "window functions cannot be used with aggregate functions".into(),
+                ));
+            }
+            let mut values = vec![];
+            for a in &aggregates {
+                values.push(self.aggregate_rows(a, table, columns, rows, outer)?);
+            }
+            return self.aggregated(table, columns, names, &exprs, &aggregates, values, outer);
+        }
+
+        let mut columns = columns.to_vec();
+        let mut rows: Vec<Cow<StoredRow>> = rows.iter().map(|x| Cow::Borrowed(*x)).collect();
+        if !windows.is_empty() {
+            let all: Vec<&StoredRow> = rows.iter().map(|x| x.as_ref()).collect();
+            let mut computed = vec![];
+            for w in &windows {
+                computed.push(self.window(w, table, &columns, &all, outer)?);
+            }
+            for (i, row) in rows.iter_mut().enumerate() {
+                row.to_mut().extend(computed.iter().map(|x| x[i].clone()));
+            }
+            columns.extend(windows.iter().map(|x| Identifier(x.to_string())));
+        }
+
+        let mut rs = ResultSet::new(names);
+        for values in &rows {
+            let scope = Scope {
+                table,
+                columns: &columns,
+                values,
+                outer,
+            };
+            let values = exprs
+                .iter()
+                .map(|x| eval::evaluate(x, self, &scope))
+                .collect::<Result<_, _>>()?;
+            rs.rows.push(values);
+        }
+        Ok(rs)
+    }
+
+    /// Computes an aggregate of the select list over the rows
+    fn aggregate_rows(
+        &self,
+        aggregate: &Expr,
+        table: &Identifier,
+        columns: &[Identifier],
+        rows: &[&StoredRow],
+        outer: Option<&Scope>,
+    ) -> DbResult<SqlValue, ExecutionError> {
+        let Expr::Aggregate { function, args } = aggregate else {
+            return Err(ExecutionError::InvalidStatement(format!(
+                "{aggregate} is not an aggregate function"
+            )));
+        };
+        let arg = args.first();
+        if arg.is_some_and(|x| !x.aggregates().is_empty() || !x.windows().is_empty()) {
+            return Err(ExecutionError::InvalidStatement(format!(
+                "{aggregate} cannot have an aggregate or window function as argument"
+            )));
+        }
+        let name = function.0.to_lowercase();
+        let mut result = Aggregate::default();
+        for values in rows {
+            let scope = Scope {
+                table,
+                columns,
+                values,
+                outer,
+            };
+            let value = arg.map(|x| eval::evaluate(x, self, &scope)).transpose()?;
+            result.add(&name, value.as_ref())?;
+        }
+        result.value(&name)
+    }
+    /// The single row of a select list of aggregates, from their computed values.
+    /// The columns of the rows can only be read by the aggregates, as there is no
+    /// `GROUP BY`.
+    #[allow(clippy::too_many_arguments)]
+    fn aggregated(
+        &self,
+        table: &Identifier,
+        columns: &[Identifier],
+        names: Vec<String>,
+        exprs: &[Expr],
+        aggregates: &[&Expr],
+        values: Vec<SqlValue>,
+        outer: Option<&Scope>,
+    ) -> DbResult<ResultSet, ExecutionError> {
+        let row = Scope {
+            table,
+            columns,
+            values: &[],
+            outer: None,
+        };
+        for expr in exprs {
+            let aggregated: Vec<_> = expr.aggregates().iter().flat_map(|x| x.columns()).collect();
+            for column in expr.columns() {
+                if !aggregated.iter().any(|x| std::ptr::eq(*x, column))
+                    && row.position(column)?.is_some()
+                {
+                    return Err(ExecutionError::InvalidStatement(format!(
+                        "column {column} must be used in an aggregate function"
+                    )));
+                }
+            }
+        }
+        let computed: Vec<_> = aggregates
+            .iter()
+            .map(|x| Identifier(x.to_string()))
+            .collect();
+        let scope = Scope {
+            table,
+            columns: &computed,
+            values: &values,
+            outer,
+        };
+        let mut rs = ResultSet::new(names);
+        rs.rows.push(
+            exprs
+                .iter()
+                .map(|x| eval::evaluate(x, self, &scope))
+                .collect::<Result<_, _>>()?,
+        );
+        Ok(rs)
+    }
+    /// The aggregates of a select list read from the storage of a table instead of
+    /// its rows, which a columnar table keeps as runs. They must all be of its
+    /// columns or `COUNT(*)`, with no `WHERE`.
+    fn table_aggregates(
+        &self,
+        select: &Select,
+        outer: Option<&Scope>,
+    ) -> DbResult<Option<ResultSet>, ExecutionError> {
+        let TableRef::Table { name, .. } = &select.from else {
+            return Ok(None);
+        };
+        if select.condition.is_some() || self.ctes.borrow().iter().any(|x| &x.0 == name) {
+            return Ok(None);
+        }
+        let Some(tbl) = self.table(&name.0) else {
+            return Ok(None);
+        };
+        let alias = select.from.name();
+        let mut names = vec![];
+        let mut exprs = vec![];
+        for col in &select.columns {
+            let Projection::Expr { expr, .. } = col else {
+                return Ok(None);
+            };
+            if !expr.windows().is_empty() {
+                return Ok(None);
+            }
+            names.push(col.name());
+            exprs.push(expr.clone());
+        }
+        let mut aggregates = vec![];
+        for expr in &exprs {
+            for a in expr.aggregates() {
+                if !aggregates.contains(&a) {
+                    aggregates.push(a);
+                }
+            }
+        }
+        if aggregates.is_empty() {
+            return Ok(None);
+        }
+        let mut values = vec![];
+        for a in &aggregates {
+            let Expr::Aggregate { function, args } = a else {
+                return Ok(None);
+            };
+            values.push(match args.first() {
+                None => SqlValue::Integer(tbl.len() as i64),
+                Some(Expr::Column(column)) if column.qualifier().is_none_or(|x| x == alias.0) => {
+                    match tbl.column_index(column.column()) {
+                        Some(_) => tbl.aggregate(&function.0.to_lowercase(), column.column())?,
+                        None => return Ok(None),
+                    }
+                }
+                Some(_) => return Ok(None),
+            });
+        }
+        let columns = tbl.column_names();
+        let rs = self.aggregated(alias, &columns, names, &exprs, &aggregates, values, outer)?;
+        Ok(Some(rs))
+    }
+
+    /// The rows of the source which match the condition. The rows of a table are
+    /// read through an index instead of a scan when the planner estimates it
+    /// cheaper, and the comparisons of an analyzed table are checked the most
+    /// selective first.
+    pub(super) fn filter(
+        &self,
+        from: &TableRef,
+        condition: Option<&Condition>,
+        outer: Option<&Scope>,
+    ) -> DbResult<Relation<'_>, ExecutionError> {
+        let table = from.name();
+        let condition = match (from, condition) {
+            (TableRef::Table { name, .. }, Some(cond)) => Some(self.plan(name, table, cond)),
+            (_, cond) => cond.map(Cow::Borrowed),
+        };
+        let condition = condition.as_deref();
+        let relation = match self.text_search(from, condition) {
+            Some(relation) => relation,
+            None => match self.index_scan(from, condition) {
+                Some(relation) => relation,
+                None => match self.scan_columns(from, condition, outer)? {
+                    Some(relation) => return Ok(relation),
+                    None => self.relation(from, outer)?,
+                },
+            },
+        };
+        let Some(condition) = condition else {
+            return Ok(relation);
+        };
+        let Relation { columns, rows } = relation;
+        let mut matching = vec![];
+        for values in rows {
+            let scope = Scope {
+                table,
+                columns: &columns,
+                values: &values,
+                outer,
+            };
+            if eval::matches(condition, self, &scope)? {
+                matching.push(values);
+            }
+        }
+        Ok(Relation {
+            columns,
+            rows: matching,
+        })
+    }
+
+    pub(super) fn relation(
+        &self,
+        from: &TableRef,
+        outer: Option<&Scope>,
+    ) -> DbResult<Relation<'_>, ExecutionError> {
+        match from {
+            // a CTE hides the table or view of its name
+            TableRef::Table { name, .. } if let Some(rs) = self.cte(name) => Ok(rs.into()),
+            TableRef::Table { name, .. } if let Some(rs) = self.catalog(name) => Ok(rs.into()),
+            TableRef::Table { name, .. } => match self.view(&name.0) {
+                // a view is expanded like a derived table, without the CTEs around it
+                Some(query) if self.table(&name.0).is_none() => {
+                    let ctes = self.ctes.take();
+                    let rs = self.query(query, None);
+                    *self.ctes.borrow_mut() = ctes;
+                    Ok(rs?.into())
+                }
+                _ => {
+                    let tbl = self.get_table(name)?;
+                    self.scanned(tbl.len());
+                    Ok(Relation {
+                        columns: tbl.column_names(),
+                        rows: tbl.iter().map(|x| x.values).collect(),
+                    })
+                }
+            },
+            TableRef::Subquery { query, .. } => Ok(self.query(query, outer)?.into()),
+            TableRef::Join { .. } => self.join(from, None, outer),
+        }
+    }
+    /// The rows of a columnar table which match the condition, which reads only its
+    /// own columns so the others are decoded for the matching rows alone. None for
+    /// the other tables, or a condition with subqueries, which may read any column.
+    fn scan_columns(
+        &self,
+        from: &TableRef,
+        condition: Option<&Condition>,
+        outer: Option<&Scope>,
+    ) -> DbResult<Option<Relation<'_>>, ExecutionError> {
+        let (TableRef::Table { name, .. }, Some(condition)) = (from, condition) else {
+            return Ok(None);
+        };
+        if !condition.subqueries().is_empty() || self.ctes.borrow().iter().any(|x| &x.0 == name) {
+            return Ok(None);
+        }
+        let Some(tbl) = self.table(&name.0) else {
+            return Ok(None);
+        };
+        let alias = from.name();
+        let all = tbl.column_names();
+        let mut positions = vec![];
+        for column in condition.columns() {
+            if column.qualifier().is_none_or(|x| x == alias.0)
+                && let Some(i) = tbl.column_index(column.column())
+                && !positions.contains(&i)
+            {
+                positions.push(i);
+            }
+        }
+        let columns: Vec<_> = positions.iter().map(|x| all[*x].clone()).collect();
+        let keep = |values: &[SqlValue]| {
+            let scope = Scope {
+                table: alias,
+                columns: &columns,
+                values,
+                outer,
+            };
+            eval::matches(condition, self, &scope)
+        };
+        let Some(rows) = tbl.filter_columns(&positions, keep) else {
+            return Ok(None);
+        };
+        self.scanned(tbl.len());
+        Ok(Some(Relation {
+            columns: all,
+            rows: rows?.into_iter().map(Cow::Owned).collect(),
+        }))
+    }
+    /// The rows of a table a B-tree index finds for a comparison of the condition,
+    /// when the planner chooses it over a scan
+    fn index_scan(&self, from: &TableRef, condition: Option<&Condition>) -> Option<Relation<'_>> {
+        let TableRef::Table { name, .. } = from else {
+            return None;
+        };
+        if self.ctes.borrow().iter().any(|x| &x.0 == name) {
+            return None;
+        }
+        let tbl = self.table(&name.0)?;
+        let (index, operator, value) =
+            self.choose_index(tbl, from.name(), &condition?.conjuncts())?;
+        self.index_hit();
+        Some(Relation {
+            columns: tbl.column_names(),
+            rows: index
+                .lookup(operator, value)
+                .into_iter()
+                .filter_map(|id| tbl.get(id))
+                .collect(),
+        })
+    }
+    /// The rows of a table a `MATCH` of an indexed column selects, read from the
+    /// index instead of a scan, in the order a scan reads them
+    fn text_search(&self, from: &TableRef, condition: Option<&Condition>) -> Option<Relation<'_>> {
+        let TableRef::Table { name, .. } = from else {
+            return None;
+        };
+        if self.ctes.borrow().iter().any(|x| &x.0 == name) {
+            return None;
+        }
+        let tbl = self.table(&name.0)?;
+        let (index, query) = fulltext::find_text_search(tbl, from.name(), condition?)?;
+        self.index_hit();
+        Some(Relation {
+            columns: tbl.column_names(),
+            rows: index
+                .search(query)
+                .into_iter()
+                .filter_map(|id| tbl.get(id))
+                .collect(),
+        })
+    }
+    /// The rows of the innermost CTE of the name
+    fn cte(&self, name: &Identifier) -> Option<ResultSet> {
+        let ctes = self.ctes.borrow();
+        ctes.iter()
+            .rev()
+            .find(|x| &x.0 == name)
+            .map(|x| x.1.clone())
+    }
+}
+
+/// Names the columns of the rows of a CTE after its column list, if it has one
+fn rename(mut rs: ResultSet, cte: &Cte) -> DbResult<ResultSet, ExecutionError> {
+    if cte.columns.is_empty() {
+        return Ok(rs);
+    }
+    if cte.columns.len() != rs.columns.len() {
+        return Err(ExecutionError::InvalidStatement(format!(
+            "{} has {} columns but its query returns {}",
+            cte.name,
+            cte.columns.len(),
+            rs.columns.len()
+        )));
+    }
+    rs.columns = cte.columns.iter().map(|x| x.0.clone()).collect();
+    Ok(rs)
+}
+
+/// The operands of a set operator must have as many columns, of comparable types
+fn check_compatible(
+    operator: SetOperator,
+    left: &ResultSet,
+    right: &ResultSet,
+) -> DbResult<(), ExecutionError> {
+    if left.columns.len() != right.columns.len() {
+        return Err(ExecutionError::InvalidStatement(format!(
+            "each {operator} query must have the same number of columns, not {} and {}",
+            left.columns.len(),
+            right.columns.len()
+        )));
+    }
+    // the first value which is not nil stands for the type of a column
+    let sample = |rs: &ResultSet, i: usize| {
+        rs.rows
+            .iter()
+            .map(|x| &x[i])
+            .find(|x| **x != SqlValue::Nil)
+            .cloned()
+    };
+    for i in 0..left.columns.len() {
+        if let (Some(a), Some(b)) = (sample(left, i), sample(right, i))
+            && a.compare(&b).is_none()
+        {
+            return Err(ExecutionError::InvalidStatement(format!(
+                "{operator} types {} and {} cannot be matched",
+                a.type_name(),
+                b.type_name()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Removes the duplicate rows, keeping the first of each
+fn distinct(rows: Vec<Vec<SqlValue>>) -> Vec<Vec<SqlValue>> {
+    let mut unique: Vec<Vec<SqlValue>> = vec![];
+    for row in rows {
+        if !unique.iter().any(|x| same_row(x, &row)) {
+            unique.push(row);
+        }
+    }
+    unique
+}
+
+/// Whether the rows are duplicates, unlike in comparisons nil is the same as nil
+pub(super) fn same_row(a: &[SqlValue], b: &[SqlValue]) -> bool {
+    a.iter().zip(b).all(|(a, b)| match (a, b) {
+        (SqlValue::Nil, SqlValue::Nil) => true,
+        _ => a.compare(b) == Some(Ordering::Equal),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::test_util::{context_with, ints, rows};
+
+    /// Users 1 to 3, the first has two orders and the second one
+    fn shop() -> ExecutionContext {
+        context_with(&[
+            "CREATE TABLE users (id INT PRIMARY KEY, name STRING)",
+            "CREATE TABLE orders (id INT PRIMARY KEY, user_id INT, total INT)",
+            "INSERT INTO users VALUES (1, 'a')",
+            "INSERT INTO users VALUES (2, 'b')",
+            "INSERT INTO users VALUES (3, 'c')",
+            "INSERT INTO orders VALUES (10, 1, 5)",
+            "INSERT INTO orders VALUES (11, 1, 7)",
+            "INSERT INTO orders VALUES (12, 2, 9)",
+        ])
+    }
+
+    #[test]
+    fn should_filter_by_in_subqueries() {
+        let mut ctx = shop();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id FROM users WHERE id IN (SELECT user_id FROM orders)"
+            ),
+            Ok(ints(&[1, 2]))
+        );
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id FROM users WHERE id NOT IN (SELECT user_id FROM orders)"
+            ),
+            Ok(ints(&[3]))
+        );
+    }
+
+    #[test]
+    fn should_reject_in_subqueries_of_many_columns() {
+        let mut ctx = shop();
+        assert!(
+            rows(
+                &mut ctx,
+                "SELECT id FROM users WHERE id IN (SELECT * FROM orders)"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn should_read_the_outer_row_in_correlated_subqueries() {
+        let mut ctx = shop();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id FROM users u WHERE NOT EXISTS (SELECT id FROM orders WHERE user_id = u.id)"
+            ),
+            Ok(ints(&[3]))
+        );
+    }
+
+    #[test]
+    fn should_return_nil_for_empty_scalar_subqueries() {
+        let mut ctx = shop();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT (SELECT total FROM orders WHERE user_id = users.id AND total > 6) AS big FROM users"
+            ),
+            Ok(vec![
+                vec![SqlValue::Integer(7)],
+                vec![SqlValue::Integer(9)],
+                vec![SqlValue::Nil],
+            ])
+        );
+    }
+
+    #[test]
+    fn should_reject_scalar_subqueries_of_many_rows() {
+        let mut ctx = shop();
+        assert!(rows(&mut ctx, "SELECT (SELECT total FROM orders) FROM users").is_err());
+    }
+
+    #[test]
+    fn should_read_derived_tables() {
+        let mut ctx = shop();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT t.doubled FROM (SELECT id, total * 2 AS doubled FROM orders WHERE user_id = 1) AS t WHERE t.id > 10"
+            ),
+            Ok(ints(&[14]))
+        );
+        assert_eq!(
+            rows(&mut ctx, "SELECT nope FROM (SELECT id FROM users) AS t"),
+            Err(ExecutionError::ColumnNotFound {
+                table: "t".into(),
+                column: "nope".into()
+            })
+        );
+    }
+
+    #[test]
+    fn should_query_views_of_views() {
+        let mut ctx = shop();
+        ctx.run(
+            "CREATE VIEW buyers AS SELECT name FROM users WHERE id IN (SELECT user_id FROM orders)",
+        )
+        .unwrap();
+        ctx.run("CREATE VIEW first AS SELECT b.name FROM buyers AS b WHERE b.name = 'a'")
+            .unwrap();
+        assert_eq!(
+            rows(&mut ctx, "SELECT * FROM first"),
+            Ok(vec![vec![SqlValue::String("a".into())]])
+        );
+        assert!(ctx.run("DROP VIEW buyers").is_err());
+    }
+
+    #[test]
+    fn should_reject_views_of_unknown_columns() {
+        let mut ctx = shop();
+        assert!(
+            ctx.run("CREATE VIEW bad AS SELECT nope FROM users")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn should_remove_duplicates_with_distinct() {
+        let mut ctx = shop();
+        assert_eq!(
+            rows(&mut ctx, "SELECT DISTINCT user_id FROM orders"),
+            Ok(ints(&[1, 2]))
+        );
+    }
+
+    #[test]
+    fn should_union_queries() {
+        let mut ctx = shop();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id FROM users UNION SELECT user_id FROM orders"
+            ),
+            Ok(ints(&[1, 2, 3]))
+        );
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id FROM users UNION ALL SELECT user_id FROM orders WHERE id > 10"
+            ),
+            Ok(ints(&[1, 2, 3, 1, 2]))
+        );
+    }
+
+    #[test]
+    fn should_intersect_and_except_queries() {
+        let mut ctx = shop();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id FROM users INTERSECT SELECT user_id FROM orders"
+            ),
+            Ok(ints(&[1, 2]))
+        );
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id FROM users EXCEPT SELECT user_id FROM orders"
+            ),
+            Ok(ints(&[3]))
+        );
+    }
+
+    #[test]
+    fn should_run_compound_subqueries() {
+        let mut ctx = shop();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id FROM users WHERE id IN (SELECT id FROM users EXCEPT SELECT user_id FROM orders)"
+            ),
+            Ok(ints(&[3]))
+        );
+    }
+
+    #[test]
+    fn should_reject_compound_queries_of_other_columns() {
+        let mut ctx = shop();
+        assert!(
+            ctx.run("SELECT id FROM users UNION SELECT id, total FROM orders")
+                .is_err()
+        );
+        assert!(
+            ctx.run("SELECT name FROM users UNION SELECT total FROM orders")
+                .is_err()
+        );
+    }
+
+    /// A forest of 1 with 2 and 3 below, 4 below 2 and 5 below 4, and 6
+    fn forest() -> ExecutionContext {
+        context_with(&[
+            "CREATE TABLE nodes (id INT PRIMARY KEY, parent INT)",
+            "INSERT INTO nodes VALUES (1, 0)",
+            "INSERT INTO nodes VALUES (2, 1)",
+            "INSERT INTO nodes VALUES (3, 1)",
+            "INSERT INTO nodes VALUES (4, 2)",
+            "INSERT INTO nodes VALUES (5, 4)",
+            "INSERT INTO nodes VALUES (6, 0)",
+        ])
+    }
+    const SUBTREE: &str = "WITH RECURSIVE tree AS (SELECT id FROM nodes WHERE id = 2 UNION ALL SELECT id FROM nodes WHERE parent IN (SELECT id FROM tree)) SELECT * FROM tree";
+
+    #[test]
+    fn should_read_earlier_common_table_expressions() {
+        let mut ctx = forest();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "WITH roots(root) AS (SELECT id FROM nodes WHERE parent = 0), others AS (SELECT id FROM nodes WHERE id NOT IN (SELECT root FROM roots)) SELECT * FROM others WHERE id < 4"
+            ),
+            Ok(ints(&[2, 3]))
+        );
+    }
+
+    #[test]
+    fn should_run_recursive_common_table_expressions() {
+        let mut ctx = forest();
+        // the subtree of node 2, level by level
+        assert_eq!(rows(&mut ctx, SUBTREE), Ok(ints(&[2, 4, 5])));
+    }
+
+    #[test]
+    fn should_run_recursive_common_table_expressions_in_subqueries() {
+        let mut ctx = forest();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id FROM nodes WHERE id IN (WITH RECURSIVE up(id, parent) AS (SELECT id, parent FROM nodes WHERE id = 5 UNION SELECT id, parent FROM nodes WHERE id IN (SELECT parent FROM up)) SELECT id FROM up) AND id > 1"
+            ),
+            Ok(ints(&[2, 4, 5]))
+        );
+    }
+
+    #[test]
+    fn should_stop_recursion_over_a_cycle() {
+        let mut ctx = forest();
+        ctx.run("UPDATE nodes SET parent = 5 WHERE id = 2").unwrap();
+        // UNION ALL over a cycle never ends, UNION drops the rows seen already
+        assert!(ctx.run(SUBTREE).is_err());
+        assert!(ctx.run(&SUBTREE.replace("UNION ALL", "UNION")).is_ok());
+        assert!(ctx.ctes.borrow().is_empty());
+    }
+
+    #[test]
+    fn should_reject_common_table_expressions_of_other_columns() {
+        let mut ctx = forest();
+        assert!(
+            ctx.run("WITH t(a, b) AS (SELECT id FROM nodes) SELECT * FROM t")
+                .is_err()
+        );
+        assert!(ctx.ctes.borrow().is_empty());
+    }
+
+    #[test]
+    fn should_reject_common_table_expressions_of_the_same_name() {
+        let mut ctx = forest();
+        assert_eq!(
+            ctx.run(
+                "WITH a AS (SELECT id FROM nodes), a AS (SELECT parent FROM nodes) SELECT * FROM a"
+            ),
+            Err(ExecutionError::InvalidStatement(
+                "WITH query name a specified more than once".into()
+            ))
+        );
+        assert!(ctx.ctes.borrow().is_empty());
+        // a nested clause may hide a CTE of an enclosing one
+        assert!(
+            ctx.run("WITH a AS (SELECT id FROM nodes) SELECT * FROM a WHERE id IN (WITH a AS (SELECT parent FROM nodes) SELECT * FROM a)")
+                .is_ok()
+        );
+    }
+
+    /// The same events in a table of each storage, more than a batch of them
+    fn events() -> ExecutionContext {
+        let mut ctx = context_with(&[
+            "CREATE TABLE rows_events (id INT PRIMARY KEY, kind STRING, amount INT)",
+            "CREATE TABLE columnar_events (id INT PRIMARY KEY, kind STRING, amount INT) \
+             USING columnar",
+        ]);
+        for id in 0..2000 {
+            let kind = ["view", "click", "buy"][id % 3];
+            for table in ["rows_events", "columnar_events"] {
+                let insert = format!("INSERT INTO {table} VALUES ({id}, '{kind}', {})", id % 10);
+                ctx.run(&insert).unwrap();
+            }
+        }
+        ctx
+    }
+
+    #[test]
+    fn should_compute_aggregates() {
+        let mut ctx = shop();
+        let int = SqlValue::Integer;
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT COUNT(*), SUM(total), MIN(total), MAX(total) FROM orders"
+            ),
+            Ok(vec![vec![int(3), int(21), int(5), int(9)]])
+        );
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT COUNT(*) * 10, SUM(total * 2) FROM orders WHERE user_id = 1"
+            ),
+            Ok(vec![vec![int(20), int(24)]])
+        );
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT COUNT(*), SUM(total) FROM orders WHERE user_id = 3"
+            ),
+            Ok(vec![vec![int(0), SqlValue::Nil]])
+        );
+    }
+
+    #[test]
+    fn should_compute_aggregates_in_correlated_subqueries() {
+        let mut ctx = shop();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id, (SELECT COUNT(*) FROM orders WHERE user_id = users.id) FROM users"
+            ),
+            Ok(vec![
+                ints(&[1, 2]).concat(),
+                ints(&[2, 1]).concat(),
+                ints(&[3, 0]).concat(),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_reject_misplaced_aggregates() {
+        let mut ctx = shop();
+        for sql in [
+            "SELECT id, COUNT(*) FROM orders",
+            "SELECT * FROM orders WHERE SUM(total) > 1",
+            "SELECT SUM(COUNT(*)) FROM orders",
+            "SELECT COUNT(*), ROW_NUMBER() OVER () FROM orders",
+        ] {
+            assert!(
+                matches!(ctx.run(sql), Err(ExecutionError::InvalidStatement(_))),
+                "{sql}"
+            );
+        }
+        assert!(ctx.run("SELECT SUM(missing) FROM orders").is_err());
+    }
+
+    #[test]
+    fn should_read_aggregates_of_the_whole_table_from_its_storage() {
+        let mut ctx = events();
+        let expected = Ok(vec![vec![
+            SqlValue::Integer(2000),
+            SqlValue::Integer(9000),
+            SqlValue::Integer(9),
+        ]]);
+        for table in ["rows_events", "columnar_events"] {
+            let sql = format!("SELECT COUNT(*), SUM(amount), MAX(e.amount) FROM {table} e");
+            assert_eq!(rows(&mut ctx, &sql), expected);
+        }
+        // without scanning the rows
+        assert_eq!(ctx.statistics().rows_scanned, 0);
+    }
+
+    #[test]
+    fn should_filter_columnar_tables_a_batch_at_a_time() {
+        let mut ctx = events();
+        let query = |table: &str| {
+            format!("SELECT * FROM {table} WHERE kind = 'buy' AND amount > 7 AND id > 1000")
+        };
+        let expected = rows(&mut ctx, &query("rows_events")).unwrap();
+        assert_eq!(expected.len(), 66);
+        assert_eq!(rows(&mut ctx, &query("columnar_events")), Ok(expected));
+        assert_eq!(ctx.statistics().rows_scanned, 4000);
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT COUNT(*), AVG(amount) FROM columnar_events WHERE kind = 'view'"
+            ),
+            rows(
+                &mut ctx,
+                "SELECT COUNT(*), AVG(amount) FROM rows_events WHERE kind = 'view'"
+            ),
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    format::OutputFormat,
+    parser::SqlValue,
+};
+use std::{fmt::Display, time::Duration};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecutionResult {
+    /// the rows of a query, or of the `RETURNING` clause of an insert
+    Select(ResultSet),
+    /// the number of the inserted rows
+    Insert(usize),
+    Create,
+    Drop,
+    /// the number of the deleted rows, without those of a cascade
+    Delete(usize),
+    /// the number of the updated rows
+    Update(usize),
+    Copy(usize),
+    Alter,
+    Analyze,
+    Grant,
+    Revoke,
+}
+
+impl ExecutionResult {
+    /// The rows returned by a query or changed by the statement
+    pub fn rows(&self) -> usize {
+        match self {
+            ExecutionResult::Select(rs) => rs.rows.len(),
+            ExecutionResult::Insert(n)
+            | ExecutionResult::Delete(n)
+            | ExecutionResult::Update(n)
+            | ExecutionResult::Copy(n) => *n,
+            _ => 0,
+        }
+    }
+}
+
+impl Display for ExecutionResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutionResult::Select(rs) => write!(f, "{}", rs),
+            ExecutionResult::Insert(n) => write!(f, "{} inserted", row_count(*n)),
+            ExecutionResult::Delete(n) => write!(f, "{} deleted", row_count(*n)),
+            ExecutionResult::Update(n) => write!(f, "{} updated", row_count(*n)),
+            ExecutionResult::Copy(n) => write!(f, "{} copied", row_count(*n)),
+            _ => write!(f, "{self:?}"),
+        }
+    }
+}
+
+/// The result of a statement with how long it took
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedResult {
+    pub result: DbResult<ExecutionResult, ExecutionError>,
+    pub elapsed: Duration,
+}
+
+fn row_count(n: usize) -> String {
+    if n == 1 {
+        "1 row".into()
+    } else {
+        format!("{n} rows")
+    }
+}
+
+/// The rows of a query, with the values in the order of `columns`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultSet {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+impl ResultSet {
+    pub fn new(columns: Vec<String>) -> Self {
+        Self {
+            columns,
+            rows: vec![],
+        }
+    }
+    pub fn format(&self, format: OutputFormat) -> String {
+        format.format(self)
+    }
+}
+
+impl Display for ResultSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(OutputFormat::Table))
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{
+    errors::ExecutionError,
+    exec::{ExecutionContext, ExecutionResult, ResultSet},
+    parser::SqlValue,
+};
+use std::time::Duration;
+
+/// The counters of the statements run by a context, read from the `db_stats`
+/// system table
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Statistics {
+    pub statements: usize,
+    pub queries: usize,
+    pub errors: usize,
+    /// the rows of the results of the queries
+    pub rows_returned: usize,
+    /// the rows inserted, updated, deleted or copied
+    pub rows_changed: usize,
+    /// the rows read from the tables by full scans
+    pub rows_scanned: usize,
+    /// the lookups answered by an index instead of a scan
+    pub index_hits: usize,
+    pub elapsed: Duration,
+    /// how long the last statement took
+    pub last_elapsed: Duration,
+}
+
+impl Statistics {
+    /// Counts a statement, whether it succeeded or not
+    pub(super) fn record(
+        &mut self,
+        res: &Result<ExecutionResult, ExecutionError>,
+        elapsed: Duration,
+    ) {
+        self.statements += 1;
+        self.elapsed += elapsed;
+        self.last_elapsed = elapsed;
+        match res {
+            Ok(ExecutionResult::Select(rs)) => {
+                self.queries += 1;
+                self.rows_returned += rs.rows.len();
+            }
+            Ok(res) => self.rows_changed += res.rows(),
+            Err(_) => self.errors += 1,
+        }
+    }
+    /// The counters as `name` and `value` rows
+    pub(super) fn to_result_set(&self) -> ResultSet {
+        let mut rs = ResultSet::new(vec!["name".into(), "value".into()]);
+        let counters = [
+            ("statements", self.statements as i64),
+            ("queries", self.queries as i64),
+            ("errors", self.errors as i64),
+            ("rows_returned", self.rows_returned as i64),
+            ("rows_changed", self.rows_changed as i64),
+            ("rows_scanned", self.rows_scanned as i64),
+            ("index_hits", self.index_hits as i64),
+            ("elapsed_us", self.elapsed.as_micros() as i64),
+            ("last_elapsed_us", self.last_elapsed.as_micros() as i64),
+        ];
+        for (name, value) in counters {
+            rs.rows.push(vec![
+                SqlValue::String(name.into()),
+                SqlValue::Integer(value),
+            ]);
+        }
+        rs
+    }
+}
+
+impl ExecutionContext {
+    /// The counters of the statements run so far
+    pub fn statistics(&self) -> Statistics {
+        self.stats.borrow().clone()
+    }
+    /// Starts the counters over
+    pub fn reset_statistics(&mut self) {
+        self.stats.take();
+    }
+    /// Counts the rows read by a full scan of a table
+    pub(crate) fn scanned(&self, rows: usize) {
+        self.stats.borrow_mut().rows_scanned += rows;
+    }
+    /// Counts a lookup which read the rows from an index
+    pub(crate) fn index_hit(&self) {
+        self.stats.borrow_mut().index_hits += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::test_util::{context_with, rows};
+
+    /// A table of three rows whose last two were updated and first deleted, then
+    /// a query and a failing one
+    fn counted() -> ExecutionContext {
+        let mut ctx = context_with(&[
+            "CREATE TABLE t (id INT)",
+            "INSERT INTO t VALUES (1)",
+            "INSERT INTO t VALUES (2)",
+            "INSERT INTO t VALUES (3)",
+            "UPDATE t SET id = id + 10 WHERE id > 1",
+            "DELETE t WHERE id = 1",
+            "SELECT id FROM t",
+        ]);
+        assert!(ctx.run("SELECT nope FROM t").is_err());
+        ctx
+    }
+
+    #[test]
+    fn should_count_statements_and_errors() {
+        let stats = counted().statistics();
+        assert_eq!(stats.statements, 8);
+        assert_eq!(stats.queries, 1);
+        assert_eq!(stats.errors, 1);
+        assert!(stats.elapsed >= stats.last_elapsed);
+    }
+
+    #[test]
+    fn should_count_the_statements_which_fail_to_parse() {
+        let mut ctx = counted();
+        assert!(ctx.run("SELEC x").is_err());
+        assert!(ctx.run_timed("SELECT FROM").result.is_err());
+        let stats = ctx.statistics();
+        assert_eq!(stats.statements, 10);
+        assert_eq!(stats.errors, 3);
+        assert_eq!(stats.last_elapsed, Duration::ZERO);
+    }
+
+    #[test]
+    fn should_count_rows() {
+        let stats = counted().statistics();
+        assert_eq!(stats.rows_returned, 2);
+        assert_eq!(stats.rows_changed, 6);
+        assert_eq!(stats.rows_scanned, 3 + 3 + 2 + 2);
+    }
+
+    #[test]
+    fn should_query_the_counters() {
+        let mut ctx = counted();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT value FROM db_stats WHERE name = 'rows_changed'"
+            ),
+            Ok(vec![vec![SqlValue::Integer(6)]])
+        );
+    }
+
+    #[test]
+    fn should_reset_the_counters() {
+        let mut ctx = counted();
+        ctx.reset_statistics();
+        assert_eq!(ctx.statistics(), Statistics::default());
+    }
+
+    #[test]
+    fn should_time_the_statement() {
+        let mut ctx = ExecutionContext::new();
+        let timed = ctx.run_timed("CREATE TABLE t (id INT)");
+        assert_eq!(timed.result, Ok(ExecutionResult::Create));
+        assert_eq!(ctx.statistics().last_elapsed, timed.elapsed);
+
+        let timed = ctx.run_timed("SELECT FROM");
+        assert!(timed.result.is_err());
+        assert_eq!(timed.elapsed, Duration::ZERO);
+    }
+
+    #[test]
+    fn should_count_the_index_hits() {
+        let mut ctx = context_with(&[
+            "CREATE TABLE docs (id INT, body TEXT)",
+            "INSERT INTO docs VALUES (1, 'rust and sql')",
+            "INSERT INTO docs VALUES (2, 'only sql')",
+        ]);
+        ctx.run("SELECT id FROM docs WHERE body MATCH 'rust'")
+            .unwrap();
+        assert_eq!(ctx.statistics().index_hits, 0);
+        assert_eq!(ctx.statistics().rows_scanned, 2);
+
+        ctx.run("CREATE INDEX docs_body ON docs USING fulltext(body)")
+            .unwrap();
+        ctx.reset_statistics();
+        assert_eq!(
+            ctx.run("SELECT id FROM docs WHERE body MATCH 'rust'")
+                .map(|x| x.rows()),
+            Ok(1)
+        );
+        assert_eq!(ctx.statistics().index_hits, 1);
+        assert_eq!(ctx.statistics().rows_scanned, 0);
+    }
+}
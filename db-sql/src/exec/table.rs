@@ -1,76 +1,468 @@
-use crate::parser::ColumnDefinition;
-use std::{
-    collections::btree_map::Iter,
-    collections::{BTreeMap, HashMap},
-    rc::Rc,
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::{
+        BTreeIndex, FullTextIndex, TableStatistics, columnar::ColumnStore, eval, window::Aggregate,
+    },
+    parser::{
+        ColumnDefinition, Identifier, IndexKind, SqlDataType, SqlStatement, SqlValue, TableStorage,
+    },
 };
+use std::{borrow::Cow, collections::BTreeMap, rc::Rc};
 
-pub type StoredRow = HashMap<String, String>;
+/// The values of a row in the order of the table columns
+pub type StoredRow = Vec<SqlValue>;
 pub type ColumnInfo = Vec<ColumnDefinition>;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Table {
-    rows: BTreeMap<usize, StoredRow>,
+    name: Identifier,
+    rows: Storage,
     columns: ColumnInfo,
+    /// the last value of the auto-increment column, generated or given
+    sequence: i64,
+    /// collected by the last `ANALYZE`
+    statistics: Option<TableStatistics>,
+    /// kept up to date on every change of the rows
+    indexes: Vec<FullTextIndex>,
+    /// same as `indexes` for the B-tree indexes
+    btree_indexes: Vec<BTreeIndex>,
 }
 
 impl Table {
-    pub fn new(columns: ColumnInfo) -> Table {
+    pub fn new(name: Identifier, columns: ColumnInfo) -> Table {
+        Self::with_storage(name, columns, TableStorage::Rows)
+    }
+    pub fn with_storage(name: Identifier, columns: ColumnInfo, storage: TableStorage) -> Table {
+        let rows = match storage {
+            TableStorage::Rows => Storage::Rows(BTreeMap::new()),
+            TableStorage::Columnar => Storage::Columnar(ColumnStore::new(columns.len())),
+        };
         Self {
-            rows: BTreeMap::new(),
+            name,
+            rows,
             columns,
+            sequence: 0,
+            statistics: None,
+            indexes: vec![],
+            btree_indexes: vec![],
         }
     }
-    pub fn insert(&mut self, rows: Vec<String>) -> usize {
-        let id = self.rows.last_key_value().map_or(0, |x| x.0 + 1);
-
-        let values = rows
+    pub fn storage(&self) -> TableStorage {
+        match self.rows {
+            Storage::Rows(_) => TableStorage::Rows,
+            Storage::Columnar(_) => TableStorage::Columnar,
+        }
+    }
+    /// Inserts the values, converted to the column types, after checking the
+    /// constraints. Missing trailing values take the defaults of their columns.
+    pub fn insert(&mut self, values: StoredRow) -> DbResult<usize, ExecutionError> {
+        if values.len() > self.columns.len() {
+            return Err(ExecutionError::ColumnCountMismatch {
+                expected: self.columns.len(),
+                found: values.len(),
+            });
+        }
+        let positions: Vec<_> = (0..values.len()).collect();
+        self.insert_into(&positions, values)
+    }
+    /// Same as `insert` but the values are for the columns at the positions, the
+    /// other columns take their defaults, or nil without one. A missing or nil
+    /// auto-increment value is the next one of the sequence.
+    pub fn insert_into(
+        &mut self,
+        positions: &[usize],
+        values: StoredRow,
+    ) -> DbResult<usize, ExecutionError> {
+        if values.len() != positions.len() {
+            return Err(ExecutionError::ColumnCountMismatch {
+                expected: positions.len(),
+                found: values.len(),
+            });
+        }
+        let mut given = vec![None; self.columns.len()];
+        for (pos, value) in positions.iter().zip(values) {
+            given[*pos] = Some(value);
+        }
+        let next = self.sequence.checked_add(1);
+        let values = self
+            .columns
             .iter()
-            .zip(self.columns.iter())
-            .map(|(v, c)| (c.name.0.clone(), v.clone()))
-            .collect();
-        self.rows.insert(id, values);
+            .zip(given)
+            .map(|(col, value)| match (value, &col.default) {
+                (None | Some(SqlValue::Nil), _) if col.auto_increment => next
+                    .map(SqlValue::Integer)
+                    .ok_or_else(|| ExecutionError::ValueOutOfRange {
+                        data_type: col.data_type.to_string(),
+                        value: format!("{} + 1", self.sequence),
+                    }),
+                (Some(value), _) => Ok(value),
+                (None, Some(default)) => eval::evaluate_constant(default),
+                (None, None) => Ok(SqlValue::Nil),
+            })
+            .collect::<Result<_, _>>()?;
+        let values = self.check(values, None)?;
+        self.advance_sequence(&values);
+
+        let id = self.rows.last_id().map_or(0, |x| x + 1);
+        for index in &mut self.indexes {
+            index.insert(id, &values[index.column()]);
+        }
+        for index in &mut self.btree_indexes {
+            index.insert(id, &values[index.column()]);
+        }
+        match &mut self.rows {
+            Storage::Rows(rows) => _ = rows.insert(id, values),
+            Storage::Columnar(store) => store.push(id, values),
+        }
 
-        id
+        Ok(id)
+    }
+    /// Replaces all the values of the row, converted and checked like on insert
+    pub fn update(&mut self, id: usize, values: StoredRow) -> DbResult<(), ExecutionError> {
+        if values.len() != self.columns.len() {
+            return Err(ExecutionError::ColumnCountMismatch {
+                expected: self.columns.len(),
+                found: values.len(),
+            });
+        }
+        let values = self.check(values, Some(id))?;
+        self.advance_sequence(&values);
+        if !(self.indexes.is_empty() && self.btree_indexes.is_empty())
+            && let Some(old) = self.get(id).map(Cow::into_owned)
+        {
+            for index in &mut self.indexes {
+                index.remove(id, &old[index.column()]);
+                index.insert(id, &values[index.column()]);
+            }
+            for index in &mut self.btree_indexes {
+                index.remove(id, &old[index.column()]);
+                index.insert(id, &values[index.column()]);
+            }
+        }
+        match &mut self.rows {
+            Storage::Rows(rows) => {
+                if let Some(row) = rows.get_mut(&id) {
+                    *row = values;
+                }
+            }
+            Storage::Columnar(store) => store.set(id, values),
+        }
+        Ok(())
     }
-    pub fn delete(&mut self, id: usize) {
-        self.rows.remove(&id);
+    /// Explicit values move the sequence forward, it never goes back
+    fn advance_sequence(&mut self, values: &StoredRow) {
+        if let Some(SqlValue::Integer(x)) = self.auto_increment_column().map(|i| &values[i]) {
+            self.sequence = self.sequence.max(*x);
+        }
+    }
+    /// Converts the values and checks the constraints, the row `except` is left out
+    /// of the uniqueness checks, e.g. the one being updated.
+    fn check(
+        &self,
+        values: StoredRow,
+        except: Option<usize>,
+    ) -> DbResult<StoredRow, ExecutionError> {
+        let mut checked = Vec::with_capacity(values.len());
+        for (i, (col, value)) in self.columns.iter().zip(values).enumerate() {
+            let value = col.data_type.coerce(value)?;
+            let violation = |constraint: &str| ExecutionError::ConstraintViolation {
+                table: self.name.0.clone(),
+                column: col.name.0.clone(),
+                constraint: constraint.to_string(),
+            };
+            if value == SqlValue::Nil {
+                if col.is_not_null() {
+                    return Err(violation("NOT NULL"));
+                }
+            } else if col.is_unique()
+                && self
+                    .rows
+                    .column(i)
+                    .any(|(id, x)| Some(id) != except && *x == value)
+            {
+                return Err(violation("UNIQUE"));
+            }
+            checked.push(value);
+        }
+        Ok(checked)
+    }
+    /// Removes the row and returns its values
+    pub fn delete(&mut self, id: usize) -> Option<StoredRow> {
+        let values = match &mut self.rows {
+            Storage::Rows(rows) => rows.remove(&id),
+            Storage::Columnar(store) => store.remove(id),
+        }?;
+        for index in &mut self.indexes {
+            index.remove(id, &values[index.column()]);
+        }
+        for index in &mut self.btree_indexes {
+            index.remove(id, &values[index.column()]);
+        }
+        Some(values)
     }
     pub fn clear_all(&mut self) {
-        self.rows.clear();
+        match &mut self.rows {
+            Storage::Rows(rows) => rows.clear(),
+            Storage::Columnar(store) => store.clear(),
+        }
+        self.indexes.iter_mut().for_each(FullTextIndex::clear);
+        self.btree_indexes.iter_mut().for_each(BTreeIndex::clear);
+    }
+    /// The values of the row, decoded when the table is columnar
+    pub fn get(&self, id: usize) -> Option<Cow<'_, StoredRow>> {
+        match &self.rows {
+            Storage::Rows(rows) => rows.get(&id).map(Cow::Borrowed),
+            Storage::Columnar(store) => store.get(id).map(Cow::Owned),
+        }
+    }
+    /// Same as `get` but the values come with their columns
+    pub fn row(&self, id: usize) -> Option<Row<'_>> {
+        let values = self.get(id)?;
+        Some(Row::new(id, Rc::new(self.columns.clone()), values))
+    }
+    /// The most recently inserted row
+    pub fn last(&self) -> Option<Cow<'_, StoredRow>> {
+        self.get(self.rows.last_id()?)
+    }
+    pub fn len(&self) -> usize {
+        match &self.rows {
+            Storage::Rows(rows) => rows.len(),
+            Storage::Columnar(store) => store.len(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn name(&self) -> &Identifier {
+        &self.name
+    }
+    pub fn columns(&self) -> &ColumnInfo {
+        &self.columns
+    }
+    /// The position of the column whose values come from the sequence
+    pub fn auto_increment_column(&self) -> Option<usize> {
+        self.columns.iter().position(|x| x.auto_increment)
+    }
+    /// The last value of the sequence, the next insert gets the one after
+    pub fn sequence(&self) -> i64 {
+        self.sequence
+    }
+    /// Restarts the sequence so the next generated value is `next`, or after the
+    /// largest stored one if that is larger.
+    pub fn restart_sequence(&mut self, next: i64) -> DbResult<(), ExecutionError> {
+        let Some(i) = self.auto_increment_column() else {
+            return Err(ExecutionError::InvalidStatement(format!(
+                "table {} has no auto-increment column",
+                self.name
+            )));
+        };
+        let largest = self
+            .rows
+            .column(i)
+            .filter_map(|(_, x)| match x {
+                SqlValue::Integer(x) => Some(*x),
+                _ => None,
+            })
+            .max();
+        self.sequence = next.saturating_sub(1).max(largest.unwrap_or(i64::MIN));
+        Ok(())
+    }
+    /// The statistics of the columns, if the table was analyzed
+    pub fn statistics(&self) -> Option<&TableStatistics> {
+        self.statistics.as_ref()
+    }
+    pub(super) fn set_statistics(&mut self, statistics: TableStatistics) {
+        self.statistics = Some(statistics);
+    }
+    /// Indexes the words of the values of the text column for `MATCH`
+    pub fn create_index(&mut self, name: Identifier, column: &str) -> DbResult<(), ExecutionError> {
+        let i = self.require_column(column)?;
+        let data_type = self.columns[i].data_type;
+        if !matches!(
+            data_type,
+            SqlDataType::String | SqlDataType::Text | SqlDataType::Varchar(_)
+        ) {
+            return Err(ExecutionError::InvalidStatement(format!(
+                "{}.{} is {}, only text columns have full-text indexes",
+                self.name, column, data_type
+            )));
+        }
+        let mut index = FullTextIndex::new(name, i);
+        for (id, value) in self.rows.column(i) {
+            index.insert(id, value);
+        }
+        self.indexes.push(index);
+        Ok(())
+    }
+    /// Indexes the values of the column in order, for its comparisons and joins
+    pub fn create_btree_index(
+        &mut self,
+        name: Identifier,
+        column: &str,
+    ) -> DbResult<(), ExecutionError> {
+        let i = self.require_column(column)?;
+        let mut index = BTreeIndex::new(name, i);
+        for (id, value) in self.rows.column(i) {
+            index.insert(id, value);
+        }
+        self.btree_indexes.push(index);
+        Ok(())
+    }
+    /// Removes the index, returns whether the table had it
+    pub fn drop_index(&mut self, name: &Identifier) -> bool {
+        let len = self.indexes.len() + self.btree_indexes.len();
+        self.indexes.retain(|x| x.name() != name);
+        self.btree_indexes.retain(|x| x.name() != name);
+        self.indexes.len() + self.btree_indexes.len() != len
     }
-    pub fn get(&self, id: usize) -> Option<&StoredRow> {
-        self.rows.get(&id)
+    pub fn has_index(&self, name: &Identifier) -> bool {
+        self.indexes.iter().any(|x| x.name() == name)
+            || self.btree_indexes.iter().any(|x| x.name() == name)
     }
-    pub fn iter(&self) -> impl Iterator<Item = Row> {
+    /// The full-text indexes in the order they were created
+    pub fn indexes(&self) -> &[FullTextIndex] {
+        &self.indexes
+    }
+    /// The B-tree indexes in the order they were created
+    pub fn btree_indexes(&self) -> &[BTreeIndex] {
+        &self.btree_indexes
+    }
+    /// The `CREATE INDEX` statements of the indexes
+    pub fn index_statements(&self) -> Vec<SqlStatement> {
+        let text = self
+            .indexes
+            .iter()
+            .map(|x| (x.name(), x.column(), IndexKind::FullText));
+        let btree = self
+            .btree_indexes
+            .iter()
+            .map(|x| (x.name(), x.column(), IndexKind::BTree));
+        text.chain(btree)
+            .map(|(name, column, kind)| SqlStatement::CreateIndex {
+                name: name.clone(),
+                table: self.name.clone(),
+                column: self.columns[column].name.clone(),
+                kind,
+            })
+            .collect()
+    }
+    /// The full-text index of the column at the position
+    pub fn text_index(&self, column: usize) -> Option<&FullTextIndex> {
+        self.indexes.iter().find(|x| x.column() == column)
+    }
+    /// The B-tree index of the column at the position
+    pub fn btree_index(&self, column: usize) -> Option<&BTreeIndex> {
+        self.btree_indexes.iter().find(|x| x.column() == column)
+    }
+    pub fn column_names(&self) -> Vec<Identifier> {
+        self.columns.iter().map(|x| x.name.clone()).collect()
+    }
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|x| x.name.0 == name)
+    }
+    /// Same as `column_index` but a missing column is an error
+    pub fn require_column(&self, name: &str) -> DbResult<usize, ExecutionError> {
+        self.column_index(name)
+            .ok_or_else(|| ExecutionError::ColumnNotFound {
+                table: self.name.0.clone(),
+                column: name.to_string(),
+            })
+    }
+    pub fn iter(&self) -> impl Iterator<Item = Row<'_>> {
         self.into_iter()
     }
+    /// The rows of a columnar table whose values of the columns at the positions
+    /// pass the filter, without decoding the others for those it drops. None for
+    /// a table of the row storage, which has nothing to decode.
+    pub fn filter_columns<E>(
+        &self,
+        positions: &[usize],
+        keep: impl FnMut(&[SqlValue]) -> Result<bool, E>,
+    ) -> Option<Result<Vec<StoredRow>, E>> {
+        match &self.rows {
+            Storage::Rows(_) => None,
+            Storage::Columnar(store) => Some(store.filter(positions, keep)),
+        }
+    }
+    /// Computes `count`, `sum`, `avg`, `min` or `max` of the column over all the
+    /// rows, a columnar table reads each run or dictionary value once.
+    pub fn aggregate(&self, function: &str, column: &str) -> DbResult<SqlValue, ExecutionError> {
+        if !matches!(function, "count" | "sum" | "avg" | "min" | "max") {
+            return Err(ExecutionError::UnknownFunction(function.to_string()));
+        }
+        let i = self.require_column(column)?;
+        let mut aggregate = Aggregate::default();
+        match &self.rows {
+            Storage::Rows(rows) => {
+                for row in rows.values() {
+                    aggregate.add(function, Some(&row[i]))?;
+                }
+            }
+            Storage::Columnar(store) => {
+                for (value, n) in store.column(i).runs() {
+                    aggregate.add_repeated(function, value, n)?;
+                }
+            }
+        }
+        aggregate.value(function)
+    }
+}
+
+/// Where the rows of a table are kept
+#[derive(Debug, Clone, PartialEq)]
+enum Storage {
+    Rows(BTreeMap<usize, StoredRow>),
+    Columnar(ColumnStore),
+}
+impl Storage {
+    fn last_id(&self) -> Option<usize> {
+        match self {
+            Storage::Rows(rows) => rows.last_key_value().map(|x| *x.0),
+            Storage::Columnar(store) => store.ids().last().copied(),
+        }
+    }
+    /// The values of a column with the ids of their rows
+    fn column(&self, i: usize) -> Box<dyn Iterator<Item = (usize, &SqlValue)> + '_> {
+        match self {
+            Storage::Rows(rows) => Box::new(rows.iter().map(move |(id, x)| (*id, &x[i]))),
+            Storage::Columnar(store) => {
+                Box::new(store.ids().iter().copied().zip(store.column(i).iter()))
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Row<'a> {
     pub id: usize,
     pub columns: Rc<ColumnInfo>,
-    pub values: &'a HashMap<String, String>,
+    pub values: Cow<'a, StoredRow>,
 }
 impl<'a> Row<'a> {
-    pub fn new(id: usize, columns: Rc<ColumnInfo>, values: &'a HashMap<String, String>) -> Self {
+    pub fn new(id: usize, columns: Rc<ColumnInfo>, values: Cow<'a, StoredRow>) -> Self {
         Self {
             id,
             columns,
             values,
         }
     }
+    pub fn get(&self, column: &str) -> Option<&SqlValue> {
+        let idx = self.columns.iter().position(|x| x.name.0 == column)?;
+        self.values.get(idx)
+    }
 }
 
 pub struct TableIter<'a> {
-    map_iter: Iter<'a, usize, StoredRow>,
+    rows: Box<dyn Iterator<Item = (usize, Cow<'a, StoredRow>)> + 'a>,
     columns: Rc<ColumnInfo>,
 }
 
 impl<'a> TableIter<'a> {
-    pub fn new(map_iter: Iter<'a, usize, StoredRow>, columns: Rc<ColumnInfo>) -> Self {
-        Self { map_iter, columns }
+    pub fn new(
+        rows: Box<dyn Iterator<Item = (usize, Cow<'a, StoredRow>)> + 'a>,
+        columns: Rc<ColumnInfo>,
+    ) -> Self {
+        Self { rows, columns }
     }
 }
 
@@ -78,9 +470,9 @@ impl<'a> Iterator for TableIter<'a> {
     type Item = Row<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.map_iter
+        self.rows
             .next()
-            .map(|(id, data)| Row::new(id.clone(), self.columns.clone(), data))
+            .map(|(id, data)| Row::new(id, self.columns.clone(), data))
     }
 }
 
@@ -91,6 +483,191 @@ impl<'a> IntoIterator for &'a Table {
 
     fn into_iter(self) -> Self::IntoIter {
         let col_info = Rc::new(self.columns.clone());
-        TableIter::new(self.rows.iter(), col_info)
+        // a columnar table is decoded a batch of rows at a time
+        let rows: Box<dyn Iterator<Item = _>> = match &self.rows {
+            Storage::Rows(rows) => Box::new(rows.iter().map(|(id, x)| (*id, Cow::Borrowed(x)))),
+            Storage::Columnar(store) => Box::new(
+                store
+                    .batches()
+                    .flat_map(|x| x.into_rows())
+                    .map(|(id, x)| (id, Cow::Owned(x))),
+            ),
+        };
+        TableIter::new(rows, col_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SqlDataType;
+
+    #[test]
+    fn should_check_types_and_constraints() {
+        let column = |name: &str, data_type, constraint: Option<&str>| ColumnDefinition {
+            name: name.into(),
+            data_type,
+            constraint: constraint.map(String::from),
+            default: None,
+            auto_increment: false,
+            references: None,
+        };
+        let mut tbl = Table::new(
+            "users".into(),
+            vec![
+                column("id", SqlDataType::Integer, Some("PRIMARY KEY")),
+                column("score", SqlDataType::Float, None),
+            ],
+        );
+
+        assert_eq!(
+            tbl.insert(vec![SqlValue::Integer(1), SqlValue::Integer(2)]),
+            Ok(0)
+        );
+        assert_eq!(tbl.get(0).unwrap()[1], SqlValue::Float(2.0));
+        assert_eq!(
+            tbl.insert(vec![SqlValue::Integer(1)]),
+            Err(ExecutionError::ConstraintViolation {
+                table: "users".into(),
+                column: "id".into(),
+                constraint: "UNIQUE".into(),
+            })
+        );
+        assert_eq!(
+            tbl.insert(vec![SqlValue::Nil]).unwrap_err().to_string(),
+            "NOT NULL constraint failed: users.id"
+        );
+        assert_eq!(
+            tbl.insert(vec![SqlValue::String("2".into())]),
+            Err(ExecutionError::TypeMismatch {
+                expected: "INT".into(),
+                found: "STRING".into(),
+            })
+        );
+        assert_eq!(
+            tbl.insert(vec![SqlValue::Integer(2), SqlValue::Nil, SqlValue::Nil]),
+            Err(ExecutionError::ColumnCountMismatch {
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn should_generate_auto_increment_values() {
+        let mut tbl = Table::new(
+            "items".into(),
+            vec![
+                ColumnDefinition {
+                    name: "id".into(),
+                    data_type: SqlDataType::Integer,
+                    constraint: Some("PRIMARY KEY".into()),
+                    default: None,
+                    auto_increment: true,
+                    references: None,
+                },
+                ColumnDefinition {
+                    name: "name".into(),
+                    data_type: SqlDataType::String,
+                    constraint: None,
+                    default: None,
+                    auto_increment: false,
+                    references: None,
+                },
+            ],
+        );
+        let name = |x: &str| SqlValue::String(x.into());
+
+        tbl.insert_into(&[1], vec![name("a")]).unwrap();
+        tbl.insert(vec![SqlValue::Integer(10), name("b")]).unwrap();
+        let id = tbl.insert(vec![SqlValue::Nil, name("c")]).unwrap();
+        assert_eq!(tbl.get(id).unwrap()[0], SqlValue::Integer(11));
+
+        // deleted values are not reused
+        tbl.delete(id);
+        let id = tbl.insert_into(&[1], vec![name("d")]).unwrap();
+        assert_eq!(tbl.get(id).unwrap()[0], SqlValue::Integer(12));
+
+        // updates keep the row unique against the others only
+        tbl.update(id, vec![SqlValue::Integer(12), name("e")])
+            .unwrap();
+        assert!(
+            tbl.update(id, vec![SqlValue::Integer(1), name("e")])
+                .is_err()
+        );
+        tbl.update(id, vec![SqlValue::Integer(20), name("e")])
+            .unwrap();
+        assert_eq!(tbl.sequence(), 20);
+
+        tbl.restart_sequence(100).unwrap();
+        assert_eq!(tbl.sequence(), 99);
+        tbl.restart_sequence(1).unwrap();
+        assert_eq!(tbl.sequence(), 20);
+    }
+
+    #[test]
+    fn should_scan_and_aggregate_columnar_tables() {
+        use crate::exec::{ExecutionContext, ExecutionResult};
+
+        let mut ctx = ExecutionContext::new();
+        ctx.run(
+            "CREATE TABLE events (id SERIAL PRIMARY KEY, kind STRING, amount INT) USING columnar",
+        )
+        .unwrap();
+        for i in 0..3000 {
+            let kind = ["view", "click", "buy"][i % 3];
+            ctx.run(&format!(
+                "INSERT INTO events (kind, amount) VALUES ('{kind}', {})",
+                i / 1000
+            ))
+            .unwrap();
+        }
+        ctx.run("UPDATE events SET amount = 10 WHERE id = 1")
+            .unwrap();
+        ctx.run("DELETE events WHERE id = 2").unwrap();
+
+        let tbl = ctx.table("events").unwrap();
+        assert_eq!(tbl.storage(), TableStorage::Columnar);
+        assert_eq!(tbl.aggregate("count", "kind"), Ok(SqlValue::Integer(2999)));
+        assert_eq!(tbl.aggregate("sum", "amount"), Ok(SqlValue::Integer(3010)));
+        assert_eq!(tbl.aggregate("max", "amount"), Ok(SqlValue::Integer(10)));
+        assert_eq!(
+            tbl.aggregate("min", "kind"),
+            Ok(SqlValue::String("buy".into()))
+        );
+        assert!(tbl.aggregate("median", "amount").is_err());
+        assert_eq!(
+            ctx.run("INSERT INTO events (id) VALUES (1)")
+                .unwrap_err()
+                .to_string(),
+            "UNIQUE constraint failed: events.id"
+        );
+
+        let ExecutionResult::Select(rs) = ctx
+            .run("SELECT id, kind FROM events WHERE amount = 2 AND kind = 'buy' AND id < 2006")
+            .unwrap()
+        else {
+            panic!("expected rows");
+        };
+        let kind = SqlValue::String("buy".into());
+        assert_eq!(
+            rs.rows,
+            vec![
+                vec![SqlValue::Integer(2001), kind.clone()],
+                vec![SqlValue::Integer(2004), kind]
+            ]
+        );
+
+        // the dump keeps the storage of the table
+        let dump = ctx.dump();
+        assert!(dump.starts_with("CREATE TABLE events (id INT AUTOINCREMENT PRIMARY KEY, kind STRING, amount INT) USING columnar;"));
+        let copy = ExecutionContext::restore(&dump).unwrap();
+        let rows = |ctx: &ExecutionContext| {
+            let tbl = ctx.table("events").unwrap();
+            tbl.iter()
+                .map(|x| x.values.into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rows(&copy), rows(&ctx));
     }
 }
@@ -0,0 +1,29 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::{ExecutionContext, ExecutionResult},
+    parser::SqlValue,
+};
+
+/// A context which ran the statements, each of them must succeed
+pub fn context_with(statements: &[&str]) -> ExecutionContext {
+    let mut ctx = ExecutionContext::new();
+    for stmt in statements {
+        if let Err(e) = ctx.run(stmt) {
+            panic!("{stmt}: {e}");
+        }
+    }
+    ctx
+}
+
+/// The rows of the query, it panics for a statement which returns none
+pub fn rows(ctx: &mut ExecutionContext, sql: &str) -> DbResult<Vec<Vec<SqlValue>>, ExecutionError> {
+    match ctx.run(sql)? {
+        ExecutionResult::Select(rs) => Ok(rs.rows),
+        x => panic!("expected rows, got {x:?}"),
+    }
+}
+
+/// The rows of a single integer column
+pub fn ints(values: &[i64]) -> Vec<Vec<SqlValue>> {
+    values.iter().map(|x| vec![SqlValue::Integer(*x)]).collect()
+}
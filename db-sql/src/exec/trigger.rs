@@ -0,0 +1,236 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::ExecutionContext,
+    parser::{Expr, Identifier, SqlStatement, SqlValue, TriggerEvent, TriggerTiming},
+};
+
+/// How deep the triggers may fire the triggers of the tables they change
+const MAX_TRIGGER_DEPTH: usize = 32;
+
+impl ExecutionContext {
+    /// Whether the table has triggers for the event
+    pub(crate) fn has_triggers(&self, table: &Identifier, event: TriggerEvent) -> bool {
+        self.triggers
+            .iter()
+            .any(|x| &x.table == table && x.event == event)
+    }
+    /// Runs the bodies of the triggers of the table for the change of a row, in the
+    /// order they were created. The columns of `old` and `new` are read as `OLD.col`
+    /// and `NEW.col`.
+    pub(crate) fn fire(
+        &mut self,
+        table: &Identifier,
+        timing: TriggerTiming,
+        event: TriggerEvent,
+        old: Option<&[SqlValue]>,
+        new: Option<&[SqlValue]>,
+    ) -> DbResult<(), ExecutionError> {
+        let triggers: Vec<_> = self
+            .triggers
+            .iter()
+            .filter(|x| &x.table == table && x.timing == timing && x.event == event)
+            .cloned()
+            .collect();
+        if triggers.is_empty() {
+            return Ok(());
+        }
+        if self.trigger_depth >= MAX_TRIGGER_DEPTH {
+            return Err(ExecutionError::InvalidStatement(format!(
+                "too many levels of trigger recursion, trigger {} exceeded {}",
+                triggers[0].name, MAX_TRIGGER_DEPTH
+            )));
+        }
+        let columns = self.get_table(table)?.column_names();
+
+        self.trigger_depth += 1;
+        let mut res = Ok(());
+        for stmt in triggers.iter().flat_map(|x| &x.body) {
+            res = bind_row(stmt, &columns, old, new).and_then(|x| self.exec(&x).map(|_| ()));
+            if res.is_err() {
+                break;
+            }
+        }
+        self.trigger_depth -= 1;
+        res
+    }
+}
+
+impl ExecutionContext {
+    /// The tables a change of the table may change as well, the table first: those
+    /// the deletes cascade to and those the triggers of any of them write to
+    pub(crate) fn affected_tables(&self, table: &Identifier) -> Vec<Identifier> {
+        let mut found = vec![table.clone()];
+        let mut i = 0;
+        while let Some(current) = found.get(i).cloned() {
+            let children = self.referencing(&current).into_iter().map(|x| x.0);
+            let written = self
+                .triggers
+                .iter()
+                .filter(|x| x.table == current)
+                .flat_map(|x| &x.body)
+                .filter_map(|x| match x {
+                    SqlStatement::Insert { table, .. }
+                    | SqlStatement::Update { table, .. }
+                    | SqlStatement::Delete { table, .. } => Some(table.clone()),
+                    _ => None,
+                });
+            for name in children.chain(written).collect::<Vec<_>>() {
+                if !found.contains(&name) {
+                    found.push(name);
+                }
+            }
+            i += 1;
+        }
+        found
+    }
+}
+
+/// Replaces the `NEW.col` and `OLD.col` columns of the statement with the values of
+/// the row
+fn bind_row(
+    stmt: &SqlStatement,
+    columns: &[Identifier],
+    old: Option<&[SqlValue]>,
+    new: Option<&[SqlValue]>,
+) -> DbResult<SqlStatement, ExecutionError> {
+    let mut stmt = stmt.clone();
+    let mut error = None;
+    stmt.visit_exprs(&mut |x| {
+        let Expr::Column(column) = x else {
+            return;
+        };
+        let row = match column.qualifier() {
+            Some(q) if q.eq_ignore_ascii_case("NEW") => new,
+            Some(q) if q.eq_ignore_ascii_case("OLD") => old,
+            _ => return,
+        };
+        let value = row.and_then(|row| {
+            let i = columns.iter().position(|c| c.0 == column.column())?;
+            Some(row[i].clone())
+        });
+        match value {
+            Some(value) => *x = Expr::Literal(value),
+            None => {
+                error.get_or_insert(ExecutionError::ColumnNotFound {
+                    table: column.qualifier().unwrap_or_default().to_uppercase(),
+                    column: column.column().to_string(),
+                });
+            }
+        }
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(stmt),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::test_util::{context_with, rows};
+
+    /// Accounts whose changes are logged by triggers, with account 1 opened
+    fn accounts() -> ExecutionContext {
+        context_with(&[
+            "CREATE TABLE accounts (id INT PRIMARY KEY, balance INT NOT NULL)",
+            "CREATE TABLE log (event STRING, account INT, old INT, new INT)",
+            "CREATE TRIGGER opened AFTER INSERT ON accounts BEGIN INSERT INTO log VALUES ('insert', NEW.id, nil, NEW.balance); END",
+            "CREATE TRIGGER changed BEFORE UPDATE ON accounts FOR EACH ROW BEGIN INSERT INTO log VALUES ('update', OLD.id, OLD.balance, NEW.balance); END",
+            "CREATE TRIGGER closed AFTER DELETE ON accounts BEGIN INSERT INTO log VALUES ('delete', OLD.id, OLD.balance, nil); END",
+            "INSERT INTO accounts VALUES (1, 10)",
+        ])
+    }
+
+    #[test]
+    fn should_fire_triggers() {
+        let mut ctx = accounts();
+        for stmt in [
+            "INSERT INTO accounts VALUES (2, 20)",
+            "UPDATE accounts SET balance = balance + 5 WHERE id = 2",
+            "DELETE accounts WHERE id = 1",
+        ] {
+            ctx.run(stmt).unwrap();
+        }
+        let int = SqlValue::Integer;
+        let row = |event: &str, account, old, new| {
+            vec![SqlValue::String(event.into()), int(account), old, new]
+        };
+        assert_eq!(
+            rows(&mut ctx, "SELECT * FROM log"),
+            Ok(vec![
+                row("insert", 1, SqlValue::Nil, int(10)),
+                row("insert", 2, SqlValue::Nil, int(20)),
+                row("update", 2, int(20), int(25)),
+                row("delete", 1, int(10), SqlValue::Nil),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_undo_the_statement_when_a_trigger_fails() {
+        let mut ctx = accounts();
+        ctx.run(
+            "CREATE TRIGGER guard AFTER INSERT ON accounts BEGIN INSERT INTO accounts VALUES (NEW.id + 100, nil); END",
+        )
+        .unwrap();
+        // the changes of the other triggers are undone too
+        assert!(ctx.run("INSERT INTO accounts VALUES (3, 30)").is_err());
+        assert_eq!(ctx.table("accounts").unwrap().len(), 1);
+        assert_eq!(ctx.table("log").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn should_stop_at_the_recursion_limit() {
+        let mut ctx = accounts();
+        ctx.run(
+            "CREATE TRIGGER again AFTER INSERT ON accounts BEGIN INSERT INTO accounts VALUES (NEW.id + 1, 0); END",
+        )
+        .unwrap();
+        assert!(ctx.run("INSERT INTO accounts VALUES (3, 30)").is_err());
+        assert_eq!(ctx.table("accounts").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn should_reject_unknown_triggers_and_tables() {
+        let mut ctx = accounts();
+        assert_eq!(
+            ctx.run("DROP TRIGGER guard"),
+            Err(ExecutionError::TriggerNotFound("guard".into()))
+        );
+        assert!(
+            ctx.run("CREATE TRIGGER bad AFTER DELETE ON nope BEGIN DELETE log; END")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn should_fail_on_unknown_columns_of_the_changed_row() {
+        let mut ctx = accounts();
+        ctx.run("CREATE TRIGGER typo AFTER DELETE ON accounts BEGIN DELETE log WHERE account = OLD.nope; END")
+            .unwrap();
+        assert_eq!(
+            ctx.run("DELETE accounts"),
+            Err(ExecutionError::ColumnNotFound {
+                table: "OLD".into(),
+                column: "nope".into()
+            })
+        );
+    }
+
+    #[test]
+    fn should_find_the_tables_a_change_affects() {
+        let ctx = context_with(&[
+            "CREATE TABLE users (id INT PRIMARY KEY)",
+            "CREATE TABLE orders (id INT PRIMARY KEY, user_id INT REFERENCES users(id) ON DELETE CASCADE)",
+            "CREATE TABLE log (id INT)",
+            "CREATE TABLE other (id INT)",
+            "CREATE TRIGGER audit AFTER DELETE ON orders BEGIN INSERT INTO log VALUES (OLD.id); END",
+        ]);
+        let names = |table: &str| -> Vec<String> {
+            let found = ctx.affected_tables(&table.into());
+            found.into_iter().map(|x| x.0).collect()
+        };
+        assert_eq!(names("users"), ["users", "orders", "log"]);
+        assert_eq!(names("log"), ["log"]);
+    }
+}
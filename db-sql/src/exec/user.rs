@@ -0,0 +1,397 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::{ExecutionContext, catalog},
+    parser::{CopyDirection, Identifier, Password, Privilege, Query, SqlStatement},
+    types::Uuid,
+};
+use pbkdf2::{
+    Algorithm, Params, Pbkdf2,
+    password_hash::{PasswordHash, PasswordHasher, SaltString},
+};
+use std::sync::LazyLock;
+use subtle::ConstantTimeEq;
+
+/// How many times PBKDF2 hashes a new password, fewer for the tests to stay quick
+const HASH_ROUNDS: u32 = if cfg!(test) {
+    1000
+} else {
+    Params::RECOMMENDED_ROUNDS as u32
+};
+
+/// The user whose hash the passwords of unknown users are checked against, nobody
+/// knows its password
+static NOBODY: LazyLock<User> = LazyLock::new(|| User {
+    name: Identifier::from(""),
+    password: hash_password(&Uuid::new_v4().to_string()),
+    grants: vec![],
+});
+
+/// An account which may log in, granted privileges on tables and views
+#[derive(Clone, Debug, PartialEq)]
+pub struct User {
+    pub name: Identifier,
+    /// the PBKDF2 hash of the password as a PHC string, with its salt and rounds
+    password: String,
+    /// those of a table together, in the order they were granted
+    grants: Vec<(Identifier, Privilege)>,
+}
+impl User {
+    /// The salted hash of the password, which `CREATE USER ... PASSWORD HASH` reads
+    pub fn password_hash(&self) -> &str {
+        &self.password
+    }
+    /// The tables and views with what the user may do with them
+    pub fn grants(&self) -> &[(Identifier, Privilege)] {
+        &self.grants
+    }
+    /// Whether the password hashes to the stored one, compared in constant time
+    fn verify(&self, password: &str) -> bool {
+        let Some((stored, params)) = parse_hash(&self.password) else {
+            return false;
+        };
+        let (Some(salt), Some(expected)) = (stored.salt, stored.hash) else {
+            return false;
+        };
+        let hashed = Pbkdf2.hash_password_customized(
+            password.as_bytes(),
+            Some(stored.algorithm),
+            None,
+            params,
+            salt,
+        );
+        hashed
+            .ok()
+            .and_then(|x| x.hash)
+            .is_some_and(|actual| actual.as_bytes().ct_eq(expected.as_bytes()).into())
+    }
+}
+
+impl ExecutionContext {
+    /// The users by name
+    pub fn users(&self) -> Vec<&User> {
+        let mut users: Vec<_> = self.users.values().collect();
+        users.sort_by(|a, b| a.name.0.cmp(&b.name.0));
+        users
+    }
+    /// The user the statements run as, `None` for the owner of the database
+    pub fn current_user(&self) -> Option<&Identifier> {
+        self.current_user.as_ref()
+    }
+    /// Logs in as the user, the later statements are checked against its
+    /// privileges. Until then the statements run as the owner, who may do anything.
+    pub fn authenticate(&mut self, name: &str, password: &str) -> DbResult<(), ExecutionError> {
+        let name = Identifier::from(name);
+        let user = self.users.get(&name);
+        // the password of an unknown user is hashed all the same, for the name not to
+        // show by how fast the login fails
+        let verified = user.unwrap_or(&NOBODY).verify(password);
+        if user.is_some() && verified {
+            self.current_user = Some(name);
+            Ok(())
+        } else {
+            Err(ExecutionError::AuthenticationFailed(name.0))
+        }
+    }
+
+    pub(super) fn create_user(
+        &mut self,
+        name: &Identifier,
+        password: &Password,
+    ) -> DbResult<(), ExecutionError> {
+        if self.users.contains_key(name) {
+            return Err(ExecutionError::UserAlreadyExists(name.0.clone()));
+        }
+        let password = match password {
+            Password::Plain(x) => hash_password(x),
+            Password::Hashed(x) => {
+                if parse_hash(x).is_none() {
+                    return Err(ExecutionError::InvalidValue {
+                        data_type: "PASSWORD HASH".into(),
+                        value: x.clone(),
+                    });
+                }
+                x.clone()
+            }
+        };
+        let user = User {
+            name: name.clone(),
+            password,
+            grants: vec![],
+        };
+        self.users.insert(name.clone(), user);
+        Ok(())
+    }
+    pub(super) fn drop_user(&mut self, name: &Identifier) -> DbResult<(), ExecutionError> {
+        self.users
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| ExecutionError::UserNotFound(name.0.clone()))
+    }
+    /// Grants the privileges on the table or view to the user, or revokes them
+    pub(super) fn grant(
+        &mut self,
+        privileges: &[Privilege],
+        table: &Identifier,
+        user: &Identifier,
+        revoke: bool,
+    ) -> DbResult<(), ExecutionError> {
+        if self.view(&table.0).is_none() {
+            self.get_table(table)?;
+        }
+        let Some(user) = self.users.get_mut(user) else {
+            return Err(ExecutionError::UserNotFound(user.0.clone()));
+        };
+        for privilege in privileges {
+            let grant = (table.clone(), *privilege);
+            let granted = user.grants.contains(&grant);
+            if revoke {
+                user.grants.retain(|x| x != &grant);
+            } else if !granted {
+                let i = user.grants.iter().rposition(|x| &x.0 == table);
+                user.grants
+                    .insert(i.map_or(user.grants.len(), |i| i + 1), grant);
+            }
+        }
+        Ok(())
+    }
+    /// Revokes the privileges on a dropped table or view
+    pub(super) fn revoke_all(&mut self, table: &Identifier) {
+        for user in self.users.values_mut() {
+            user.grants.retain(|x| &x.0 != table);
+        }
+    }
+    /// Checks the current user may run the statement. Only the owner may change the
+    /// schema or the users, the triggers and views run with the owner's rights.
+    pub(super) fn authorize(&self, stmt: &SqlStatement) -> DbResult<(), ExecutionError> {
+        let Some(name) = &self.current_user else {
+            return Ok(());
+        };
+        let granted = self.users.get(name).map_or(&[][..], |x| x.grants());
+        let require = |privilege: Privilege, table: &Identifier| {
+            if granted.contains(&(table.clone(), privilege)) {
+                Ok(())
+            } else {
+                Err(ExecutionError::PermissionDenied {
+                    user: name.0.clone(),
+                    action: format!("{privilege} on {table}"),
+                })
+            }
+        };
+        let owner_only = |action: &str| {
+            Err(ExecutionError::PermissionDenied {
+                user: name.0.clone(),
+                action: action.to_string(),
+            })
+        };
+        // the system tables can be read by anyone
+        let read = |queries: Vec<&Query>| {
+            for table in queries.iter().flat_map(|x| x.tables()) {
+                if !catalog::is_catalog(table) {
+                    require(Privilege::Select, table)?;
+                }
+            }
+            Ok(())
+        };
+        match stmt {
+            SqlStatement::Select(query) => read(vec![query]),
+            SqlStatement::Insert {
+                table, returning, ..
+            } => {
+                require(Privilege::Insert, table)?;
+                if !returning.is_empty() {
+                    require(Privilege::Select, table)?;
+                }
+                Ok(())
+            }
+            SqlStatement::Update {
+                table,
+                assignments,
+                condition,
+                ..
+            } => {
+                require(Privilege::Update, table)?;
+                let mut queries: Vec<_> =
+                    assignments.iter().flat_map(|x| x.1.subqueries()).collect();
+                queries.extend(condition.iter().flat_map(|x| x.subqueries()));
+                read(queries)
+            }
+            SqlStatement::Delete { table, condition } => {
+                require(Privilege::Delete, table)?;
+                read(condition.iter().flat_map(|x| x.subqueries()).collect())
+            }
+            SqlStatement::Copy {
+                table, direction, ..
+            } => match direction {
+                CopyDirection::From => require(Privilege::Insert, table),
+                CopyDirection::To => require(Privilege::Select, table),
+            },
+            SqlStatement::Create { .. } => owner_only("CREATE TABLE"),
+            SqlStatement::Alter { .. } => owner_only("ALTER TABLE"),
+            SqlStatement::Analyze { .. } => owner_only("ANALYZE"),
+            SqlStatement::CreateView { .. } => owner_only("CREATE VIEW"),
+            SqlStatement::DropView { .. } => owner_only("DROP VIEW"),
+            SqlStatement::CreateIndex { .. } => owner_only("CREATE INDEX"),
+            SqlStatement::DropIndex { .. } => owner_only("DROP INDEX"),
+            SqlStatement::CreateTrigger(_) => owner_only("CREATE TRIGGER"),
+            SqlStatement::DropTrigger { .. } => owner_only("DROP TRIGGER"),
+            SqlStatement::CreateUser { .. } => owner_only("CREATE USER"),
+            SqlStatement::DropUser { .. } => owner_only("DROP USER"),
+            SqlStatement::Grant { .. } => owner_only("GRANT"),
+            SqlStatement::Revoke { .. } => owner_only("REVOKE"),
+        }
+    }
+}
+
+/// The PBKDF2-SHA256 hash of the password with a random salt, as a PHC string
+fn hash_password(password: &str) -> String {
+    let salt = SaltString::encode_b64(Uuid::new_v4().as_bytes()).expect("a valid salt");
+    let params = Params {
+        rounds: HASH_ROUNDS,
+        ..Params::default()
+    };
+    Pbkdf2
+        .hash_password_customized(password.as_bytes(), None, None, params, &salt)
+        .expect("a valid password hash")
+        .to_string()
+}
+
+/// The hash of a PHC string made by `hash_password`, with its parameters
+fn parse_hash(hash: &str) -> Option<(PasswordHash<'_>, Params)> {
+    let hash = PasswordHash::new(hash).ok()?;
+    Algorithm::try_from(hash.algorithm).ok()?;
+    let params = Params::try_from(&hash).ok()?;
+    hash.salt?;
+    hash.hash?;
+    Some((hash, params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::{ExecutionResult, test_util::context_with};
+
+    /// Alice may read and insert users, and only insert, update and delete secrets
+    fn granted() -> ExecutionContext {
+        context_with(&[
+            "CREATE TABLE users (id INT, name STRING)",
+            "CREATE TABLE secrets (id INT)",
+            "INSERT INTO users VALUES (1, 'a')",
+            "CREATE USER alice WITH PASSWORD 's3cret'",
+            "GRANT SELECT, INSERT ON users TO alice",
+            "GRANT ALL ON secrets TO alice",
+            "REVOKE SELECT ON secrets FROM alice",
+        ])
+    }
+    fn logged_in() -> ExecutionContext {
+        let mut ctx = granted();
+        ctx.authenticate("alice", "s3cret").unwrap();
+        ctx
+    }
+    fn denied(action: &str) -> DbResult<ExecutionResult, ExecutionError> {
+        Err(ExecutionError::PermissionDenied {
+            user: "alice".into(),
+            action: action.into(),
+        })
+    }
+
+    #[test]
+    fn should_authenticate_with_the_password() {
+        let mut ctx = granted();
+        assert_eq!(
+            ctx.authenticate("alice", "wrong"),
+            Err(ExecutionError::AuthenticationFailed("alice".into()))
+        );
+        assert_eq!(
+            ctx.authenticate("nobody", "s3cret"),
+            Err(ExecutionError::AuthenticationFailed("nobody".into()))
+        );
+        assert_eq!(ctx.current_user(), None);
+        ctx.authenticate("alice", "s3cret").unwrap();
+        assert_eq!(ctx.current_user(), Some(&"alice".into()));
+    }
+
+    #[test]
+    fn should_allow_the_granted_privileges() {
+        let mut ctx = logged_in();
+        assert!(matches!(
+            ctx.run("SELECT name FROM users"),
+            Ok(ExecutionResult::Select(_))
+        ));
+        assert!(ctx.run("INSERT INTO users VALUES (2, 'b')").is_ok());
+        assert!(ctx.run("SELECT name FROM db_tables").is_ok());
+        assert_eq!(
+            ctx.run("DELETE secrets WHERE id = (SELECT id FROM users)"),
+            Ok(ExecutionResult::Delete(0))
+        );
+    }
+
+    #[test]
+    fn should_deny_the_other_privileges() {
+        let mut ctx = logged_in();
+        assert_eq!(
+            ctx.run("UPDATE users SET name = 'x'"),
+            denied("UPDATE on users")
+        );
+        assert_eq!(
+            ctx.run("SELECT id FROM secrets"),
+            denied("SELECT on secrets")
+        );
+    }
+
+    #[test]
+    fn should_check_the_subqueries() {
+        let mut ctx = logged_in();
+        assert_eq!(
+            ctx.run("DELETE users WHERE id = (SELECT id FROM secrets)"),
+            denied("DELETE on users")
+        );
+        assert_eq!(
+            ctx.run("UPDATE secrets SET id = 1 WHERE id = (SELECT id FROM secrets)"),
+            denied("SELECT on secrets")
+        );
+        assert_eq!(
+            ctx.run("SELECT u.name FROM users u JOIN secrets s ON s.id = u.id"),
+            denied("SELECT on secrets")
+        );
+        assert_eq!(
+            ctx.run("SELECT * FROM users u JOIN users v ON u.id IN (SELECT id FROM secrets)"),
+            denied("SELECT on secrets")
+        );
+    }
+
+    #[test]
+    fn should_leave_the_schema_to_the_owner() {
+        let mut ctx = logged_in();
+        assert_eq!(ctx.run("GRANT ALL ON secrets TO alice"), denied("GRANT"));
+        assert_eq!(ctx.run("CREATE TABLE x (id INT)"), denied("CREATE TABLE"));
+    }
+
+    #[test]
+    fn should_store_the_password_hashed() {
+        let ctx = granted();
+        let user = &ctx.users()[0];
+        assert!(!user.password_hash().contains("s3cret"));
+        assert!(
+            user.password_hash()
+                .starts_with("$pbkdf2-sha256$i=1000,l=32$")
+        );
+        assert_eq!(user.grants().len(), 5);
+    }
+
+    #[test]
+    fn should_authenticate_with_the_hash_of_a_dump() {
+        let ctx = granted();
+        let mut restored = ExecutionContext::restore(&ctx.dump()).unwrap();
+        assert_eq!(restored.users(), ctx.users());
+        assert!(restored.authenticate("alice", "s3creT").is_err());
+        assert!(restored.authenticate("alice", "s3cret").is_ok());
+    }
+
+    #[test]
+    fn should_reject_invalid_password_hashes() {
+        assert!(matches!(
+            ExecutionContext::new().run("CREATE USER bob PASSWORD HASH 'ab$cd'"),
+            Err(ExecutionError::InvalidValue { .. })
+        ));
+    }
+}
@@ -0,0 +1,343 @@
+use crate::{
+    errors::{DbResult, ExecutionError},
+    exec::{ExecutionContext, StoredRow, eval, eval::Scope, query::same_row},
+    parser::{ArithmeticOperator, Identifier, OrderBy, SqlValue, Window},
+    types::Decimal,
+};
+use std::cmp::Ordering;
+
+impl ExecutionContext {
+    /// Computes the window function for each of the rows, in their order. The
+    /// aggregates of an ordered window run from the first row of the partition to
+    /// the last row sorted the same as the current one.
+    pub(crate) fn window(
+        &self,
+        window: &Window,
+        table: &Identifier,
+        columns: &[Identifier],
+        rows: &[&StoredRow],
+        outer: Option<&Scope>,
+    ) -> DbResult<Vec<SqlValue>, ExecutionError> {
+        let name = window.function.0.to_lowercase();
+        let args = window.args.len();
+        let expected = match name.as_str() {
+            "row_number" | "rank" | "dense_rank" => 0,
+            "lag" | "lead" => args.clamp(1, 3),
+            "count" => args.min(1),
+            "sum" | "avg" | "min" | "max" => 1,
+            _ => return Err(ExecutionError::UnknownFunction(window.function.0.clone())),
+        };
+        if args != expected {
+            return Err(ExecutionError::ArgumentCountMismatch {
+                function: window.function.0.clone(),
+                expected,
+                found: args,
+            });
+        }
+
+        // the arguments, partition and sort keys of each row
+        let mut evaluated = vec![];
+        for values in rows {
+            let scope = Scope {
+                table,
+                columns,
+                values,
+                outer,
+            };
+            let eval = |xs: &mut dyn Iterator<Item = _>| -> DbResult<Vec<_>, _> {
+                xs.map(|x| eval::evaluate(x, self, &scope)).collect()
+            };
+            evaluated.push((
+                eval(&mut window.args.iter())?,
+                eval(&mut window.partition_by.iter())?,
+                eval(&mut window.order_by.iter().map(|x| &x.expr))?,
+            ));
+        }
+        let mut partitions: Vec<(&[SqlValue], Vec<usize>)> = vec![];
+        for (i, (_, key, _)) in evaluated.iter().enumerate() {
+            match partitions.iter_mut().find(|x| same_row(x.0, key)) {
+                Some(x) => x.1.push(i),
+                None => partitions.push((key, vec![i])),
+            }
+        }
+
+        let mut result = vec![SqlValue::Nil; rows.len()];
+        for (_, mut ids) in partitions {
+            ids.sort_by(|a, b| sort(&evaluated[*a].2, &evaluated[*b].2, &window.order_by));
+            let peers = |a: usize, b: usize| {
+                sort(&evaluated[a].2, &evaluated[b].2, &window.order_by) == Ordering::Equal
+            };
+            let mut rank = 0;
+            let mut dense_rank = 0;
+            let mut aggregate = Aggregate::default();
+            let mut added = 0;
+            for (pos, id) in ids.iter().enumerate() {
+                let new_peers = pos == 0 || !peers(ids[pos - 1], *id);
+                if new_peers {
+                    rank = pos + 1;
+                    dense_rank += 1;
+                }
+                let args = &evaluated[*id].0;
+                result[*id] = match name.as_str() {
+                    "row_number" => SqlValue::Integer(pos as i64 + 1),
+                    "rank" => SqlValue::Integer(rank as i64),
+                    "dense_rank" => SqlValue::Integer(dense_rank),
+                    "lag" | "lead" => {
+                        let offset = match args.get(1) {
+                            None => 1,
+                            Some(SqlValue::Integer(x)) if *x >= 0 => *x as usize,
+                            Some(x) => {
+                                return Err(ExecutionError::TypeMismatch {
+                                    expected: "a non-negative integer offset".into(),
+                                    found: x.to_literal(),
+                                });
+                            }
+                        };
+                        let other = match name.as_str() {
+                            "lag" => pos.checked_sub(offset),
+                            _ => pos.checked_add(offset),
+                        };
+                        match other.and_then(|x| ids.get(x)) {
+                            Some(x) => evaluated[*x].0[0].clone(),
+                            None => args.get(2).cloned().unwrap_or(SqlValue::Nil),
+                        }
+                    }
+                    _ => {
+                        // the frame ends with the last peer, or the partition when unordered
+                        let end = if window.order_by.is_empty() {
+                            ids.len()
+                        } else {
+                            (pos..ids.len())
+                                .find(|x| !peers(*id, ids[*x]))
+                                .unwrap_or(ids.len())
+                        };
+                        for x in ids.get(added..end).unwrap_or_default() {
+                            aggregate.add(&name, evaluated[*x].0.first())?;
+                        }
+                        added = added.max(end);
+                        aggregate.value(&name)?
+                    }
+                };
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Orders the sort keys of two rows, nil before the other values
+fn sort(a: &[SqlValue], b: &[SqlValue], order_by: &[OrderBy]) -> Ordering {
+    for ((a, b), key) in a.iter().zip(b).zip(order_by) {
+        let ord = match (a, b) {
+            (SqlValue::Nil, SqlValue::Nil) => Ordering::Equal,
+            (SqlValue::Nil, _) => Ordering::Less,
+            (_, SqlValue::Nil) => Ordering::Greater,
+            _ => a.compare(b).unwrap_or(Ordering::Equal),
+        };
+        let ord = if key.descending { ord.reverse() } else { ord };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    Ordering::Equal
+}
+
+/// The running state of an aggregate, nil values are skipped
+#[derive(Default)]
+pub(super) struct Aggregate {
+    count: i64,
+    sum: Option<SqlValue>,
+    min: Option<SqlValue>,
+    max: Option<SqlValue>,
+}
+
+impl Aggregate {
+    /// Adds the argument of a row, `None` for `COUNT(*)` which counts every row
+    pub(super) fn add(
+        &mut self,
+        name: &str,
+        value: Option<&SqlValue>,
+    ) -> DbResult<(), ExecutionError> {
+        let value = match value {
+            None => {
+                self.count += 1;
+                return Ok(());
+            }
+            Some(SqlValue::Nil) => return Ok(()),
+            Some(x) => x,
+        };
+        self.count += 1;
+        match name {
+            "sum" | "avg" if !value.is_numeric() => {
+                return Err(ExecutionError::TypeMismatch {
+                    expected: "a number".into(),
+                    found: value.type_name().to_string(),
+                });
+            }
+            "sum" | "avg" => {
+                self.sum = Some(match &self.sum {
+                    Some(sum) => eval::arithmetic(sum, ArithmeticOperator::Add, value)?,
+                    None => value.clone(),
+                });
+            }
+            "min"
+                if self
+                    .min
+                    .as_ref()
+                    .is_none_or(|x| value.compare(x) == Some(Ordering::Less)) =>
+            {
+                self.min = Some(value.clone());
+            }
+            "max"
+                if self
+                    .max
+                    .as_ref()
+                    .is_none_or(|x| value.compare(x) == Some(Ordering::Greater)) =>
+            {
+                self.max = Some(value.clone());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+    /// Same as `add` for `n` rows of the same value, e.g. a run of a columnar table
+    pub(super) fn add_repeated(
+        &mut self,
+        name: &str,
+        value: &SqlValue,
+        n: usize,
+    ) -> DbResult<(), ExecutionError> {
+        if n == 0 || *value == SqlValue::Nil {
+            return Ok(());
+        }
+        if matches!(name, "sum" | "avg") && value.is_numeric() {
+            let n = SqlValue::Integer(n as i64);
+            self.add(
+                name,
+                Some(&eval::arithmetic(value, ArithmeticOperator::Multiply, &n)?),
+            )?;
+        } else {
+            self.add(name, Some(value))?;
+        }
+        self.count += n as i64 - 1;
+        Ok(())
+    }
+    pub(super) fn value(&self, name: &str) -> DbResult<SqlValue, ExecutionError> {
+        let value = match name {
+            "count" => Some(SqlValue::Integer(self.count)),
+            "sum" => self.sum.clone(),
+            "avg" => match &self.sum {
+                Some(sum) => Some(eval::arithmetic(
+                    sum,
+                    ArithmeticOperator::Divide,
+                    &SqlValue::Decimal(Decimal::from(self.count)),
+                )?),
+                None => None,
+            },
+            "min" => self.min.clone(),
+            _ => self.max.clone(),
+        };
+        Ok(value.unwrap_or(SqlValue::Nil))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::test_util::{context_with, rows};
+
+    fn int(x: i64) -> SqlValue {
+        SqlValue::Integer(x)
+    }
+
+    /// Three sales in the north and two in the south, one without an amount
+    fn sales() -> ExecutionContext {
+        context_with(&[
+            "CREATE TABLE sales (id INT PRIMARY KEY, region STRING, amount INT)",
+            "INSERT INTO sales VALUES (1, 'north', 10)",
+            "INSERT INTO sales VALUES (2, 'south', 30)",
+            "INSERT INTO sales VALUES (3, 'north', 20)",
+            "INSERT INTO sales VALUES (4, 'north', 20)",
+            "INSERT INTO sales VALUES (5, 'south', nil)",
+        ])
+    }
+
+    #[test]
+    fn should_number_and_rank_rows() {
+        let mut ctx = sales();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT id, ROW_NUMBER() OVER (PARTITION BY region ORDER BY amount DESC, id), RANK() OVER (ORDER BY amount) FROM sales WHERE id < 5"
+            ),
+            Ok(vec![
+                vec![int(1), int(3), int(1)],
+                vec![int(2), int(1), int(4)],
+                vec![int(3), int(1), int(2)],
+                vec![int(4), int(2), int(2)],
+            ])
+        );
+    }
+
+    #[test]
+    fn should_read_the_previous_and_next_rows() {
+        let mut ctx = sales();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT LAG(amount) OVER (ORDER BY id), LEAD(amount, 2, 0) OVER (ORDER BY id) FROM sales WHERE region = 'north'"
+            ),
+            Ok(vec![
+                vec![SqlValue::Nil, int(20)],
+                vec![int(10), int(0)],
+                vec![int(20), int(0)],
+            ])
+        );
+    }
+
+    #[test]
+    fn should_aggregate_up_to_the_peers_or_over_partitions() {
+        let mut ctx = sales();
+        // a running total with peers, and totals of whole partitions
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT SUM(amount) OVER (ORDER BY amount), COUNT(*) OVER (PARTITION BY region), COUNT(amount) OVER (PARTITION BY region), MAX(amount) OVER (PARTITION BY region) - amount FROM sales"
+            ),
+            Ok(vec![
+                vec![int(10), int(3), int(3), int(10)],
+                vec![int(80), int(2), int(1), int(0)],
+                vec![int(50), int(3), int(3), int(0)],
+                vec![int(50), int(3), int(3), int(0)],
+                vec![SqlValue::Nil, int(2), int(1), SqlValue::Nil],
+            ])
+        );
+    }
+
+    #[test]
+    fn should_average_to_a_decimal() {
+        let mut ctx = sales();
+        assert_eq!(
+            rows(
+                &mut ctx,
+                "SELECT AVG(amount) OVER (PARTITION BY region) FROM sales WHERE id = 1"
+            ),
+            Ok(vec![vec![SqlValue::Decimal("10.000000".parse().unwrap())]])
+        );
+    }
+
+    #[test]
+    fn should_reject_window_functions_in_conditions() {
+        let mut ctx = sales();
+        assert!(
+            ctx.run("SELECT id FROM sales WHERE ROW_NUMBER() OVER () = 1")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn should_reject_unknown_window_functions_and_arguments() {
+        let mut ctx = sales();
+        assert!(ctx.run("SELECT NOPE() OVER () FROM sales").is_err());
+        assert!(ctx.run("SELECT RANK(id) OVER () FROM sales").is_err());
+    }
+}
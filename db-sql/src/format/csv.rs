@@ -0,0 +1,141 @@
+use crate::{exec::ResultSet, parser::SqlValue};
+
+/// A parsed CSV line, the line number it started on and its fields. An empty field
+/// which was not quoted is `None`.
+pub type CsvRecord = (usize, Vec<Option<String>>);
+
+/// Quotes the field when it contains a separator, a quote or a line break.
+/// An empty string is quoted too, to tell it apart from a missing value.
+pub fn escape(field: &str) -> String {
+    if field.is_empty() || field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Formats the values as a CSV line, nil is left empty.
+pub fn format_row(row: &[SqlValue]) -> String {
+    let fields: Vec<_> = row
+        .iter()
+        .map(|x| match x {
+            SqlValue::Nil => String::new(),
+            _ => escape(&x.to_string()),
+        })
+        .collect();
+    fields.join(",")
+}
+
+pub fn format(rs: &ResultSet) -> String {
+    let mut lines = vec![];
+    let header: Vec<_> = rs.columns.iter().map(|x| escape(x)).collect();
+    lines.push(header.join(","));
+    for row in rs.rows.iter() {
+        lines.push(format_row(row));
+    }
+
+    lines.join("\n")
+}
+
+/// Parses CSV text following RFC 4180: fields may be quoted to contain separators,
+/// line breaks and doubled quotes. Blank lines are skipped.
+pub fn parse(input: &str) -> Result<Vec<CsvRecord>, (usize, String)> {
+    let mut records = vec![];
+    let mut chars = input.chars().peekable();
+    let mut line = 1;
+
+    while chars.peek().is_some() {
+        let start = line;
+        let mut fields = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        loop {
+            match chars.next() {
+                Some('"') if field.is_empty() && !quoted => {
+                    quoted = true;
+                    loop {
+                        match chars.next() {
+                            Some('"') if chars.peek() == Some(&'"') => {
+                                chars.next();
+                                field.push('"');
+                            }
+                            Some('"') => break,
+                            Some(c) => {
+                                if c == '\n' {
+                                    line += 1;
+                                }
+                                field.push(c);
+                            }
+                            None => return Err((start, "unterminated quoted field".into())),
+                        }
+                    }
+                    if !matches!(chars.peek(), None | Some(',' | '\r' | '\n')) {
+                        return Err((line, "unexpected character after a quoted field".into()));
+                    }
+                }
+                Some(',') => {
+                    fields.push(take_field(&mut field, &mut quoted));
+                }
+                Some('\r') if chars.peek() == Some(&'\n') => continue,
+                Some('\n') | None => {
+                    line += 1;
+                    fields.push(take_field(&mut field, &mut quoted));
+                    break;
+                }
+                Some(c) => field.push(c),
+            }
+        }
+        let blank = fields.len() == 1 && fields[0].is_none();
+        if !blank {
+            records.push((start, fields));
+        }
+    }
+
+    Ok(records)
+}
+fn take_field(field: &mut String, quoted: &mut bool) -> Option<String> {
+    let res = if field.is_empty() && !*quoted {
+        None
+    } else {
+        Some(std::mem::take(field))
+    };
+    *quoted = false;
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_csv() {
+        let input = "id,name\r\n1,\"doe, \"\"jo\"\"\"\n\n2,\n3,\"multi\nline\"\n4,\"\"";
+        let records = parse(input).unwrap();
+        assert_eq!(
+            records,
+            vec![
+                (1, vec![Some("id".into()), Some("name".into())]),
+                (2, vec![Some("1".into()), Some("doe, \"jo\"".into())]),
+                (4, vec![Some("2".into()), None]),
+                (5, vec![Some("3".into()), Some("multi\nline".into())]),
+                (7, vec![Some("4".into()), Some("".into())]),
+            ]
+        );
+        assert_eq!(
+            parse("1,\"open\n2,x"),
+            Err((1, "unterminated quoted field".into()))
+        );
+    }
+
+    #[test]
+    fn should_format_csv() {
+        let rs = ResultSet {
+            columns: vec!["id".into(), "name".into()],
+            rows: vec![
+                vec![SqlValue::Integer(1), SqlValue::String("doe, \"jo\"".into())],
+                vec![SqlValue::Integer(2), SqlValue::Nil],
+            ],
+        };
+        assert_eq!(format(&rs), "id,name\n1,\"doe, \"\"jo\"\"\"\n2,");
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{exec::ResultSet, parser::SqlValue};
+
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn value(val: &SqlValue) -> String {
+    match val {
+        SqlValue::String(s) => escape(s),
+        SqlValue::Float(x) if !x.is_finite() => "null".into(),
+        SqlValue::Boolean(_) | SqlValue::Integer(_) | SqlValue::Float(_) | SqlValue::Decimal(_) => {
+            val.to_string()
+        }
+        SqlValue::Date(_) | SqlValue::Timestamp(_) | SqlValue::Blob(_) | SqlValue::Uuid(_) => {
+            escape(&val.to_string())
+        }
+        SqlValue::Json(x) => x.to_string(),
+        SqlValue::Nil | SqlValue::Placeholder => "null".into(),
+    }
+}
+
+/// Formats the rows as JSON lines, an object per row keyed by the column names
+pub fn format(rs: &ResultSet) -> String {
+    rs.rows
+        .iter()
+        .map(|row| {
+            let fields: Vec<_> = rs
+                .columns
+                .iter()
+                .zip(row)
+                .map(|(col, val)| format!("{}:{}", escape(col), value(val)))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_format_json_lines() {
+        let rs = ResultSet {
+            columns: vec!["id".into(), "name".into(), "active".into()],
+            rows: vec![
+                vec![
+                    SqlValue::Integer(1),
+                    SqlValue::String("say \"hi\"".into()),
+                    SqlValue::Boolean(true),
+                ],
+                vec![
+                    SqlValue::Float(1.5),
+                    SqlValue::Nil,
+                    SqlValue::Boolean(false),
+                ],
+            ],
+        };
+        assert_eq!(
+            format(&rs),
+            "{\"id\":1,\"name\":\"say \\\"hi\\\"\",\"active\":true}\n{\"id\":1.5,\"name\":null,\"active\":false}"
+        );
+    }
+}
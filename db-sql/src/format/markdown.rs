@@ -0,0 +1,33 @@
+use crate::exec::ResultSet;
+
+fn escape(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', " ")
+}
+
+pub fn format(rs: &ResultSet) -> String {
+    let mut lines = vec![];
+    let header: Vec<_> = rs.columns.iter().map(|x| escape(x)).collect();
+    lines.push(format!("| {} |", header.join(" | ")));
+    lines.push(format!("|{}", "---|".repeat(rs.columns.len())));
+    for row in rs.rows.iter() {
+        let cells: Vec<_> = row.iter().map(|x| escape(&x.to_string())).collect();
+        lines.push(format!("| {} |", cells.join(" | ")));
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SqlValue;
+
+    #[test]
+    fn should_format_markdown_table() {
+        let rs = ResultSet {
+            columns: vec!["id".into(), "name".into()],
+            rows: vec![vec![SqlValue::Integer(1), SqlValue::String("a|b".into())]],
+        };
+        assert_eq!(format(&rs), "| id | name |\n|---|---|\n| 1 | a\\|b |");
+    }
+}
@@ -0,0 +1,49 @@
+pub mod csv;
+pub(crate) mod json;
+mod markdown;
+mod table;
+
+use crate::exec::ResultSet;
+
+/// The ways a result set can be rendered for the user
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// aligned ASCII table
+    #[default]
+    Table,
+    /// comma separated values with a header line
+    Csv,
+    /// one JSON object per row
+    Json,
+    /// markdown table
+    Markdown,
+}
+
+impl OutputFormat {
+    pub fn format(&self, rs: &ResultSet) -> String {
+        match self {
+            OutputFormat::Table => table::format(rs),
+            OutputFormat::Csv => csv::format(rs),
+            OutputFormat::Json => json::format(rs),
+            OutputFormat::Markdown => markdown::format(rs),
+        }
+    }
+}
+
+impl TryFrom<&str> for OutputFormat {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(match value.to_lowercase().as_str() {
+            "table" => OutputFormat::Table,
+            "csv" => OutputFormat::Csv,
+            "json" => OutputFormat::Json,
+            "markdown" | "md" => OutputFormat::Markdown,
+            _ => {
+                return Err(format!(
+                    "unknown format {value}, expected table|csv|json|markdown"
+                ));
+            }
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use crate::exec::ResultSet;
+
+pub fn format(rs: &ResultSet) -> String {
+    let cells: Vec<Vec<String>> = rs
+        .rows
+        .iter()
+        .map(|row| row.iter().map(|x| x.to_string()).collect())
+        .collect();
+    let mut widths: Vec<usize> = rs.columns.iter().map(|x| x.chars().count()).collect();
+    for row in cells.iter() {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let separator = widths
+        .iter()
+        .map(|w| "-".repeat(w + 2))
+        .collect::<Vec<_>>()
+        .join("+");
+    let separator = format!("+{separator}+");
+
+    let mut lines = vec![separator.clone()];
+    let header = rs
+        .columns
+        .iter()
+        .zip(widths.iter())
+        .map(|(name, w)| format!(" {name:<w$} "))
+        .collect::<Vec<_>>();
+    lines.push(format!("|{}|", header.join("|")));
+    lines.push(separator.clone());
+    for (row, values) in cells.iter().zip(rs.rows.iter()) {
+        let row = row
+            .iter()
+            .zip(values)
+            .zip(widths.iter())
+            .map(|((cell, value), w)| match value {
+                // numbers are right aligned
+                x if x.is_numeric() => format!(" {cell:>w$} "),
+                _ => format!(" {cell:<w$} "),
+            })
+            .collect::<Vec<_>>();
+        lines.push(format!("|{}|", row.join("|")));
+    }
+    if !rs.rows.is_empty() {
+        lines.push(separator);
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SqlValue;
+
+    #[test]
+    fn should_format_aligned_table() {
+        let rs = ResultSet {
+            columns: vec!["id".into(), "name".into()],
+            rows: vec![
+                vec![SqlValue::Integer(1), SqlValue::String("alice".into())],
+                vec![SqlValue::Integer(20), SqlValue::Nil],
+            ],
+        };
+        assert_eq!(
+            format(&rs),
+            "\
++----+-------+
+| id | name  |
++----+-------+
+|  1 | alice |
+| 20 | nil   |
++----+-------+"
+        );
+    }
+}
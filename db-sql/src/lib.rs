@@ -0,0 +1,8 @@
+pub mod database;
+pub mod errors;
+pub mod exec;
+pub mod format;
+pub mod parser;
+pub mod types;
+
+pub use database::*;
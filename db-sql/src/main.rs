@@ -1,58 +1,102 @@
-mod errors;
-mod exec;
-mod parser;
+mod cli;
+mod repl;
 
-use crate::exec::ExecutionContext;
-use std::io::{Write, stdin};
+use crate::{
+    cli::{Args, EXIT_FAILURE, EXIT_IO, EXIT_USAGE, Input, PASSWORD_VAR, USAGE},
+    repl::Repl,
+};
+use db_sql::exec::ExecutionContext;
+use std::io::{IsTerminal, Read};
 
 fn main() {
-    wall();
-    repl();
+    std::process::exit(run());
 }
 
-//https://cstack.github.io/db_tutorial/parts/part1.html
-// https://johns.codes/blog/build-a-db/part01
-//https://medium.com/@krizzsrivastava/retr0db-building-a-database-in-rust-b223e2b98cbd
+/// Runs what the arguments ask for and returns the exit status
+fn run() -> i32 {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("Error: {e}\n\n{USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+    if args.help {
+        println!("{USAGE}");
+        return 0;
+    }
+    if args.version {
+        println!("db-sql {}", env!("CARGO_PKG_VERSION"));
+        return 0;
+    }
+    let ctx = match &args.path {
+        Some(path) if args.readonly => ExecutionContext::open_readonly(path),
+        Some(path) => ExecutionContext::open(path),
+        None => Ok(ExecutionContext::new()),
+    };
+    let mut repl = match ctx {
+        Ok(ctx) => Repl::with_context(ctx, args.format),
+        Err(e) => {
+            let path = args.path.unwrap_or_default();
+            eprintln!("Error: cannot open {}: {e}", path.display());
+            return EXIT_IO;
+        }
+    };
 
-fn repl() {
-    let mut ctx = ExecutionContext::new();
+    if let Some(user) = &args.user {
+        let login = match std::env::var(PASSWORD_VAR) {
+            Ok(password) => Ok(password),
+            Err(_) => rpassword::prompt_password("Password: "),
+        }
+        .map_err(|e| e.into())
+        .and_then(|x| repl.login(user, &x));
+        if let Err(e) = login {
+            eprintln!("Error: {e}");
+            return EXIT_FAILURE;
+        }
+    }
 
-    loop {
-        print!("> ");
-        std::io::stdout().flush().unwrap();
-        match stdin().lines().next() {
-            Some(Ok(line)) => {
-                if line.trim() == "exit" {
-                    println!("exiting ...");
-                    break;
+    if args.inputs.is_empty() && std::io::stdin().is_terminal() {
+        wall();
+        if let Err(e) = repl.run() {
+            eprintln!("Error: {e}");
+            return EXIT_IO;
+        }
+        return 0;
+    }
+    if args.inputs.is_empty() {
+        // the statements are piped in
+        let mut script = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut script) {
+            eprintln!("Error: cannot read the standard input: {e}");
+            return EXIT_IO;
+        }
+        repl.run_script(&script);
+    }
+    for input in args.inputs {
+        match input {
+            Input::Sql(sql) => repl.run_script(&sql),
+            Input::File(path) => {
+                if let Err(e) = repl.run_file(&path) {
+                    eprintln!("Error: cannot read {}: {e}", path.display());
+                    return EXIT_IO;
                 }
-                let _ = ctx.run(line.as_str());
-            }
-            Some(Err(e)) if e.kind() == std::io::ErrorKind::Interrupted => {
-                // CTRL + C
-                continue;
-            }
-            Some(Err(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-                // CTRL + D
-                return;
-            }
-            Some(Err(e)) => {
-                println!("Error: {}", e);
-                return;
-            }
-            _ => {
-                println!("received empty line iterator");
-                continue;
             }
         }
     }
+    if repl.errors() > 0 { EXIT_FAILURE } else { 0 }
 }
 
+//https://cstack.github.io/db_tutorial/parts/part1.html
+// https://johns.codes/blog/build-a-db/part01
+//https://medium.com/@krizzsrivastava/retr0db-building-a-database-in-rust-b223e2b98cbd
+
 fn wall() {
     println!("Welcome to DB SQL");
     println!(" ** This is a try to create a database for practicing Rust **");
     println!("Version: 0.1.0");
     println!("Help:");
-    println!("Just write your SQL Command/Query here");
+    println!("Just write your SQL Command/Query here, terminated by ';'");
+    println!("Enter .help for the list of the commands");
     println!("-----------------------------------------------------");
 }
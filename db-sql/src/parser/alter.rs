@@ -0,0 +1,62 @@
+use crate::parser::{SqlStatement, identifier, keyword};
+use nom::{
+    IResult, Parser,
+    character::complete::{char, i64, space0, space1},
+    combinator::map,
+    sequence::preceded,
+};
+use std::fmt::{Display, Formatter};
+
+/// The change an `ALTER TABLE` makes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AlterAction {
+    /// `AUTO_INCREMENT = n`, the next value of the sequence of the table
+    AutoIncrement(i64),
+}
+impl Display for AlterAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlterAction::AutoIncrement(x) => write!(f, "AUTO_INCREMENT = {x}"),
+        }
+    }
+}
+
+fn alter_action(input: &str) -> IResult<&str, AlterAction> {
+    map(
+        preceded((keyword("AUTO_INCREMENT"), space0, char('='), space0), i64),
+        AlterAction::AutoIncrement,
+    )
+    .parse(input)
+}
+pub fn parse_alter_statement(input: &str) -> IResult<&str, SqlStatement> {
+    map(
+        (
+            preceded(
+                (space0, keyword("ALTER"), space1, keyword("TABLE"), space1),
+                identifier,
+            ),
+            preceded(space1, alter_action),
+        ),
+        |(table, action)| SqlStatement::Alter { table, action },
+    )
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_alter_statement() {
+        let input = "ALTER TABLE users AUTO_INCREMENT = 100";
+        let (_, stmt) = parse_alter_statement(input).unwrap();
+        assert_eq!(
+            stmt,
+            SqlStatement::Alter {
+                table: "users".into(),
+                action: AlterAction::AutoIncrement(100),
+            }
+        );
+        assert_eq!(stmt.to_string(), input);
+    }
+}
@@ -0,0 +1,38 @@
+use crate::parser::{SqlStatement, identifier, keyword};
+use nom::{
+    IResult, Parser,
+    character::complete::{space0, space1},
+    combinator::{map, opt},
+    sequence::preceded,
+};
+
+/// `ANALYZE [table]`, all the tables without one
+pub fn parse_analyze(input: &str) -> IResult<&str, SqlStatement> {
+    map(
+        preceded(
+            (space0, keyword("ANALYZE")),
+            opt(preceded(space1, identifier)),
+        ),
+        |table| SqlStatement::Analyze { table },
+    )
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_analyze() {
+        let (_, stmt) = parse_analyze("analyze users").unwrap();
+        assert_eq!(
+            stmt,
+            SqlStatement::Analyze {
+                table: Some("users".into())
+            }
+        );
+        assert_eq!(stmt.to_string(), "ANALYZE users");
+        assert_eq!(parse_analyze("ANALYZE").unwrap().1.to_string(), "ANALYZE");
+        assert!(parse_analyze("ANALYZED").is_err());
+    }
+}
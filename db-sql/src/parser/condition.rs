@@ -1,25 +1,39 @@
-use crate::parser::{Identifier, SqlValue, identifier, parse_sql_value};
+use crate::parser::{Expr, Identifier, Query, expr::parse_expr, keyword, nested, select::query};
+use std::fmt::{Display, Formatter};
+
 use nom::{
     IResult, Parser,
     branch::alt,
     bytes::tag_no_case,
     character::char,
+    character::complete::satisfy,
     character::complete::{space0, space1},
-    combinator::{cut, map, opt},
+    combinator::{cut, map, not, opt, peek, verify},
+    multi::many0,
     sequence::{delimited, preceded},
 };
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum Condition {
     Comparison {
-        left: Identifier,
+        left: Expr,
         operator: Operator,
-        right: SqlValue,
+        right: Expr,
     },
     Logical {
         operator: LogicalOperator,
         conditions: Vec<Condition>,
     },
+    /// `expr [NOT] IN (SELECT ...)`, the subquery returns a single column
+    In {
+        expr: Expr,
+        query: Box<Query>,
+        negated: bool,
+    },
+    /// `[NOT] EXISTS (SELECT ...)`
+    Exists { query: Box<Query>, negated: bool },
+    /// `expr MATCH 'words'`, whether the text has all the words of the query
+    Match { expr: Expr, query: Expr },
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -36,6 +50,123 @@ pub enum LogicalOperator {
     And,
     Or,
 }
+impl Condition {
+    /// The subqueries of the condition, not those nested in them
+    pub fn subqueries(&self) -> Vec<&Query> {
+        match self {
+            Condition::Comparison { left, right, .. } => {
+                [left.subqueries(), right.subqueries()].concat()
+            }
+            Condition::Logical { conditions, .. } => {
+                conditions.iter().flat_map(|x| x.subqueries()).collect()
+            }
+            Condition::In { expr, query, .. } => [expr.subqueries(), vec![query]].concat(),
+            Condition::Exists { query, .. } => vec![query],
+            Condition::Match { expr, query } => [expr.subqueries(), query.subqueries()].concat(),
+        }
+    }
+    /// The conditions joined by `AND`, or itself
+    pub fn conjuncts(&self) -> Vec<&Condition> {
+        match self {
+            Condition::Logical {
+                operator: LogicalOperator::And,
+                conditions,
+            } => conditions.iter().flat_map(|x| x.conjuncts()).collect(),
+            x => vec![x],
+        }
+    }
+    /// The columns the condition reads, not those of its subqueries
+    pub fn columns(&self) -> Vec<&Identifier> {
+        match self {
+            Condition::Comparison { left, right, .. } => [left.columns(), right.columns()].concat(),
+            Condition::Logical { conditions, .. } => {
+                conditions.iter().flat_map(|x| x.columns()).collect()
+            }
+            Condition::In { expr, .. } => expr.columns(),
+            Condition::Exists { .. } => vec![],
+            Condition::Match { expr, query } => [expr.columns(), query.columns()].concat(),
+        }
+    }
+    /// Visits the expressions of the condition in the order they appear
+    pub(crate) fn visit_exprs(&mut self, f: &mut impl FnMut(&mut Expr)) {
+        match self {
+            Condition::Comparison { left, right, .. } => {
+                left.visit_exprs(f);
+                right.visit_exprs(f);
+            }
+            Condition::Logical { conditions, .. } => {
+                conditions.iter_mut().for_each(|x| x.visit_exprs(f));
+            }
+            Condition::In { expr, query, .. } => {
+                expr.visit_exprs(f);
+                query.visit_exprs(f);
+            }
+            Condition::Exists { query, .. } => query.visit_exprs(f),
+            Condition::Match { expr, query } => {
+                expr.visit_exprs(f);
+                query.visit_exprs(f);
+            }
+        }
+    }
+}
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Comparison {
+                left,
+                operator,
+                right,
+            } => write!(f, "{} {} {}", left, operator, right),
+            Condition::Logical {
+                operator,
+                conditions,
+            } => {
+                let conditions: Vec<_> = conditions
+                    .iter()
+                    .map(|x| match x {
+                        Condition::Logical { .. } => format!("({x})"),
+                        _ => x.to_string(),
+                    })
+                    .collect();
+                write!(f, "{}", conditions.join(&format!(" {operator} ")))
+            }
+            Condition::In {
+                expr,
+                query,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{} {}IN ({})", expr, not, query)
+            }
+            Condition::Exists { query, negated } => {
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{}EXISTS ({})", not, query)
+            }
+            Condition::Match { expr, query } => write!(f, "{} MATCH {}", expr, query),
+        }
+    }
+}
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let txt = match self {
+            Operator::Equal => "=",
+            Operator::NotEqual => "!=",
+            Operator::LessThan => "<",
+            Operator::LessThanOrEqual => "<=",
+            Operator::GreaterThan => ">",
+            Operator::GreaterThanOrEqual => ">=",
+        };
+        write!(f, "{}", txt)
+    }
+}
+impl Display for LogicalOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogicalOperator::And => write!(f, "AND"),
+            LogicalOperator::Or => write!(f, "OR"),
+        }
+    }
+}
 fn parse_operator(input: &str) -> IResult<&str, Operator> {
     alt((
         map(tag_no_case("=="), |_| Operator::Equal),
@@ -49,6 +180,8 @@ fn parse_operator(input: &str) -> IResult<&str, Operator> {
     .parse(input)
 }
 fn parse_logical_operator(input: &str) -> IResult<&str, LogicalOperator> {
+    // the condition may be the end of the input
+    use nom::bytes::complete::tag_no_case;
     alt((
         map(tag_no_case("&&"), |_| LogicalOperator::And),
         map(tag_no_case("AND"), |_| LogicalOperator::And),
@@ -57,39 +190,119 @@ fn parse_logical_operator(input: &str) -> IResult<&str, LogicalOperator> {
     ))
     .parse(input)
 }
-fn parse_condition(input: &str) -> IResult<&str, Condition> {
-    cut(alt((
+fn comparison(input: &str) -> IResult<&str, Condition> {
+    map(
+        (
+            preceded(space0, parse_expr),
+            preceded(space0, parse_operator),
+            preceded(space0, parse_expr),
+        ),
+        |(left, operator, right)| Condition::Comparison {
+            left,
+            operator,
+            right,
+        },
+    )
+    .parse(input)
+}
+fn subquery(input: &str) -> IResult<&str, Box<Query>> {
+    map(
+        delimited((space0, char('('), space0), query, (space0, char(')'))),
+        Box::new,
+    )
+    .parse(input)
+}
+fn in_subquery(input: &str) -> IResult<&str, Condition> {
+    map(
+        (
+            preceded(space0, parse_expr),
+            delimited(space1, opt((keyword("NOT"), space1)), keyword("IN")),
+            subquery,
+        ),
+        |(expr, not, query)| Condition::In {
+            expr,
+            query,
+            negated: not.is_some(),
+        },
+    )
+    .parse(input)
+}
+fn exists(input: &str) -> IResult<&str, Condition> {
+    map(
+        preceded(
+            space0,
+            (opt((keyword("NOT"), space1)), keyword("EXISTS"), subquery),
+        ),
+        |(not, _, query)| Condition::Exists {
+            query,
+            negated: not.is_some(),
+        },
+    )
+    .parse(input)
+}
+fn match_words(input: &str) -> IResult<&str, Condition> {
+    map(
+        (
+            preceded(space0, parse_expr),
+            preceded((space1, keyword("MATCH"), space0), parse_expr),
+        ),
+        |(expr, query)| Condition::Match { expr, query },
+    )
+    .parse(input)
+}
+fn term(input: &str) -> IResult<&str, Condition> {
+    alt((
         delimited(
             preceded(space0, char('(')),
-            parse_condition,
+            or_condition,
             preceded(space0, char(')')),
         ),
-        // map(
-        //     (
-        //         parse_condition,
-        //         preceded(space1, parse_logical_operator),
-        //         preceded(space1, parse_condition),
-        //     ),
-        //     |(f, operator, l)| Condition::Logical {
-        //         operator,
-        //         conditions: vec![f, l],
-        //     },
-        // ),
-        map(
-            (
-                preceded(space1, identifier),
-                preceded(space1, parse_operator),
-                preceded(space1, parse_sql_value),
-            ),
-            |(left, operator, right)| Condition::Comparison {
-                left,
-                operator,
-                right,
-            },
-        ),
-    )))
+        exists,
+        in_subquery,
+        match_words,
+        comparison,
+    ))
     .parse(input)
 }
+/// Chains the operands joined by the given logical operator into a single condition
+fn logical<'a>(
+    expected: LogicalOperator,
+    operand: fn(&'a str) -> IResult<&'a str, Condition>,
+) -> impl Parser<&'a str, Output = Condition, Error = nom::error::Error<&'a str>> {
+    map(
+        (
+            operand,
+            many0(preceded(
+                (
+                    space0,
+                    verify(parse_logical_operator, move |x| *x == expected),
+                    not(peek(satisfy(|c: char| c.is_alphanumeric() || c == '_'))),
+                ),
+                operand,
+            )),
+        ),
+        move |(first, rest)| {
+            if rest.is_empty() {
+                first
+            } else {
+                Condition::Logical {
+                    operator: expected,
+                    conditions: [vec![first], rest].concat(),
+                }
+            }
+        },
+    )
+}
+fn and_condition(input: &str) -> IResult<&str, Condition> {
+    logical(LogicalOperator::And, term).parse(input)
+}
+/// `AND` binds tighter than `OR`, parentheses group the conditions explicitly
+fn or_condition(input: &str) -> IResult<&str, Condition> {
+    nested(input, logical(LogicalOperator::Or, and_condition))
+}
+pub(crate) fn parse_condition(input: &str) -> IResult<&str, Condition> {
+    cut(or_condition).parse(input)
+}
 pub fn parse_where_clause(input: &str) -> IResult<&str, Option<Condition>> {
     opt(preceded((space1, tag_no_case("WHERE")), parse_condition)).parse(input)
 }
@@ -97,6 +310,7 @@ pub fn parse_where_clause(input: &str) -> IResult<&str, Option<Condition>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::SqlValue;
 
     #[test]
     fn should_parse_where_clause() {
@@ -107,8 +321,51 @@ mod tests {
             Some(Condition::Comparison {
                 left: "id".into(),
                 operator: Operator::Equal,
-                right: SqlValue::Integer(1)
+                right: SqlValue::Integer(1).into()
             })
         )
     }
+
+    #[test]
+    fn should_parse_logical_conditions() {
+        let input = " WHERE (id=1 OR name = 'x') AND active = true ORDER";
+        let (rest, res) = parse_where_clause(input).unwrap();
+        assert_eq!(rest, " ORDER");
+        let res = res.unwrap();
+        assert_eq!(
+            res,
+            Condition::Logical {
+                operator: LogicalOperator::And,
+                conditions: vec![
+                    Condition::Logical {
+                        operator: LogicalOperator::Or,
+                        conditions: vec![
+                            Condition::Comparison {
+                                left: "id".into(),
+                                operator: Operator::Equal,
+                                right: SqlValue::Integer(1).into()
+                            },
+                            Condition::Comparison {
+                                left: "name".into(),
+                                operator: Operator::Equal,
+                                right: SqlValue::String("x".into()).into()
+                            },
+                        ],
+                    },
+                    Condition::Comparison {
+                        left: "active".into(),
+                        operator: Operator::Equal,
+                        right: SqlValue::Boolean(true).into()
+                    },
+                ],
+            }
+        );
+        assert_eq!(res.to_string(), "(id = 1 OR name = 'x') AND active = true");
+
+        let (_, res) = parse_where_clause(" WHERE body match 'rust book' AND id > 1").unwrap();
+        assert_eq!(
+            res.unwrap().to_string(),
+            "body MATCH 'rust book' AND id > 1"
+        );
+    }
 }
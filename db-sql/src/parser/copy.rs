@@ -0,0 +1,62 @@
+use crate::parser::{CopyDirection, SqlStatement, SqlValue, identifier, string_value};
+use nom::{
+    IResult, Parser,
+    branch::alt,
+    bytes::tag_no_case,
+    character::complete::{space0, space1},
+    combinator::{map, map_res, opt},
+    sequence::preceded,
+};
+
+fn direction(input: &str) -> IResult<&str, CopyDirection> {
+    alt((
+        map(tag_no_case("FROM"), |_| CopyDirection::From),
+        map(tag_no_case("TO"), |_| CopyDirection::To),
+    ))
+    .parse(input)
+}
+fn path(input: &str) -> IResult<&str, String> {
+    map_res(string_value, |x| match x {
+        SqlValue::String(s) => Ok(s),
+        _ => Err("expected a file path"),
+    })
+    .parse(input)
+}
+pub fn parse_copy_statement(input: &str) -> IResult<&str, SqlStatement> {
+    map(
+        (
+            preceded((space0, tag_no_case("COPY"), space1), identifier),
+            preceded(space1, direction),
+            preceded(space1, path),
+            opt((space1, tag_no_case("WITH"), space1, tag_no_case("HEADER"))),
+        ),
+        |(table, direction, path, header)| SqlStatement::Copy {
+            table,
+            direction,
+            path,
+            header: header.is_some(),
+        },
+    )
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_copy_statement() {
+        let input = "COPY users FROM 'data/users.csv' WITH HEADER";
+        let (_, stmt) = parse_copy_statement(input).unwrap();
+        assert_eq!(
+            stmt,
+            SqlStatement::Copy {
+                table: "users".into(),
+                direction: CopyDirection::From,
+                path: "data/users.csv".into(),
+                header: true,
+            }
+        );
+        assert_eq!(stmt.to_string(), input);
+    }
+}
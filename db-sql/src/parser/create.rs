@@ -1,73 +1,207 @@
-use crate::parser::{ColumnDefinition, SqlDataType, SqlStatement, identifier};
+use crate::{
+    parser::{
+        ColumnDefinition, Expr, ForeignKey, ReferentialAction, SqlDataType, SqlStatement,
+        TableStorage, expr::parse_expr, identifier, keyword,
+    },
+    types::MAX_PRECISION,
+};
 use nom::{
     IResult, Parser,
     branch::alt,
     bytes::tag_no_case,
-    character::char,
-    character::complete::{space0, space1},
-    combinator::map,
+    character::complete::{char, space0, space1},
     combinator::opt,
-    multi::separated_list1,
+    combinator::{map, map_opt},
+    multi::{many0, separated_list1},
     sequence::delimited,
     sequence::preceded,
 };
 
-fn data_type(input: &str) -> IResult<&str, SqlDataType> {
-    let (input, dtyp) = alt((
-        tag_no_case("INT"),
-        tag_no_case("STRING"),
-        tag_no_case("TEXT"),
-        tag_no_case("BIT"),
-        tag_no_case("UUID"),
-        tag_no_case("FLOAT"),
+fn number(input: &str) -> IResult<&str, u32> {
+    nom::character::complete::u32(input)
+}
+/// `(n)` or `(n, m)` after the name of a type
+fn type_arguments(input: &str) -> IResult<&str, (u32, Option<u32>)> {
+    delimited(
+        (space0, char('('), space0),
+        (number, opt(preceded((space0, char(','), space0), number))),
+        (space0, char(')')),
+    )
+    .parse(input)
+}
+fn decimal_type(input: &str) -> IResult<&str, SqlDataType> {
+    map_opt(
+        preceded(
+            alt((keyword("DECIMAL"), keyword("NUMERIC"))),
+            opt(type_arguments),
+        ),
+        |args| {
+            let Some((precision, scale)) = args else {
+                return Some(SqlDataType::DEFAULT_DECIMAL);
+            };
+            let scale = scale.unwrap_or(0);
+            ((1..=MAX_PRECISION).contains(&precision) && scale <= precision)
+                .then_some(SqlDataType::Decimal { precision, scale })
+        },
+    )
+    .parse(input)
+}
+fn varchar_type(input: &str) -> IResult<&str, SqlDataType> {
+    map_opt(
+        preceded(keyword("VARCHAR"), type_arguments),
+        |(n, m)| match m {
+            None if n > 0 => Some(SqlDataType::Varchar(n)),
+            _ => None,
+        },
+    )
+    .parse(input)
+}
+pub(crate) fn data_type(input: &str) -> IResult<&str, SqlDataType> {
+    let simple = alt((
+        keyword("INTEGER"),
+        keyword("INT"),
+        keyword("BIGINT"),
+        keyword("STRING"),
+        keyword("TEXT"),
+        keyword("BIT"),
+        keyword("UUID"),
+        keyword("FLOAT"),
+        keyword("DATE"),
+        keyword("TIMESTAMP"),
+        keyword("BLOB"),
+        keyword("JSON"),
+    ));
+    alt((
+        decimal_type,
+        varchar_type,
+        map_opt(simple, |x| SqlDataType::try_from(x).ok()),
     ))
-    .parse(input)?;
-
-    let Ok(dtyp) = SqlDataType::try_from(dtyp) else {
-        return Err(nom::Err::Failure(nom::error::make_error(
-            input,
-            nom::error::ErrorKind::Alpha,
-        )));
-    };
-    Ok((input, dtyp))
+    .parse(input)
 }
 fn constraint(input: &str) -> IResult<&str, String> {
     let (input, constraint) = alt((
-        tag_no_case("DEFAULT"),
         tag_no_case("PRIMARY KEY"),
         tag_no_case("NOT NULL"),
         tag_no_case("UNIQUE"),
-        tag_no_case("REFERENCES"),
         tag_no_case("CHECK"),
     ))
     .parse(input)?;
-    Ok((input, constraint.to_string()))
+    Ok((input, constraint.to_uppercase()))
 }
 
-fn column_definition(input: &str) -> IResult<&str, ColumnDefinition> {
+fn referential_action(input: &str) -> IResult<&str, ReferentialAction> {
+    alt((
+        map(keyword("RESTRICT"), |_| ReferentialAction::Restrict),
+        map((keyword("NO"), space1, keyword("ACTION")), |_| {
+            ReferentialAction::Restrict
+        }),
+        map(keyword("CASCADE"), |_| ReferentialAction::Cascade),
+        map((keyword("SET"), space1, keyword("NULL")), |_| {
+            ReferentialAction::SetNull
+        }),
+    ))
+    .parse(input)
+}
+/// `REFERENCES table(column) [ON DELETE action]`
+fn references(input: &str) -> IResult<&str, ForeignKey> {
     map(
         (
-            identifier,
-            preceded(space1, data_type),
-            opt(preceded(space1, constraint)),
+            preceded((keyword("REFERENCES"), space1), identifier),
+            delimited((space0, char('('), space0), identifier, (space0, char(')'))),
+            opt(preceded(
+                (space1, keyword("ON"), space1, keyword("DELETE"), space1),
+                referential_action,
+            )),
         ),
-        |(name, data_type, constraint)| ColumnDefinition {
-            name: name.into(),
-            data_type,
-            constraint,
+        |(table, column, on_delete)| ForeignKey {
+            table,
+            column,
+            on_delete: on_delete.unwrap_or_default(),
         },
     )
     .parse(input)
 }
-fn column_list(input: &str) -> IResult<&str, Vec<ColumnDefinition>> {
-    separated_list1(
-        delimited(space0, nom::character::complete::char(','), space0),
-        column_definition,
+
+/// What may follow the type of a column, in any order
+enum ColumnOption {
+    AutoIncrement,
+    Constraint(String),
+    Default(Expr),
+    References(ForeignKey),
+}
+fn column_option(input: &str) -> IResult<&str, ColumnOption> {
+    alt((
+        map(
+            preceded((keyword("DEFAULT"), space1), parse_expr),
+            ColumnOption::Default,
+        ),
+        map(
+            alt((keyword("AUTOINCREMENT"), keyword("AUTO_INCREMENT"))),
+            |_| ColumnOption::AutoIncrement,
+        ),
+        map(references, ColumnOption::References),
+        map(constraint, ColumnOption::Constraint),
+    ))
+    .parse(input)
+}
+/// The type of a column, `SERIAL` and `BIGSERIAL` are auto-incremented integers
+fn column_type(input: &str) -> IResult<&str, (SqlDataType, bool)> {
+    alt((
+        map(keyword("SERIAL"), |_| (SqlDataType::Integer, true)),
+        map(keyword("BIGSERIAL"), |_| (SqlDataType::BigInt, true)),
+        map(data_type, |x| (x, false)),
+    ))
+    .parse(input)
+}
+fn column_definition(input: &str) -> IResult<&str, ColumnDefinition> {
+    map_opt(
+        (
+            identifier,
+            preceded(space1, column_type),
+            many0(preceded(space1, column_option)),
+        ),
+        |(name, (data_type, auto_increment), options)| {
+            let mut column = ColumnDefinition {
+                name,
+                data_type,
+                constraint: None,
+                default: None,
+                auto_increment,
+                references: None,
+            };
+            for option in options {
+                // each option may be given once
+                match option {
+                    ColumnOption::AutoIncrement if !column.auto_increment => {
+                        column.auto_increment = true
+                    }
+                    ColumnOption::Constraint(x) if column.constraint.is_none() => {
+                        column.constraint = Some(x)
+                    }
+                    ColumnOption::Default(x) if column.default.is_none() => {
+                        column.default = Some(x)
+                    }
+                    ColumnOption::References(x) if column.references.is_none() => {
+                        column.references = Some(x)
+                    }
+                    _ => return None,
+                }
+            }
+            // the generated values are integers and replace a default
+            let integer = matches!(column.data_type, SqlDataType::Integer | SqlDataType::BigInt);
+            if column.auto_increment && (!integer || column.default.is_some()) {
+                return None;
+            }
+            Some(column)
+        },
     )
     .parse(input)
 }
+fn column_list(input: &str) -> IResult<&str, Vec<ColumnDefinition>> {
+    separated_list1(delimited(space0, char(','), space0), column_definition).parse(input)
+}
 pub fn parse_create_statement(input: &str) -> IResult<&str, SqlStatement> {
-    map(
+    map_opt(
         (
             preceded(
                 (
@@ -82,15 +216,34 @@ pub fn parse_create_statement(input: &str) -> IResult<&str, SqlStatement> {
             preceded((space0, char('('), space0), column_list),
             space0,
             char(')'),
+            opt(preceded(
+                (space1, keyword("USING"), space1),
+                alt((
+                    map(keyword("columnar"), |_| TableStorage::Columnar),
+                    map(keyword("rows"), |_| TableStorage::Rows),
+                )),
+            )),
         ),
-        |(table, columns, _, _)| SqlStatement::Create { table, columns },
+        |(table, columns, _, _, storage)| {
+            // a table has a single sequence
+            (columns.iter().filter(|x| x.auto_increment).count() <= 1).then_some(
+                SqlStatement::Create {
+                    table,
+                    columns,
+                    storage: storage.unwrap_or_default(),
+                },
+            )
+        },
     )
     .parse(input)
 }
 #[cfg(test)]
 mod tests {
     use crate::parser::create::parse_create_statement;
-    use crate::parser::{ColumnDefinition, SqlDataType, SqlStatement};
+    use crate::parser::{
+        ColumnDefinition, Expr, ForeignKey, ReferentialAction, SqlDataType, SqlStatement,
+        TableStorage,
+    };
 
     #[test]
     fn should_parse_create_statement() {
@@ -105,14 +258,99 @@ mod tests {
                         name: "id".into(),
                         data_type: SqlDataType::Integer,
                         constraint: Some("PRIMARY KEY".into()),
+                        default: None,
+                        auto_increment: false,
+                        references: None,
                     },
                     ColumnDefinition {
                         name: "name".into(),
                         data_type: SqlDataType::String,
-                        constraint: None
+                        constraint: None,
+                        default: None,
+                        auto_increment: false,
+                        references: None,
                     },
                 ],
+                storage: TableStorage::Rows,
+            }
+        );
+    }
+
+    #[test]
+    fn should_parse_column_defaults() {
+        let input =
+            "CREATE TABLE users (id UUID DEFAULT gen_random_uuid() PRIMARY KEY, name STRING)";
+        let (_, parsed) = parse_create_statement(input).unwrap();
+        let SqlStatement::Create { columns, .. } = &parsed else {
+            panic!("expected a CREATE TABLE");
+        };
+        assert_eq!(columns[0].data_type, SqlDataType::Uuid);
+        assert_eq!(
+            columns[0].default,
+            Some(Expr::Function {
+                name: "gen_random_uuid".into(),
+                args: vec![],
+            })
+        );
+        assert_eq!(columns[0].constraint.as_deref(), Some("PRIMARY KEY"));
+        assert_eq!(columns[1].default, None);
+        assert_eq!(parsed.to_string(), input);
+    }
+
+    #[test]
+    fn should_parse_serial_columns() {
+        let input = "CREATE TABLE users (name STRING, n SERIAL)";
+        let (_, parsed) = parse_create_statement(input).unwrap();
+        let SqlStatement::Create { columns, .. } = &parsed else {
+            panic!("expected a CREATE TABLE");
+        };
+        assert_eq!(columns[1].data_type, SqlDataType::Integer);
+        assert!(columns[1].auto_increment);
+        assert!(!columns[0].auto_increment);
+        assert_eq!(
+            parsed.to_string(),
+            "CREATE TABLE users (name STRING, n INT AUTOINCREMENT)"
+        );
+        assert!(parse_create_statement("CREATE TABLE t (a SERIAL, b SERIAL)").is_err());
+        assert!(parse_create_statement("CREATE TABLE t (a STRING AUTOINCREMENT)").is_err());
+    }
+
+    #[test]
+    fn should_parse_foreign_keys() {
+        let input = "CREATE TABLE orders (id INT PRIMARY KEY, user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE, note STRING REFERENCES notes (id))";
+        let (_, parsed) = parse_create_statement(input).unwrap();
+        let SqlStatement::Create { columns, .. } = &parsed else {
+            panic!("expected a CREATE TABLE");
+        };
+        assert_eq!(
+            columns[1].references,
+            Some(ForeignKey {
+                table: "users".into(),
+                column: "id".into(),
+                on_delete: ReferentialAction::Cascade,
+            })
+        );
+        assert_eq!(columns[1].constraint.as_deref(), Some("NOT NULL"));
+        assert_eq!(
+            parsed.to_string(),
+            "CREATE TABLE orders (id INT PRIMARY KEY, user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE, note STRING REFERENCES notes(id))"
+        );
+    }
+
+    #[test]
+    fn should_parse_columnar_tables() {
+        let input = "CREATE TABLE events (at INT, kind STRING) USING COLUMNAR";
+        let (_, parsed) = parse_create_statement(input).unwrap();
+        assert!(matches!(
+            parsed,
+            SqlStatement::Create {
+                storage: TableStorage::Columnar,
+                ..
             }
+        ));
+        assert_eq!(
+            parsed.to_string(),
+            "CREATE TABLE events (at INT, kind STRING) USING columnar"
         );
     }
 }
@@ -38,7 +38,7 @@ mod tests {
                 condition: Some(Condition::Comparison {
                     left: "id".into(),
                     operator: Operator::Equal,
-                    right: SqlValue::Integer(1)
+                    right: SqlValue::Integer(1).into()
                 })
             }
         )
@@ -0,0 +1,476 @@
+use crate::parser::{
+    Identifier, Query, SqlDataType, SqlValue,
+    create::data_type,
+    identifier, keyword, nested, parse_sql_value, qualified_identifier,
+    select::query,
+    window::{Window, over},
+};
+use std::fmt::{Display, Formatter};
+
+use nom::{
+    IResult, Parser,
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{anychar, char, space0, space1},
+    combinator::{cut, map, map_opt, opt, peek},
+    multi::{many0, separated_list0},
+    sequence::{delimited, preceded, terminated},
+};
+
+/// The functions which aggregate the rows of a query
+const AGGREGATES: [&str; 5] = ["COUNT", "SUM", "AVG", "MIN", "MAX"];
+
+/// A value computed for each row, e.g. a column or `price * 2`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Literal(SqlValue),
+    Column(Identifier),
+    Negate(Box<Expr>),
+    Binary {
+        left: Box<Expr>,
+        operator: ArithmeticOperator,
+        right: Box<Expr>,
+    },
+    /// `CAST(expr AS type)`
+    Cast {
+        expr: Box<Expr>,
+        data_type: SqlDataType,
+    },
+    /// A call of a built-in function, e.g. `gen_random_uuid()`
+    Function {
+        name: Identifier,
+        args: Vec<Expr>,
+    },
+    /// `(SELECT ...)`, the single value of a subquery or nil without rows
+    Subquery(Box<Query>),
+    /// A window function, only computed for the select list
+    Window(Box<Window>),
+    /// `COUNT`, `SUM`, `AVG`, `MIN` or `MAX` of the rows of a query without `OVER`,
+    /// only computed for the select list, which then returns a single row
+    Aggregate {
+        function: Identifier,
+        /// none for `COUNT(*)`
+        args: Vec<Expr>,
+    },
+    /// `expr -> key` reads the field or item of a JSON document, `expr ->> key` reads
+    /// it as a SQL value
+    JsonField {
+        expr: Box<Expr>,
+        key: Box<Expr>,
+        as_text: bool,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ArithmeticOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+impl Expr {
+    /// The columns the expression reads, in the order they appear
+    pub fn columns(&self) -> Vec<&Identifier> {
+        match self {
+            Expr::Literal(_) => vec![],
+            Expr::Column(x) => vec![x],
+            Expr::Negate(expr) | Expr::Cast { expr, .. } => expr.columns(),
+            Expr::Binary { left, right, .. } => [left.columns(), right.columns()].concat(),
+            Expr::Function { args, .. } | Expr::Aggregate { args, .. } => {
+                args.iter().flat_map(|x| x.columns()).collect()
+            }
+            // the subquery resolves its own columns
+            Expr::Subquery(_) => vec![],
+            Expr::Window(w) => w.exprs().into_iter().flat_map(|x| x.columns()).collect(),
+            Expr::JsonField { expr, key, .. } => [expr.columns(), key.columns()].concat(),
+        }
+    }
+    /// The subqueries of the expression, not those nested in them
+    pub fn subqueries(&self) -> Vec<&Query> {
+        match self {
+            Expr::Literal(_) | Expr::Column(_) => vec![],
+            Expr::Negate(expr) | Expr::Cast { expr, .. } => expr.subqueries(),
+            Expr::Binary { left, right, .. } => [left.subqueries(), right.subqueries()].concat(),
+            Expr::Function { args, .. } | Expr::Aggregate { args, .. } => {
+                args.iter().flat_map(|x| x.subqueries()).collect()
+            }
+            Expr::Subquery(query) => vec![query],
+            Expr::Window(w) => w.exprs().into_iter().flat_map(|x| x.subqueries()).collect(),
+            Expr::JsonField { expr, key, .. } => [expr.subqueries(), key.subqueries()].concat(),
+        }
+    }
+    /// The window functions of the expression, not those nested in them
+    pub fn windows(&self) -> Vec<&Window> {
+        match self {
+            Expr::Literal(_) | Expr::Column(_) | Expr::Subquery(_) => vec![],
+            Expr::Negate(expr) | Expr::Cast { expr, .. } => expr.windows(),
+            Expr::Binary { left, right, .. } => [left.windows(), right.windows()].concat(),
+            Expr::Function { args, .. } | Expr::Aggregate { args, .. } => {
+                args.iter().flat_map(|x| x.windows()).collect()
+            }
+            Expr::Window(w) => vec![w],
+            Expr::JsonField { expr, key, .. } => [expr.windows(), key.windows()].concat(),
+        }
+    }
+    /// The aggregates of the expression, not those nested in them or in windows
+    pub fn aggregates(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Literal(_) | Expr::Column(_) | Expr::Subquery(_) | Expr::Window(_) => vec![],
+            Expr::Negate(expr) | Expr::Cast { expr, .. } => expr.aggregates(),
+            Expr::Binary { left, right, .. } => [left.aggregates(), right.aggregates()].concat(),
+            Expr::Function { args, .. } => args.iter().flat_map(|x| x.aggregates()).collect(),
+            Expr::Aggregate { .. } => vec![self],
+            Expr::JsonField { expr, key, .. } => [expr.aggregates(), key.aggregates()].concat(),
+        }
+    }
+    /// Visits the expression, then those nested in it in the order they appear
+    pub(crate) fn visit_exprs(&mut self, f: &mut impl FnMut(&mut Expr)) {
+        f(self);
+        match self {
+            Expr::Literal(_) | Expr::Column(_) => {}
+            Expr::Negate(expr) | Expr::Cast { expr, .. } => expr.visit_exprs(f),
+            Expr::Binary { left, right, .. }
+            | Expr::JsonField {
+                expr: left,
+                key: right,
+                ..
+            } => {
+                left.visit_exprs(f);
+                right.visit_exprs(f);
+            }
+            Expr::Function { args, .. } | Expr::Aggregate { args, .. } => {
+                args.iter_mut().for_each(|x| x.visit_exprs(f))
+            }
+            Expr::Subquery(query) => query.visit_exprs(f),
+            Expr::Window(w) => w.visit_exprs(f),
+        }
+    }
+}
+impl ArithmeticOperator {
+    /// Operators of a higher precedence bind tighter
+    fn precedence(&self) -> u8 {
+        match self {
+            ArithmeticOperator::Add | ArithmeticOperator::Subtract => 1,
+            _ => 2,
+        }
+    }
+}
+
+impl From<&str> for Expr {
+    fn from(value: &str) -> Self {
+        Expr::Column(value.into())
+    }
+}
+impl From<SqlValue> for Expr {
+    fn from(value: SqlValue) -> Self {
+        Expr::Literal(value)
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Literal(x) => write!(f, "{}", x.to_literal()),
+            Expr::Column(x) => write!(f, "{}", x),
+            Expr::Negate(expr) => match expr.as_ref() {
+                Expr::Column(_) => write!(f, "-{}", expr),
+                _ => write!(f, "-({})", expr),
+            },
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                // operands binding looser than the operator need parentheses, and so
+                // does a right operand of the same precedence, e.g. `a - (b - c)`
+                let operand = |x: &Expr, right: bool| match x {
+                    Expr::Binary { operator: op, .. }
+                        if op.precedence() < operator.precedence()
+                            || (right && op.precedence() == operator.precedence()) =>
+                    {
+                        format!("({x})")
+                    }
+                    _ => x.to_string(),
+                };
+                write!(
+                    f,
+                    "{} {} {}",
+                    operand(left, false),
+                    operator,
+                    operand(right, true)
+                )
+            }
+            Expr::Cast { expr, data_type } => write!(f, "CAST({} AS {})", expr, data_type),
+            Expr::Function { name, args } => {
+                let args: Vec<_> = args.iter().map(|x| x.to_string()).collect();
+                write!(f, "{}({})", name, args.join(", "))
+            }
+            Expr::Aggregate { function, args } => match args.first() {
+                Some(arg) => write!(f, "{}({})", function, arg),
+                None => write!(f, "{}(*)", function),
+            },
+            Expr::Subquery(query) => write!(f, "({})", query),
+            Expr::Window(w) => write!(f, "{}", w),
+            Expr::JsonField { expr, key, as_text } => {
+                let expr = match expr.as_ref() {
+                    Expr::Binary { .. } => format!("({expr})"),
+                    _ => expr.to_string(),
+                };
+                let key = match key.as_ref() {
+                    Expr::Binary { .. } | Expr::JsonField { .. } => format!("({key})"),
+                    _ => key.to_string(),
+                };
+                let operator = if *as_text { "->>" } else { "->" };
+                write!(f, "{} {} {}", expr, operator, key)
+            }
+        }
+    }
+}
+impl Display for ArithmeticOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let txt = match self {
+            ArithmeticOperator::Add => "+",
+            ArithmeticOperator::Subtract => "-",
+            ArithmeticOperator::Multiply => "*",
+            ArithmeticOperator::Divide => "/",
+            ArithmeticOperator::Modulo => "%",
+        };
+        write!(f, "{}", txt)
+    }
+}
+
+/// `CAST(expr AS type)`, the input is not read as anything else once `CAST(` is
+fn cast(input: &str) -> IResult<&str, Expr> {
+    map(
+        preceded(
+            (keyword("CAST"), space0, char('('), space0),
+            cut((
+                parse_expr,
+                delimited(
+                    (space1, keyword("AS"), space1),
+                    data_type,
+                    (space0, char(')')),
+                ),
+            )),
+        ),
+        |(expr, data_type)| Expr::Cast {
+            expr: Box::new(expr),
+            data_type,
+        },
+    )
+    .parse(input)
+}
+/// A call of a function, which is an aggregate function without `OVER` and a window
+/// function with it. The arguments are parsed once whatever the call turns out to
+/// be, `*` only counts the rows.
+fn call(input: &str) -> IResult<&str, Expr> {
+    let args = delimited(
+        (space0, char('('), space0),
+        alt((
+            map(char('*'), |_| None),
+            map(
+                separated_list0((space0, char(','), space0), parse_expr),
+                Some,
+            ),
+        )),
+        (space0, char(')')),
+    );
+    let over = opt(preceded(space1, over));
+    map_opt((identifier, args, over), |(name, args, over)| {
+        let count = name.0.eq_ignore_ascii_case("COUNT");
+        Some(match (args, over) {
+            (args, Some((partition_by, order_by))) => Expr::Window(Box::new(Window {
+                function: name,
+                args: args.unwrap_or_default(),
+                partition_by,
+                order_by,
+            })),
+            (None, None) if count => Expr::Aggregate {
+                function: name,
+                args: vec![],
+            },
+            (None, None) => return None,
+            (Some(args), None)
+                if args.len() == 1 && AGGREGATES.iter().any(|f| name.0.eq_ignore_ascii_case(f)) =>
+            {
+                Expr::Aggregate {
+                    function: name,
+                    args,
+                }
+            }
+            (Some(args), None) => Expr::Function { name, args },
+        })
+    })
+    .parse(input)
+}
+fn primary(input: &str) -> IResult<&str, Expr> {
+    alt((
+        map(parse_sql_value, Expr::Literal),
+        cast,
+        call,
+        // `(SELECT` is a subquery, nothing else is tried when it is not a valid one
+        map(
+            preceded(
+                (
+                    char('('),
+                    space0,
+                    peek(alt((keyword("SELECT"), keyword("WITH")))),
+                ),
+                cut(terminated(query, (space0, char(')')))),
+            ),
+            |x| Expr::Subquery(Box::new(x)),
+        ),
+        delimited((char('('), space0), parse_expr, (space0, char(')'))),
+        map(qualified_identifier, Expr::Column),
+        map(preceded((char('-'), space0), primary), |x| {
+            Expr::Negate(Box::new(x))
+        }),
+    ))
+    .parse(input)
+}
+/// `->` and `->>` bind tighter than the arithmetic operators
+fn json_field(input: &str) -> IResult<&str, Expr> {
+    let operator = delimited(
+        space0,
+        alt((map(tag("->>"), |_| true), map(tag("->"), |_| false))),
+        space0,
+    );
+    map((primary, many0((operator, primary))), |(first, rest)| {
+        rest.into_iter()
+            .fold(first, |expr, (as_text, key)| Expr::JsonField {
+                expr: Box::new(expr),
+                key: Box::new(key),
+                as_text,
+            })
+    })
+    .parse(input)
+}
+/// Chains the operands joined by the operators of the same precedence, from left to
+/// right
+fn binary<'a>(
+    operators: &'static [(char, ArithmeticOperator)],
+    operand: fn(&'a str) -> IResult<&'a str, Expr>,
+) -> impl Parser<&'a str, Output = Expr, Error = nom::error::Error<&'a str>> {
+    let operator = delimited(
+        space0,
+        map_opt(anychar, |c| {
+            operators.iter().find(|x| x.0 == c).map(|x| x.1)
+        }),
+        space0,
+    );
+    map((operand, many0((operator, operand))), |(first, rest)| {
+        rest.into_iter()
+            .fold(first, |left, (operator, right)| Expr::Binary {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            })
+    })
+}
+fn term(input: &str) -> IResult<&str, Expr> {
+    binary(
+        &[
+            ('*', ArithmeticOperator::Multiply),
+            ('/', ArithmeticOperator::Divide),
+            ('%', ArithmeticOperator::Modulo),
+        ],
+        json_field,
+    )
+    .parse(input)
+}
+/// `*`, `/` and `%` bind tighter than `+` and `-`
+pub fn parse_expr(input: &str) -> IResult<&str, Expr> {
+    let sum = binary(
+        &[
+            ('+', ArithmeticOperator::Add),
+            ('-', ArithmeticOperator::Subtract),
+        ],
+        term,
+    );
+    nested(input, sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_expressions() {
+        let (rest, expr) =
+            parse_expr("CAST(price AS DECIMAL(10,2)) * (qty - 1) + -x FROM").unwrap();
+        assert_eq!(rest, " FROM");
+        assert_eq!(
+            expr,
+            Expr::Binary {
+                left: Box::new(Expr::Binary {
+                    left: Box::new(Expr::Cast {
+                        expr: Box::new("price".into()),
+                        data_type: SqlDataType::Decimal {
+                            precision: 10,
+                            scale: 2
+                        },
+                    }),
+                    operator: ArithmeticOperator::Multiply,
+                    right: Box::new(Expr::Binary {
+                        left: Box::new("qty".into()),
+                        operator: ArithmeticOperator::Subtract,
+                        right: Box::new(SqlValue::Integer(1).into()),
+                    }),
+                }),
+                operator: ArithmeticOperator::Add,
+                right: Box::new(Expr::Negate(Box::new("x".into()))),
+            }
+        );
+        assert_eq!(
+            expr.to_string(),
+            "CAST(price AS DECIMAL(10,2)) * (qty - 1) + -x"
+        );
+
+        let (_, expr) = parse_expr("DATE '2024-01-31' - 1").unwrap();
+        assert_eq!(expr.to_string(), "DATE '2024-01-31' - 1");
+
+        let (_, expr) = parse_expr("payload->'items'->>0 * 2 - (a + b)->>'x'").unwrap();
+        assert_eq!(
+            expr.to_string(),
+            "payload -> 'items' ->> 0 * 2 - (a + b) ->> 'x'"
+        );
+        assert_eq!(parse_expr(&expr.to_string()), Ok(("", expr)));
+    }
+
+    #[test]
+    fn should_parse_aggregates() {
+        let (_, expr) = parse_expr("MAX(amount) - min( amount ) + count(*)").unwrap();
+        assert_eq!(expr.aggregates().len(), 3);
+        assert_eq!(expr.to_string(), "MAX(amount) - min(amount) + count(*)");
+        assert_eq!(parse_expr(&expr.to_string()), Ok(("", expr)));
+
+        let (_, expr) = parse_expr("SUM(amount) OVER ()").unwrap();
+        assert!(matches!(expr, Expr::Window(_)));
+        assert!(expr.aggregates().is_empty());
+        // only COUNT counts the rows
+        assert_ne!(parse_expr("SUM(*)").map(|x| x.0), Ok(""));
+    }
+
+    #[test]
+    fn should_reject_deep_nesting() {
+        let nested = |open: &str, inner: &str, depth: usize| {
+            format!("{}{inner}{}", open.repeat(depth), ")".repeat(depth))
+        };
+        let parse = |sql: String| crate::parser::SqlStatement::try_from(sql.as_str());
+        assert!(parse(format!("SELECT {} FROM o", nested("(", "1", 30))).is_ok());
+        for depth in [100, 10_000] {
+            let parens = nested("(", "1", depth);
+            assert!(parse(format!("SELECT {parens} FROM o")).is_err());
+            let condition = nested("(", "a = 1", depth);
+            assert!(parse(format!("SELECT a FROM o WHERE {condition}")).is_err());
+            let subqueries = nested("(SELECT ", "1", depth);
+            assert!(parse(format!("SELECT {subqueries}")).is_err());
+        }
+        // the calls and subqueries which fail are not parsed again as something else
+        for open in ["f(", "CAST(", "(SELECT "] {
+            assert!(parse(format!("SELECT {} FROM o", nested(open, "1 +", 30))).is_err());
+        }
+    }
+}
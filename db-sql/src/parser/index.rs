@@ -0,0 +1,79 @@
+use crate::parser::{IndexKind, SqlStatement, identifier, keyword};
+use nom::{
+    IResult, Parser,
+    branch::alt,
+    character::complete::{char, space0, space1},
+    combinator::map,
+    sequence::{delimited, preceded},
+};
+
+/// `CREATE INDEX name ON table USING fulltext(column)` or `USING btree(column)`
+pub fn parse_create_index(input: &str) -> IResult<&str, SqlStatement> {
+    map(
+        (
+            preceded(
+                (space0, keyword("CREATE"), space1, keyword("INDEX"), space1),
+                identifier,
+            ),
+            preceded((space1, keyword("ON"), space1), identifier),
+            preceded(
+                (space1, keyword("USING"), space1),
+                alt((
+                    map(keyword("FULLTEXT"), |_| IndexKind::FullText),
+                    map(keyword("BTREE"), |_| IndexKind::BTree),
+                )),
+            ),
+            delimited((space0, char('('), space0), identifier, (space0, char(')'))),
+        ),
+        |(name, table, kind, column)| SqlStatement::CreateIndex {
+            name,
+            table,
+            column,
+            kind,
+        },
+    )
+    .parse(input)
+}
+pub fn parse_drop_index(input: &str) -> IResult<&str, SqlStatement> {
+    map(
+        preceded(
+            (space0, keyword("DROP"), space1, keyword("INDEX"), space1),
+            identifier,
+        ),
+        |name| SqlStatement::DropIndex { name },
+    )
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_index_statements() {
+        let input = "create index docs_body on docs using FULLTEXT ( body )";
+        let (_, stmt) = parse_create_index(input).unwrap();
+        assert_eq!(
+            stmt,
+            SqlStatement::CreateIndex {
+                name: "docs_body".into(),
+                table: "docs".into(),
+                column: "body".into(),
+                kind: IndexKind::FullText,
+            }
+        );
+        assert_eq!(
+            stmt.to_string(),
+            "CREATE INDEX docs_body ON docs USING fulltext(body)"
+        );
+        let (_, stmt) = parse_create_index("CREATE INDEX docs_at ON docs USING btree(at)").unwrap();
+        assert_eq!(
+            stmt.to_string(),
+            "CREATE INDEX docs_at ON docs USING btree(at)"
+        );
+        assert!(parse_create_index("CREATE INDEX docs_body ON docs (body)").is_err());
+
+        let (_, stmt) = parse_drop_index("DROP INDEX docs_body").unwrap();
+        assert_eq!(stmt.to_string(), "DROP INDEX docs_body");
+    }
+}
@@ -1,4 +1,7 @@
-use crate::parser::{SqlStatement, SqlValue, identifier, parse_sql_value};
+use crate::parser::{
+    Expr, Identifier, SqlStatement, expr::parse_expr, identifier, keyword,
+    select::column_list as select_list,
+};
 use nom::{
     IResult, Parser,
     bytes::tag_no_case,
@@ -10,8 +13,16 @@ use nom::{
     sequence::preceded,
 };
 
-fn value_list(input: &str) -> IResult<&str, Vec<SqlValue>> {
-    separated_list1(delimited(space0, char(','), space0), parse_sql_value).parse(input)
+fn column_list(input: &str) -> IResult<&str, Vec<Identifier>> {
+    delimited(
+        (space0, char('('), space0),
+        separated_list1(delimited(space0, char(','), space0), identifier),
+        (space0, char(')')),
+    )
+    .parse(input)
+}
+fn value_list(input: &str) -> IResult<&str, Vec<Expr>> {
+    separated_list1(delimited(space0, char(','), space0), parse_expr).parse(input)
 }
 pub fn parse_insert_statement(input: &str) -> IResult<&str, SqlStatement> {
     map(
@@ -25,14 +36,24 @@ pub fn parse_insert_statement(input: &str) -> IResult<&str, SqlStatement> {
                 ),
                 identifier,
             ),
+            opt(column_list),
             preceded(
                 (space1, tag_no_case("VALUES"), space0, char('('), space0),
                 value_list,
             ),
             space0,
             char(')'),
+            opt(preceded(
+                (space1, keyword("RETURNING"), space1),
+                select_list,
+            )),
         ),
-        |(table, values, _, _)| SqlStatement::Insert { table, values },
+        |(table, columns, values, _, _, returning)| SqlStatement::Insert {
+            table,
+            columns: columns.unwrap_or_default(),
+            values,
+            returning: returning.unwrap_or_default(),
+        },
     )
     .parse(input)
 }
@@ -50,13 +71,20 @@ mod tests {
             stat,
             SqlStatement::Insert {
                 table: "users".into(),
+                columns: vec![],
                 values: vec![
-                    SqlValue::Integer(1),
-                    SqlValue::String("email@gmail.com".into()),
-                    SqlValue::Boolean(true),
-                    SqlValue::Nil,
-                ]
+                    SqlValue::Integer(1).into(),
+                    SqlValue::String("email@gmail.com".into()).into(),
+                    SqlValue::Boolean(true).into(),
+                    SqlValue::Nil.into(),
+                ],
+                returning: vec![],
             }
         );
+
+        let input =
+            "INSERT INTO users (id, name) VALUES (gen_random_uuid(), 'x') RETURNING id, name AS n";
+        let (_, stat) = parse_insert_statement(input).unwrap();
+        assert_eq!(stat.to_string(), input);
     }
 }
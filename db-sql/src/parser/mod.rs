@@ -1,19 +1,57 @@
+mod alter;
+mod analyze;
 mod condition;
+mod copy;
 mod create;
 mod delete;
+mod expr;
+mod index;
 mod insert;
+mod script;
 mod select;
+mod trigger;
+mod update;
+mod user;
+mod view;
+mod window;
+mod with;
 
-use condition::*;
+use crate::types::parse_hex;
+pub use crate::types::{SqlDataType, SqlValue};
+pub use alter::AlterAction;
+use alter::*;
+use analyze::*;
+pub use condition::{Condition, LogicalOperator, Operator};
+use copy::*;
 use create::*;
 use delete::*;
+pub use expr::{ArithmeticOperator, Expr};
+use index::*;
 use insert::*;
+pub use script::*;
 use select::*;
-use std::fmt::{Display, Formatter};
+pub use select::{Projection, Query, Select, SetOperator, TableRef};
+use std::{
+    cell::Cell,
+    fmt::{Display, Formatter},
+};
+use trigger::*;
+pub use trigger::{Trigger, TriggerEvent, TriggerTiming};
+use update::*;
+use user::*;
+pub use user::{Password, Privilege};
+use view::*;
+pub use window::{OrderBy, Window};
+pub use with::Cte;
 
 use nom::{
-    IResult, Parser, branch::alt, bytes::complete::take_while1, bytes::streaming::tag_no_case,
-    character::char, combinator::map, sequence::delimited,
+    IResult, Parser,
+    branch::alt,
+    bytes::complete::{tag_no_case, take_while1},
+    character::complete::{char, satisfy, space0},
+    combinator::{map, map_res, not, opt, peek, recognize},
+    number::complete::recognize_float,
+    sequence::{preceded, terminated},
 };
 
 fn parse_sql(input: &str) -> IResult<&str, SqlStatement> {
@@ -21,10 +59,36 @@ fn parse_sql(input: &str) -> IResult<&str, SqlStatement> {
         Ok(x)
     } else if let Ok(x) = parse_create_statement(input) {
         Ok(x)
+    } else if let Ok(x) = parse_create_view(input) {
+        Ok(x)
+    } else if let Ok(x) = parse_drop_view(input) {
+        Ok(x)
+    } else if let Ok(x) = parse_create_index(input) {
+        Ok(x)
+    } else if let Ok(x) = parse_drop_index(input) {
+        Ok(x)
+    } else if let Ok(x) = parse_create_trigger(input) {
+        Ok(x)
+    } else if let Ok(x) = parse_drop_trigger(input) {
+        Ok(x)
+    } else if let Ok(x) = parse_create_user(input) {
+        Ok(x)
+    } else if let Ok(x) = parse_drop_user(input) {
+        Ok(x)
+    } else if let Ok(x) = parse_grant_statement(input) {
+        Ok(x)
     } else if let Ok(x) = parse_delete_command(input) {
         Ok(x)
     } else if let Ok(x) = parse_insert_statement(input) {
         Ok(x)
+    } else if let Ok(x) = parse_copy_statement(input) {
+        Ok(x)
+    } else if let Ok(x) = parse_update_statement(input) {
+        Ok(x)
+    } else if let Ok(x) = parse_alter_statement(input) {
+        Ok(x)
+    } else if let Ok(x) = parse_analyze(input) {
+        Ok(x)
     } else {
         Err(nom::Err::Error(nom::error::make_error(
             input,
@@ -35,90 +99,463 @@ fn parse_sql(input: &str) -> IResult<&str, SqlStatement> {
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum SqlStatement {
-    Select {
-        table: Identifier,
-        columns: Vec<String>,
-        condition: Option<Condition>,
-    },
+    Select(Box<Query>),
     Create {
         table: Identifier,
         columns: Vec<ColumnDefinition>,
+        storage: TableStorage,
+    },
+    /// `CREATE VIEW name AS SELECT ...`, a query named like a table
+    CreateView {
+        name: Identifier,
+        query: Box<Query>,
+    },
+    DropView {
+        name: Identifier,
+    },
+    /// `CREATE INDEX name ON table USING fulltext(column)`, an index of the words
+    /// of a text column for `MATCH`, or `USING btree(column)` of its values in order
+    CreateIndex {
+        name: Identifier,
+        table: Identifier,
+        column: Identifier,
+        kind: IndexKind,
+    },
+    DropIndex {
+        name: Identifier,
+    },
+    CreateTrigger(Box<Trigger>),
+    DropTrigger {
+        name: Identifier,
     },
     Insert {
         table: Identifier,
-        values: Vec<SqlValue>,
+        /// the columns the values are given for, all of them in order when empty
+        columns: Vec<Identifier>,
+        values: Vec<Expr>,
+        /// the select list of the inserted row, nothing is returned when empty
+        returning: Vec<Projection>,
     },
     Delete {
         table: Identifier,
         condition: Option<Condition>,
     },
+    Update {
+        table: Identifier,
+        /// the columns and their new values, computed from the old row
+        assignments: Vec<(Identifier, Expr)>,
+        /// `AT ROW n`, only the row at this position of the table, which the journal
+        /// records the new values of each row with
+        row: Option<usize>,
+        condition: Option<Condition>,
+    },
+    Copy {
+        table: Identifier,
+        direction: CopyDirection,
+        path: String,
+        header: bool,
+    },
+    Alter {
+        table: Identifier,
+        action: AlterAction,
+    },
+    /// `ANALYZE [table]`, collects the statistics of the columns
+    Analyze {
+        table: Option<Identifier>,
+    },
+    CreateUser {
+        name: Identifier,
+        password: Password,
+    },
+    DropUser {
+        name: Identifier,
+    },
+    /// `GRANT SELECT, INSERT ON table TO user`
+    Grant {
+        privileges: Vec<Privilege>,
+        table: Identifier,
+        user: Identifier,
+    },
+    Revoke {
+        privileges: Vec<Privilege>,
+        table: Identifier,
+        user: Identifier,
+    },
+}
+impl SqlStatement {
+    /// An `INSERT` of the values of all the columns, e.g. of a stored row
+    pub fn insert_values(table: Identifier, values: &[SqlValue]) -> Self {
+        SqlStatement::Insert {
+            table,
+            columns: vec![],
+            values: values.iter().cloned().map(Expr::Literal).collect(),
+            returning: vec![],
+        }
+    }
+    /// Returns the number of `?` parameters in the statement
+    pub fn placeholder_count(&self) -> usize {
+        let mut count = 0;
+        self.clone().visit_placeholders(&mut |_| count += 1);
+        count
+    }
+    /// Replaces the `?` parameters, in the order they appear, with the given values
+    pub fn bind_values(&self, params: &[SqlValue]) -> SqlStatement {
+        let mut params = params.iter();
+        let mut stmt = self.clone();
+        stmt.visit_placeholders(&mut |x| {
+            if let Some(param) = params.next() {
+                *x = param.clone();
+            }
+        });
+        stmt
+    }
+    fn visit_placeholders(&mut self, f: &mut impl FnMut(&mut SqlValue)) {
+        self.visit_exprs(&mut |x| {
+            if let Expr::Literal(x @ SqlValue::Placeholder) = x {
+                f(x)
+            }
+        });
+    }
+    /// Visits the expressions of the statement in the order they appear
+    pub(crate) fn visit_exprs(&mut self, f: &mut impl FnMut(&mut Expr)) {
+        match self {
+            SqlStatement::Select(select) => select.visit_exprs(f),
+            SqlStatement::Delete { condition, .. } => {
+                if let Some(condition) = condition {
+                    condition.visit_exprs(f);
+                }
+            }
+            SqlStatement::Update {
+                assignments,
+                condition,
+                ..
+            } => {
+                for (_, expr) in assignments {
+                    expr.visit_exprs(f);
+                }
+                if let Some(condition) = condition {
+                    condition.visit_exprs(f);
+                }
+            }
+            SqlStatement::Insert {
+                values, returning, ..
+            } => {
+                values.iter_mut().for_each(|x| x.visit_exprs(f));
+                for column in returning {
+                    if let Projection::Expr { expr, .. } = column {
+                        expr.visit_exprs(f);
+                    }
+                }
+            }
+            SqlStatement::CreateView { query, .. } => query.visit_exprs(f),
+            SqlStatement::CreateTrigger(trigger) => {
+                trigger.body.iter_mut().for_each(|x| x.visit_exprs(f))
+            }
+            SqlStatement::Create { .. }
+            | SqlStatement::DropView { .. }
+            | SqlStatement::CreateIndex { .. }
+            | SqlStatement::DropIndex { .. }
+            | SqlStatement::DropTrigger { .. }
+            | SqlStatement::Copy { .. }
+            | SqlStatement::Alter { .. }
+            | SqlStatement::Analyze { .. }
+            | SqlStatement::CreateUser { .. }
+            | SqlStatement::DropUser { .. }
+            | SqlStatement::Grant { .. }
+            | SqlStatement::Revoke { .. } => {}
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CopyDirection {
+    From,
+    To,
+}
+impl Display for CopyDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CopyDirection::From => write!(f, "FROM"),
+            CopyDirection::To => write!(f, "TO"),
+        }
+    }
+}
+
+impl Display for SqlStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SqlStatement::Select(select) => write!(f, "{}", select),
+            SqlStatement::Create {
+                table,
+                columns,
+                storage,
+            } => {
+                let columns: Vec<_> = columns.iter().map(|x| x.to_string()).collect();
+                write!(f, "CREATE TABLE {} ({})", table, columns.join(", "))?;
+                if *storage != TableStorage::Rows {
+                    write!(f, " USING {}", storage)?;
+                }
+                Ok(())
+            }
+            SqlStatement::CreateView { name, query } => {
+                write!(f, "CREATE VIEW {} AS {}", name, query)
+            }
+            SqlStatement::DropView { name } => write!(f, "DROP VIEW {}", name),
+            SqlStatement::CreateIndex {
+                name,
+                table,
+                column,
+                kind,
+            } => write!(
+                f,
+                "CREATE INDEX {} ON {} USING {}({})",
+                name, table, kind, column
+            ),
+            SqlStatement::DropIndex { name } => write!(f, "DROP INDEX {}", name),
+            SqlStatement::CreateTrigger(trigger) => write!(f, "{}", trigger),
+            SqlStatement::DropTrigger { name } => write!(f, "DROP TRIGGER {}", name),
+            SqlStatement::Insert {
+                table,
+                columns,
+                values,
+                returning,
+            } => {
+                write!(f, "INSERT INTO {}", table)?;
+                if !columns.is_empty() {
+                    let columns: Vec<_> = columns.iter().map(|x| x.to_string()).collect();
+                    write!(f, " ({})", columns.join(", "))?;
+                }
+                let values: Vec<_> = values.iter().map(|x| x.to_string()).collect();
+                write!(f, " VALUES ({})", values.join(", "))?;
+                if !returning.is_empty() {
+                    let returning: Vec<_> = returning.iter().map(|x| x.to_string()).collect();
+                    write!(f, " RETURNING {}", returning.join(", "))?;
+                }
+                Ok(())
+            }
+            SqlStatement::Delete { table, condition } => {
+                write!(f, "DELETE {}", table)?;
+                if let Some(condition) = condition {
+                    write!(f, " WHERE {}", condition)?;
+                }
+                Ok(())
+            }
+            SqlStatement::Update {
+                table,
+                assignments,
+                row,
+                condition,
+            } => {
+                let assignments: Vec<_> = assignments
+                    .iter()
+                    .map(|(column, expr)| format!("{} = {}", column, expr))
+                    .collect();
+                write!(f, "UPDATE {} SET {}", table, assignments.join(", "))?;
+                if let Some(row) = row {
+                    write!(f, " AT ROW {row}")?;
+                }
+                if let Some(condition) = condition {
+                    write!(f, " WHERE {}", condition)?;
+                }
+                Ok(())
+            }
+            SqlStatement::Copy {
+                table,
+                direction,
+                path,
+                header,
+            } => {
+                let path = SqlValue::String(path.clone()).to_literal();
+                write!(f, "COPY {} {} {}", table, direction, path)?;
+                if *header {
+                    write!(f, " WITH HEADER")?;
+                }
+                Ok(())
+            }
+            SqlStatement::Alter { table, action } => write!(f, "ALTER TABLE {} {}", table, action),
+            SqlStatement::Analyze { table: None } => write!(f, "ANALYZE"),
+            SqlStatement::Analyze { table: Some(table) } => write!(f, "ANALYZE {}", table),
+            SqlStatement::CreateUser { name, password } => {
+                write!(f, "CREATE USER {} {}", name, password)
+            }
+            SqlStatement::DropUser { name } => write!(f, "DROP USER {}", name),
+            SqlStatement::Grant {
+                privileges,
+                table,
+                user,
+            } => {
+                let privileges: Vec<_> = privileges.iter().map(|x| x.to_string()).collect();
+                write!(
+                    f,
+                    "GRANT {} ON {} TO {}",
+                    privileges.join(", "),
+                    table,
+                    user
+                )
+            }
+            SqlStatement::Revoke {
+                privileges,
+                table,
+                user,
+            } => {
+                let privileges: Vec<_> = privileges.iter().map(|x| x.to_string()).collect();
+                write!(
+                    f,
+                    "REVOKE {} ON {} FROM {}",
+                    privileges.join(", "),
+                    table,
+                    user
+                )
+            }
+        }
+    }
 }
 
 impl TryFrom<&str> for SqlStatement {
     type Error = String;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let near = |input: &str| {
+            let near: String = input.chars().take(32).collect();
+            format!("syntax error near '{}'", near.trim())
+        };
         match parse_sql(value) {
-            Ok(x) => Ok(x.1),
-            Err(e) => Err(e.to_string()),
+            // the whole input is the statement, only a `;` may follow it
+            Ok((rest, stmt)) => match rest.trim().trim_start_matches(';').trim() {
+                "" => Ok(stmt),
+                _ => Err(near(rest)),
+            },
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => Err(near(e.input)),
+            Err(nom::Err::Incomplete(_)) => Err("incomplete statement".into()),
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub enum SqlDataType {
-    Integer,
-    String,
-    Boolean,
-    Uuid,
-    Text,
-    Float,
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnDefinition {
+    pub name: Identifier,
+    pub data_type: SqlDataType,
+    pub constraint: Option<String>,
+    /// the value of the column when an insert leaves it out
+    pub default: Option<Expr>,
+    /// whether the values missing on insert are taken from the sequence of the table
+    pub auto_increment: bool,
+    /// the column of another table, or of the same one, the values must be found in
+    pub references: Option<ForeignKey>,
+}
+impl ColumnDefinition {
+    pub fn is_not_null(&self) -> bool {
+        matches!(self.constraint.as_deref(), Some("NOT NULL" | "PRIMARY KEY"))
+    }
+    pub fn is_unique(&self) -> bool {
+        matches!(self.constraint.as_deref(), Some("UNIQUE" | "PRIMARY KEY"))
+    }
+}
+impl Display for ColumnDefinition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.name, self.data_type)?;
+        if self.auto_increment {
+            write!(f, " AUTOINCREMENT")?;
+        }
+        if let Some(default) = &self.default {
+            write!(f, " DEFAULT {}", default)?;
+        }
+        if let Some(constraint) = &self.constraint {
+            write!(f, " {}", constraint)?;
+        }
+        if let Some(references) = &self.references {
+            write!(f, " {}", references)?;
+        }
+        Ok(())
+    }
 }
-impl TryFrom<&str> for SqlDataType {
-    type Error = nom::Err<&'static str>;
 
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        Ok(match value.to_uppercase().as_str() {
-            "INT" => SqlDataType::Integer,
-            "STRING" => SqlDataType::String,
-            "UUID" => SqlDataType::Uuid,
-            "TEXT" => SqlDataType::Text,
-            "BIT" => SqlDataType::Boolean,
-            "FLOAT" => SqlDataType::Float,
-            _ => return Err(Self::Error::Failure("Invalid DataType")),
-        })
+/// How the rows of a table are stored, given by `CREATE TABLE ... USING columnar`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TableStorage {
+    /// row by row, the default
+    #[default]
+    Rows,
+    /// column by column with their values encoded, for scans and aggregates
+    Columnar,
+}
+impl Display for TableStorage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableStorage::Rows => write!(f, "rows"),
+            TableStorage::Columnar => write!(f, "columnar"),
+        }
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
-pub enum SqlValue {
-    String(String),
-    Boolean(bool),
-    Integer(i32),
-    Float(f64),
-    Nil,
-}
-impl Display for SqlValue {
+/// What an index of a column holds, given by `CREATE INDEX ... USING`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IndexKind {
+    /// the words of a text column, for `MATCH`
+    FullText,
+    /// the values in order, for comparisons and joins
+    BTree,
+}
+impl Display for IndexKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            SqlValue::String(s) => write!(f, "{}", s),
-            SqlValue::Boolean(b) => write!(f, "{}", b),
-            SqlValue::Integer(i) => write!(f, "{}", i),
-            SqlValue::Float(x) => write!(f, "{}", x),
-            SqlValue::Nil => write!(f, "nil"),
+            IndexKind::FullText => write!(f, "fulltext"),
+            IndexKind::BTree => write!(f, "btree"),
         }
     }
 }
 
+/// `REFERENCES table(column)`, a column whose values must be present in another
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct ColumnDefinition {
-    pub name: Identifier,
-    pub data_type: SqlDataType,
-    pub constraint: Option<String>,
+pub struct ForeignKey {
+    pub table: Identifier,
+    pub column: Identifier,
+    pub on_delete: ReferentialAction,
+}
+impl Display for ForeignKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "REFERENCES {}({})", self.table, self.column)?;
+        if self.on_delete != ReferentialAction::Restrict {
+            write!(f, " ON DELETE {}", self.on_delete)?;
+        }
+        Ok(())
+    }
 }
 
+/// What happens to the referencing rows when the row they reference is deleted
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ReferentialAction {
+    /// the delete fails
+    #[default]
+    Restrict,
+    /// the referencing rows are deleted too
+    Cascade,
+    /// the referencing columns become nil
+    SetNull,
+}
+impl Display for ReferentialAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReferentialAction::Restrict => write!(f, "RESTRICT"),
+            ReferentialAction::Cascade => write!(f, "CASCADE"),
+            ReferentialAction::SetNull => write!(f, "SET NULL"),
+        }
+    }
+}
+
+/// A name, a column may be qualified by its table as `table.column`
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Identifier(pub String);
+impl Identifier {
+    /// The table of a qualified column
+    pub fn qualifier(&self) -> Option<&str> {
+        self.0.rsplit_once('.').map(|x| x.0)
+    }
+    /// The name without the qualifier
+    pub fn column(&self) -> &str {
+        self.0.rsplit_once('.').map_or(&self.0, |x| x.1)
+    }
+}
 impl From<&str> for Identifier {
     fn from(value: &str) -> Self {
         Self(String::from(value))
@@ -133,47 +570,169 @@ fn identifier(input: &str) -> IResult<&str, Identifier> {
     let x = take_while1(|c: char| c.is_alphanumeric() || c == '_');
     map(x, Identifier::from).parse(input)
 }
-
-fn identifier_string(input: &str) -> IResult<&str, String> {
-    let x = take_while1(|c: char| c.is_alphanumeric() || c == '_');
-    map(x, |x: &str| x.into()).parse(input)
-}
-fn string_value(input: &str) -> IResult<&str, SqlValue> {
+/// A column, optionally qualified by its table
+fn qualified_identifier(input: &str) -> IResult<&str, Identifier> {
     map(
-        delimited(
-            char('\''),
-            map(take_while1(|x| x != '\''), |s: &str| s.to_string()),
-            char('\''),
-        ),
-        |x| SqlValue::String(x),
+        recognize((identifier, opt((char('.'), identifier)))),
+        Identifier::from,
     )
     .parse(input)
 }
-fn boolean_value(input: &str) -> IResult<&str, SqlValue> {
-    let (input, val) = alt((tag_no_case("true"), tag_no_case("false"))).parse(input)?;
+/// The deepest nesting of expressions, conditions and queries in one another, e.g.
+/// of parentheses. Each level is parsed by recursion, which takes up to 35KB of
+/// stack in a debug build, and threads get 2MB by default.
+const MAX_DEPTH: usize = 32;
 
-    if val.to_lowercase() == "true" {
-        Ok((input, SqlValue::Boolean(true)))
-    } else {
-        Ok((input, SqlValue::Boolean(false)))
+thread_local! {
+    /// how many expressions, conditions and queries are being parsed, one inside the
+    /// other
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Runs the parser one level deeper, the input is rejected past [`MAX_DEPTH`]
+/// levels instead of overflowing the stack
+fn nested<'a, O>(
+    input: &'a str,
+    mut parser: impl Parser<&'a str, Output = O, Error = nom::error::Error<&'a str>>,
+) -> IResult<&'a str, O> {
+    let depth = DEPTH.get();
+    if depth >= MAX_DEPTH {
+        return Err(nom::Err::Failure(nom::error::make_error(
+            input,
+            nom::error::ErrorKind::TooLarge,
+        )));
     }
+    DEPTH.set(depth + 1);
+    let res = parser.parse(input);
+    DEPTH.set(depth);
+    res
+}
+
+/// The keywords which cannot be taken for an alias
+fn is_reserved(word: &str) -> bool {
+    const RESERVED: &[&str] = &[
+        "WHERE",
+        "FROM",
+        "AS",
+        "ON",
+        "JOIN",
+        "INNER",
+        "CROSS",
+        "UNION",
+        "INTERSECT",
+        "EXCEPT",
+        "ORDER",
+        "GROUP",
+        "LIMIT",
+        "END",
+    ];
+    RESERVED.iter().any(|x| x.eq_ignore_ascii_case(word))
+}
+
+/// A single quoted string, a doubled quote stands for a quote
+fn quoted(input: &str) -> IResult<&str, String> {
+    let (mut input, _) = char('\'').parse(input)?;
+    let mut value = String::new();
+    loop {
+        let Some(end) = input.find('\'') else {
+            return Err(nom::Err::Error(nom::error::make_error(
+                input,
+                nom::error::ErrorKind::Char,
+            )));
+        };
+        value.push_str(&input[..end]);
+        input = &input[end + 1..];
+        // a doubled quote is an escaped one
+        match input.strip_prefix('\'') {
+            Some(rest) => {
+                value.push('\'');
+                input = rest;
+            }
+            None => break,
+        }
+    }
+    Ok((input, value))
+}
+fn string_value(input: &str) -> IResult<&str, SqlValue> {
+    map(quoted, SqlValue::String).parse(input)
+}
+/// Matches the keyword only as a whole word, e.g. `true` but not `trueish`
+fn keyword<'a>(
+    kw: &'static str,
+) -> impl Parser<&'a str, Output = &'a str, Error = nom::error::Error<&'a str>> {
+    terminated(
+        tag_no_case(kw),
+        not(peek(satisfy(|c: char| c.is_alphanumeric() || c == '_'))),
+    )
+}
+fn boolean_value(input: &str) -> IResult<&str, SqlValue> {
+    alt((
+        map(keyword("true"), |_| SqlValue::Boolean(true)),
+        map(keyword("false"), |_| SqlValue::Boolean(false)),
+    ))
+    .parse(input)
 }
 fn nil_value(input: &str) -> IResult<&str, SqlValue> {
-    map(tag_no_case("NIL"), |_| SqlValue::Nil).parse(input)
+    map(keyword("NIL"), |_| SqlValue::Nil).parse(input)
 }
 fn int_value(input: &str) -> IResult<&str, SqlValue> {
-    map(
-        take_while1(|x: char| x.is_numeric() || x == '-' || x == '.'),
-        |s: &str| {
-            if s.contains('.') {
-                SqlValue::Float(s.parse::<f64>().expect("Invalid Float"))
-            } else {
-                SqlValue::Integer(s.parse::<i32>().expect("Invalid Integer"))
+    map_res(recognize_float, |s: &str| {
+        if s.contains(['.', 'e', 'E']) {
+            match s.parse::<f64>() {
+                Ok(x) if x.is_finite() => Ok(SqlValue::Float(x)),
+                Ok(_) => Err(format!("{s} is out of range for FLOAT")),
+                Err(e) => Err(e.to_string()),
             }
+        } else {
+            s.parse::<i64>()
+                .map(SqlValue::Integer)
+                .map_err(|e| e.to_string())
+        }
+    })
+    .parse(input)
+}
+/// A string prefixed by its type, e.g. `DATE '2024-01-31'`
+fn typed_value(input: &str) -> IResult<&str, SqlValue> {
+    map_res(
+        (
+            alt((
+                keyword("DECIMAL"),
+                keyword("DATE"),
+                keyword("TIMESTAMP"),
+                keyword("UUID"),
+                keyword("JSON"),
+            )),
+            space0,
+            quoted,
+        ),
+        |(data_type, _, text)| {
+            let data_type = SqlDataType::try_from(data_type).map_err(|_| "invalid type")?;
+            data_type
+                .parse_value(Some(&text))
+                .map_err(|_| "invalid literal")
         },
     )
     .parse(input)
 }
+/// Hex digits of a blob, e.g. `X'cafe'`
+fn blob_value(input: &str) -> IResult<&str, SqlValue> {
+    map_res(preceded(tag_no_case("X"), quoted), |text| {
+        parse_hex(&text).map(SqlValue::Blob).ok_or("invalid hex")
+    })
+    .parse(input)
+}
+fn placeholder(input: &str) -> IResult<&str, SqlValue> {
+    map(char('?'), |_| SqlValue::Placeholder).parse(input)
+}
 fn parse_sql_value(input: &str) -> IResult<&str, SqlValue> {
-    alt((string_value, int_value, boolean_value, nil_value)).parse(input)
+    alt((
+        string_value,
+        int_value,
+        boolean_value,
+        nil_value,
+        typed_value,
+        blob_value,
+        placeholder,
+    ))
+    .parse(input)
 }
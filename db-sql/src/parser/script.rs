@@ -0,0 +1,93 @@
+/// Splits a SQL script into the statements terminated by `;`.
+///
+/// Quoted strings are kept intact, `--` comments are dropped and line breaks become
+/// spaces, as the parser expects a statement on a single line. The `;` of the body of
+/// a `CREATE TRIGGER`, between `BEGIN` and `END`, do not end it. The unterminated tail
+/// is returned as the second item so callers reading line by line (the REPL) can keep
+/// buffering until the statement is complete.
+pub fn split_statements(input: &str) -> (Vec<String>, String) {
+    let mut statements = vec![];
+    let mut current = String::new();
+    let mut in_quote = false;
+    let mut in_body = false;
+    let mut word = String::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if !in_quote && (c.is_alphanumeric() || c == '_') {
+            word.push(c);
+        } else if !word.is_empty() {
+            let trigger = || {
+                let mut words = current.split_whitespace();
+                words
+                    .next()
+                    .is_some_and(|x| x.eq_ignore_ascii_case("CREATE"))
+                    && words
+                        .next()
+                        .is_some_and(|x| x.eq_ignore_ascii_case("TRIGGER"))
+            };
+            if word.eq_ignore_ascii_case("BEGIN") && trigger() {
+                in_body = true;
+            } else if word.eq_ignore_ascii_case("END") {
+                in_body = false;
+            }
+            word.clear();
+        }
+        match c {
+            '\'' => {
+                in_quote = !in_quote;
+                current.push(c);
+            }
+            '-' if !in_quote && chars.peek() == Some(&'-') => {
+                // skip the comment up to the end of the line
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        current.push(' ');
+                        break;
+                    }
+                }
+            }
+            ';' if !in_quote && !in_body => {
+                let stmt = current.trim();
+                if !stmt.is_empty() {
+                    statements.push(stmt.to_string());
+                }
+                current.clear();
+            }
+            '\r' | '\n' | '\t' if !in_quote => current.push(' '),
+            _ => current.push(c),
+        }
+    }
+
+    (statements, current.trim_start().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_split_statements() {
+        let input = "CREATE TABLE t (id INT);\n-- comment; here\nINSERT INTO t VALUES ('a;b');\nSELECT id\nFROM t\n";
+        let (statements, rest) = split_statements(input);
+        assert_eq!(
+            statements,
+            vec![
+                "CREATE TABLE t (id INT)".to_string(),
+                "INSERT INTO t VALUES ('a;b')".to_string(),
+            ]
+        );
+        assert_eq!(rest, "SELECT id FROM t ");
+
+        let input = "CREATE TRIGGER t AFTER INSERT ON t BEGIN\n  DELETE log;\n  INSERT INTO log VALUES (NEW.id);\nEND;\nSELECT end FROM t;";
+        let (statements, rest) = split_statements(input);
+        assert_eq!(
+            statements,
+            vec![
+                "CREATE TRIGGER t AFTER INSERT ON t BEGIN   DELETE log;   INSERT INTO log VALUES (NEW.id); END".to_string(),
+                "SELECT end FROM t".to_string(),
+            ]
+        );
+        assert_eq!(rest, "");
+    }
+}
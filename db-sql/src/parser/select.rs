@@ -1,40 +1,505 @@
 use crate::{
-    parser::condition::parse_where_clause,
-    parser::{SqlStatement, identifier, identifier_string},
+    parser::condition::{parse_condition, parse_where_clause},
+    parser::{
+        Condition, Expr, Identifier, SqlStatement,
+        expr::parse_expr,
+        identifier, is_reserved, keyword, nested,
+        with::{Cte, with_clause},
+    },
 };
 use nom::{
     IResult, Parser,
     branch::alt,
-    bytes::tag_no_case,
     character::complete::char,
     character::complete::{space0, space1},
-    combinator::map,
-    combinator::opt,
-    multi::separated_list1,
-    sequence::{delimited, preceded},
+    combinator::{map, opt, verify},
+    multi::{many0, separated_list1},
+    sequence::{delimited, preceded, terminated},
 };
+use std::fmt::{Display, Formatter};
 
-fn column_list(input: &str) -> IResult<&str, Vec<String>> {
-    separated_list1(delimited(space0, char(','), space0), identifier_string).parse(input)
+/// A query, which may also be the subquery of an expression or a `FROM`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    Select(Box<Select>),
+    /// `left UNION right` and the like, chained from left to right
+    Compound {
+        left: Box<Query>,
+        operator: SetOperator,
+        right: Box<Select>,
+    },
+    /// `WITH [RECURSIVE] name AS (query), ... body`, the CTEs are only seen by the
+    /// body and the CTEs after them, or by themselves when recursive
+    With {
+        recursive: bool,
+        ctes: Vec<Cte>,
+        body: Box<Query>,
+    },
+}
+impl Query {
+    /// The tables and views the query reads, including those of its subqueries
+    pub fn tables(&self) -> Vec<&Identifier> {
+        match self {
+            Query::Select(select) => select.tables(),
+            Query::Compound { left, right, .. } => [left.tables(), right.tables()].concat(),
+            // the CTEs are not tables, the ones they read are
+            Query::With { ctes, body, .. } => {
+                let mut tables = body.tables();
+                for cte in ctes {
+                    tables.extend(cte.query.tables());
+                }
+                tables.retain(|x| !ctes.iter().any(|cte| &cte.name == *x));
+                tables
+            }
+        }
+    }
+    /// Visits the expressions of the query in the order they appear
+    pub(crate) fn visit_exprs(&mut self, f: &mut impl FnMut(&mut Expr)) {
+        match self {
+            Query::Select(select) => select.visit_exprs(f),
+            Query::Compound { left, right, .. } => {
+                left.visit_exprs(f);
+                right.visit_exprs(f);
+            }
+            Query::With { ctes, body, .. } => {
+                for cte in ctes {
+                    cte.visit_exprs(f);
+                }
+                body.visit_exprs(f);
+            }
+        }
+    }
+}
+impl From<Select> for Query {
+    fn from(value: Select) -> Self {
+        Query::Select(Box::new(value))
+    }
+}
+impl Display for Query {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Query::Select(select) => write!(f, "{}", select),
+            Query::Compound {
+                left,
+                operator,
+                right,
+            } => write!(f, "{} {} {}", left, operator, right),
+            Query::With {
+                recursive,
+                ctes,
+                body,
+            } => {
+                let ctes: Vec<_> = ctes.iter().map(|x| x.to_string()).collect();
+                let recursive = if *recursive { "RECURSIVE " } else { "" };
+                write!(f, "WITH {}{} {}", recursive, ctes.join(", "), body)
+            }
+        }
+    }
 }
 
-fn select_statement(input: &str) -> IResult<&str, SqlStatement> {
+/// How the rows of the queries of a compound are combined
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetOperator {
+    /// the distinct rows of both
+    Union,
+    /// all the rows of both, with duplicates
+    UnionAll,
+    /// the distinct rows found in both
+    Intersect,
+    /// the distinct rows of the left one not found in the right one
+    Except,
+}
+impl Display for SetOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let txt = match self {
+            SetOperator::Union => "UNION",
+            SetOperator::UnionAll => "UNION ALL",
+            SetOperator::Intersect => "INTERSECT",
+            SetOperator::Except => "EXCEPT",
+        };
+        write!(f, "{}", txt)
+    }
+}
+
+/// A single `SELECT`, without set operators
+#[derive(Clone, Debug, PartialEq)]
+pub struct Select {
+    /// whether the duplicate rows are removed
+    pub distinct: bool,
+    pub columns: Vec<Projection>,
+    pub from: TableRef,
+    pub condition: Option<Condition>,
+}
+impl Select {
+    /// The tables and views the query reads, including those of its subqueries
+    pub fn tables(&self) -> Vec<&Identifier> {
+        let mut queries = vec![];
+        for column in &self.columns {
+            if let Projection::Expr { expr, .. } = column {
+                queries.extend(expr.subqueries());
+            }
+        }
+        if let Some(condition) = &self.condition {
+            queries.extend(condition.subqueries());
+        }
+        let mut tables = vec![];
+        for from in self.from.leaves() {
+            match from {
+                TableRef::Table { name, .. } => tables.push(name),
+                TableRef::Subquery { query, .. } => tables.extend(query.tables()),
+                TableRef::Join { .. } => {}
+            }
+        }
+        for condition in self.from.conditions() {
+            queries.extend(condition.subqueries());
+        }
+        for query in queries {
+            tables.extend(query.tables());
+        }
+        tables
+    }
+    /// Visits the expressions of the query in the order they appear
+    pub(crate) fn visit_exprs(&mut self, f: &mut impl FnMut(&mut Expr)) {
+        for column in &mut self.columns {
+            if let Projection::Expr { expr, .. } = column {
+                expr.visit_exprs(f);
+            }
+        }
+        self.from.visit_exprs(f);
+        if let Some(condition) = &mut self.condition {
+            condition.visit_exprs(f);
+        }
+    }
+}
+impl Display for Select {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let columns: Vec<_> = self.columns.iter().map(|x| x.to_string()).collect();
+        let distinct = if self.distinct { "DISTINCT " } else { "" };
+        write!(
+            f,
+            "SELECT {}{} FROM {}",
+            distinct,
+            columns.join(", "),
+            self.from
+        )?;
+        if let Some(condition) = &self.condition {
+            write!(f, " WHERE {}", condition)?;
+        }
+        Ok(())
+    }
+}
+
+/// The source of the rows of a query
+#[derive(Clone, Debug, PartialEq)]
+pub enum TableRef {
+    Table {
+        name: Identifier,
+        alias: Option<Identifier>,
+    },
+    /// `(SELECT ...) AS alias`, a derived table
+    Subquery {
+        query: Box<Query>,
+        alias: Identifier,
+    },
+    /// `left [INNER] JOIN right ON condition`, or `left CROSS JOIN right` and
+    /// `left, right` without a condition
+    Join {
+        left: Box<TableRef>,
+        right: Box<TableRef>,
+        condition: Option<Condition>,
+    },
+}
+impl TableRef {
+    /// The name the columns are qualified by, the alias if there is one. The
+    /// columns of a join are qualified by the names of its tables, the first one
+    /// stands for the join.
+    pub fn name(&self) -> &Identifier {
+        match self {
+            TableRef::Table {
+                alias: Some(alias), ..
+            }
+            | TableRef::Subquery { alias, .. } => alias,
+            TableRef::Table { name, .. } => name,
+            TableRef::Join { left, .. } => left.name(),
+        }
+    }
+    /// The tables and derived tables of a join in their order, or itself
+    pub fn leaves(&self) -> Vec<&TableRef> {
+        match self {
+            TableRef::Join { left, right, .. } => [left.leaves(), right.leaves()].concat(),
+            x => vec![x],
+        }
+    }
+    /// The `ON` conditions of a join in their order
+    pub fn conditions(&self) -> Vec<&Condition> {
+        match self {
+            TableRef::Join {
+                left,
+                right,
+                condition,
+            } => {
+                let mut conditions = left.conditions();
+                conditions.extend(right.conditions());
+                conditions.extend(condition);
+                conditions
+            }
+            _ => vec![],
+        }
+    }
+    fn visit_exprs(&mut self, f: &mut impl FnMut(&mut Expr)) {
+        match self {
+            TableRef::Table { .. } => {}
+            TableRef::Subquery { query, .. } => query.visit_exprs(f),
+            TableRef::Join {
+                left,
+                right,
+                condition,
+            } => {
+                left.visit_exprs(f);
+                right.visit_exprs(f);
+                if let Some(condition) = condition {
+                    condition.visit_exprs(f);
+                }
+            }
+        }
+    }
+}
+impl From<&str> for TableRef {
+    fn from(value: &str) -> Self {
+        TableRef::Table {
+            name: value.into(),
+            alias: None,
+        }
+    }
+}
+impl Display for TableRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableRef::Table { name, alias } => {
+                write!(f, "{}", name)?;
+                if let Some(alias) = alias {
+                    write!(f, " AS {}", alias)?;
+                }
+                Ok(())
+            }
+            TableRef::Subquery { query, alias } => write!(f, "({}) AS {}", query, alias),
+            TableRef::Join {
+                left,
+                right,
+                condition: Some(condition),
+            } => write!(f, "{} JOIN {} ON {}", left, right, condition),
+            TableRef::Join {
+                left,
+                right,
+                condition: None,
+            } => write!(f, "{} CROSS JOIN {}", left, right),
+        }
+    }
+}
+
+/// An item of the select list
+#[derive(Clone, Debug, PartialEq)]
+pub enum Projection {
+    /// `*`, all the columns of the table
+    All,
+    Expr {
+        expr: Expr,
+        alias: Option<Identifier>,
+    },
+}
+impl Projection {
+    /// The name of the column in the result, the alias or the expression itself
+    pub fn name(&self) -> String {
+        match self {
+            Projection::All => "*".into(),
+            Projection::Expr {
+                alias: Some(alias), ..
+            } => alias.0.clone(),
+            // a qualified column is named without its table
+            Projection::Expr {
+                expr: Expr::Column(x),
+                ..
+            } => x.column().to_string(),
+            Projection::Expr { expr, .. } => expr.to_string(),
+        }
+    }
+}
+impl From<&str> for Projection {
+    fn from(value: &str) -> Self {
+        Projection::Expr {
+            expr: value.into(),
+            alias: None,
+        }
+    }
+}
+impl Display for Projection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Projection::All => write!(f, "*"),
+            Projection::Expr { expr, alias } => {
+                write!(f, "{}", expr)?;
+                if let Some(alias) = alias {
+                    write!(f, " AS {}", alias)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn projection(input: &str) -> IResult<&str, Projection> {
+    alt((
+        map(char('*'), |_| Projection::All),
+        map(
+            (
+                parse_expr,
+                opt(preceded((space1, keyword("AS"), space1), identifier)),
+            ),
+            |(expr, alias)| Projection::Expr { expr, alias },
+        ),
+    ))
+    .parse(input)
+}
+pub(crate) fn column_list(input: &str) -> IResult<&str, Vec<Projection>> {
+    separated_list1(delimited(space0, char(','), space0), projection).parse(input)
+}
+/// `AS alias` or just `alias`, which must not be a keyword following a table
+fn alias(input: &str) -> IResult<&str, Identifier> {
+    alt((
+        preceded((keyword("AS"), space1), identifier),
+        verify(identifier, |x: &Identifier| !is_reserved(&x.0)),
+    ))
+    .parse(input)
+}
+fn table_ref(input: &str) -> IResult<&str, TableRef> {
+    alt((
+        map(
+            (
+                delimited((char('('), space0), query, (space0, char(')'))),
+                preceded(space0, alias),
+            ),
+            |(query, alias)| TableRef::Subquery {
+                query: Box::new(query),
+                alias,
+            },
+        ),
+        map(
+            (identifier, opt(preceded(space1, alias))),
+            |(name, alias)| TableRef::Table { name, alias },
+        ),
+    ))
+    .parse(input)
+}
+/// The tables of a `FROM` joined from left to right
+fn joined_tables(input: &str) -> IResult<&str, TableRef> {
+    let join = alt((
+        map(preceded((space0, char(','), space0), table_ref), |x| {
+            (x, None)
+        }),
+        map(
+            preceded(
+                (space1, keyword("CROSS"), space1, keyword("JOIN"), space1),
+                table_ref,
+            ),
+            |x| (x, None),
+        ),
+        map(
+            (
+                preceded(
+                    (
+                        space1,
+                        opt((keyword("INNER"), space1)),
+                        keyword("JOIN"),
+                        space1,
+                    ),
+                    table_ref,
+                ),
+                preceded((space1, keyword("ON")), parse_condition),
+            ),
+            |(x, condition)| (x, Some(condition)),
+        ),
+    ));
+    map((table_ref, many0(join)), |(first, rest)| {
+        rest.into_iter()
+            .fold(first, |left, (right, condition)| TableRef::Join {
+                left: Box::new(left),
+                right: Box::new(right),
+                condition,
+            })
+    })
+    .parse(input)
+}
+fn select(input: &str) -> IResult<&str, Select> {
     map(
         (
-            preceded((space0, tag_no_case("SELECT"), space1), column_list),
-            preceded((space0, tag_no_case("FROM"), space1), identifier),
+            preceded(
+                (space0, keyword("SELECT"), space1),
+                opt(terminated(
+                    alt((
+                        map(keyword("DISTINCT"), |_| true),
+                        map(keyword("ALL"), |_| false),
+                    )),
+                    space1,
+                )),
+            ),
+            column_list,
+            preceded((space0, keyword("FROM"), space1), joined_tables),
             parse_where_clause,
-            space0,
-            opt(char(';')),
         ),
-        |(columns, table, condition, _, _)| SqlStatement::Select {
-            table,
+        |(distinct, columns, from, condition)| Select {
+            distinct: distinct.unwrap_or(false),
             columns,
+            from,
             condition,
         },
     )
     .parse(input)
 }
+fn set_operator(input: &str) -> IResult<&str, SetOperator> {
+    alt((
+        map((keyword("UNION"), space1, keyword("ALL")), |_| {
+            SetOperator::UnionAll
+        }),
+        map(keyword("UNION"), |_| SetOperator::Union),
+        map(keyword("INTERSECT"), |_| SetOperator::Intersect),
+        map(keyword("EXCEPT"), |_| SetOperator::Except),
+    ))
+    .parse(input)
+}
+/// A query without the terminating semicolon, e.g. a subquery
+pub(crate) fn query(input: &str) -> IResult<&str, Query> {
+    let query = map(
+        (
+            opt(terminated(with_clause, space1)),
+            select,
+            many0((delimited(space1, set_operator, space1), select)),
+        ),
+        |(with, first, rest)| {
+            let query = rest
+                .into_iter()
+                .fold(first.into(), |left, (operator, right)| Query::Compound {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                });
+            match with {
+                Some((recursive, ctes)) => Query::With {
+                    recursive,
+                    ctes,
+                    body: Box::new(query),
+                },
+                None => query,
+            }
+        },
+    );
+    nested(input, query)
+}
+fn select_statement(input: &str) -> IResult<&str, SqlStatement> {
+    map(terminated(query, (space0, opt(char(';')))), |x| {
+        SqlStatement::Select(Box::new(x))
+    })
+    .parse(input)
+}
 
 pub fn parse_select_query(input: &str) -> IResult<&str, SqlStatement> {
     alt((select_statement,)).parse(input)
@@ -54,17 +519,113 @@ mod tests {
             output,
             (
                 "",
-                SqlStatement::Select {
-                    table: "users".into(),
+                SqlStatement::Select(Box::new(Query::Select(Box::new(Select {
+                    distinct: false,
                     columns: vec!["id".into(), "email".into(), "username".into()],
+                    from: "users".into(),
                     condition: Some(Condition::Comparison {
                         left: "id".into(),
                         operator: Operator::Equal,
-                        right: SqlValue::Integer(1),
+                        right: SqlValue::Integer(1).into(),
                     }),
                     // condition: Some("id = 1".into()),
-                }
+                }))))
             )
         );
     }
+
+    #[test]
+    fn should_parse_subqueries() {
+        let input = "SELECT u.name, (SELECT total FROM orders WHERE orders.user_id = u.id) AS total FROM (SELECT * FROM users WHERE EXISTS (SELECT id FROM orders) AND id NOT IN (SELECT id FROM banned)) AS u";
+        let (rest, stmt) = parse_select_query(input).unwrap();
+        assert_eq!(rest, "");
+        let SqlStatement::Select(query) = &stmt else {
+            panic!("expected a SELECT");
+        };
+        let Query::Select(select) = query.as_ref() else {
+            panic!("expected a single SELECT");
+        };
+        let TableRef::Subquery { query, alias } = &select.from else {
+            panic!("expected a derived table");
+        };
+        let Query::Select(query) = query.as_ref() else {
+            panic!("expected a single SELECT");
+        };
+        assert_eq!(alias, &"u".into());
+        assert!(matches!(
+            &query.condition,
+            Some(Condition::Logical { conditions, .. })
+                if matches!(conditions[..], [Condition::Exists { .. }, Condition::In { negated: true, .. }])
+        ));
+        assert_eq!(select.columns[0].name(), "name");
+        assert_eq!(stmt.to_string(), input);
+
+        let (_, stmt) = parse_select_query("SELECT * FROM users u WHERE id = 1").unwrap();
+        assert_eq!(stmt.to_string(), "SELECT * FROM users AS u WHERE id = 1");
+        let (rest, _) = parse_select_query("SELECT * FROM users WHERE id = 1").unwrap();
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn should_parse_compound_queries() {
+        let input =
+            "SELECT DISTINCT a FROM x UNION ALL SELECT b FROM y WHERE b > 1 EXCEPT SELECT c FROM z";
+        let (rest, stmt) = parse_select_query(input).unwrap();
+        assert_eq!(rest, "");
+        let SqlStatement::Select(query) = &stmt else {
+            panic!("expected a SELECT");
+        };
+        let Query::Compound { left, operator, .. } = query.as_ref() else {
+            panic!("expected a compound query");
+        };
+        assert_eq!(*operator, SetOperator::Except);
+        assert!(matches!(
+            left.as_ref(),
+            Query::Compound {
+                operator: SetOperator::UnionAll,
+                left,
+                ..
+            } if matches!(left.as_ref(), Query::Select(x) if x.distinct)
+        ));
+        assert_eq!(stmt.to_string(), input);
+        assert_eq!(query.tables(), vec![&"x".into(), &"y".into(), &"z".into()]);
+    }
+
+    #[test]
+    fn should_parse_joins() {
+        let input = "SELECT u.name FROM users u INNER JOIN orders AS o ON o.user_id = u.id, \
+                     items CROSS JOIN (SELECT 1 AS n FROM x) AS t WHERE o.total > 1";
+        let (rest, stmt) = parse_select_query(input).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(
+            stmt.to_string(),
+            "SELECT u.name FROM users AS u JOIN orders AS o ON o.user_id = u.id CROSS JOIN \
+             items CROSS JOIN (SELECT 1 AS n FROM x) AS t WHERE o.total > 1"
+        );
+        let SqlStatement::Select(query) = &stmt else {
+            panic!("expected a SELECT");
+        };
+        let Query::Select(select) = query.as_ref() else {
+            panic!("expected a SELECT");
+        };
+        let names: Vec<_> = select
+            .from
+            .leaves()
+            .iter()
+            .map(|x| x.name().0.clone())
+            .collect();
+        assert_eq!(names, vec!["u", "o", "items", "t"]);
+        assert_eq!(select.from.conditions().len(), 1);
+        assert_eq!(
+            query.tables(),
+            vec![
+                &"users".into(),
+                &"orders".into(),
+                &"items".into(),
+                &"x".into()
+            ]
+        );
+        // a join needs its condition
+        assert!(SqlStatement::try_from("SELECT * FROM a JOIN b WHERE a.id = 1").is_err());
+    }
 }
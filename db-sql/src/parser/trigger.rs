@@ -0,0 +1,186 @@
+use crate::parser::{
+    Identifier, SqlStatement, delete::parse_delete_command, identifier,
+    insert::parse_insert_statement, keyword, select::parse_select_query,
+    update::parse_update_statement,
+};
+use nom::{
+    IResult, Parser,
+    branch::alt,
+    character::complete::{char, space0, space1},
+    combinator::{map, opt},
+    multi::many1,
+    sequence::{delimited, preceded, terminated},
+};
+use std::fmt::{Display, Formatter};
+
+/// Whether a trigger runs before or after the change of each row
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerTiming {
+    Before,
+    After,
+}
+impl Display for TriggerTiming {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TriggerTiming::Before => write!(f, "BEFORE"),
+            TriggerTiming::After => write!(f, "AFTER"),
+        }
+    }
+}
+
+/// The change of a row which fires a trigger
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerEvent {
+    Insert,
+    Update,
+    Delete,
+}
+impl Display for TriggerEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TriggerEvent::Insert => write!(f, "INSERT"),
+            TriggerEvent::Update => write!(f, "UPDATE"),
+            TriggerEvent::Delete => write!(f, "DELETE"),
+        }
+    }
+}
+
+/// `CREATE TRIGGER name BEFORE|AFTER INSERT|UPDATE|DELETE ON table FOR EACH ROW
+/// BEGIN stmt; ... END`, the statements of the body read the changed row as `NEW.col`
+/// and `OLD.col`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trigger {
+    pub name: Identifier,
+    pub timing: TriggerTiming,
+    pub event: TriggerEvent,
+    pub table: Identifier,
+    pub body: Vec<SqlStatement>,
+}
+impl Display for Trigger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CREATE TRIGGER {} {} {} ON {} FOR EACH ROW BEGIN",
+            self.name, self.timing, self.event, self.table
+        )?;
+        for stmt in &self.body {
+            write!(f, " {};", stmt)?;
+        }
+        write!(f, " END")
+    }
+}
+
+fn body_statement(input: &str) -> IResult<&str, SqlStatement> {
+    terminated(
+        alt((
+            parse_select_query,
+            parse_insert_statement,
+            parse_update_statement,
+            parse_delete_command,
+        )),
+        (space0, opt(char(';')), space0),
+    )
+    .parse(input)
+}
+pub fn parse_create_trigger(input: &str) -> IResult<&str, SqlStatement> {
+    map(
+        (
+            preceded(
+                (
+                    space0,
+                    keyword("CREATE"),
+                    space1,
+                    keyword("TRIGGER"),
+                    space1,
+                ),
+                identifier,
+            ),
+            preceded(
+                space1,
+                alt((
+                    map(keyword("BEFORE"), |_| TriggerTiming::Before),
+                    map(keyword("AFTER"), |_| TriggerTiming::After),
+                )),
+            ),
+            preceded(
+                space1,
+                alt((
+                    map(keyword("INSERT"), |_| TriggerEvent::Insert),
+                    map(keyword("UPDATE"), |_| TriggerEvent::Update),
+                    map(keyword("DELETE"), |_| TriggerEvent::Delete),
+                )),
+            ),
+            preceded((space1, keyword("ON"), space1), identifier),
+            opt((
+                space1,
+                keyword("FOR"),
+                space1,
+                keyword("EACH"),
+                space1,
+                keyword("ROW"),
+            )),
+            delimited(
+                (space1, keyword("BEGIN"), space1),
+                many1(body_statement),
+                (keyword("END"), space0, opt(char(';'))),
+            ),
+        ),
+        |(name, timing, event, table, _, body)| {
+            SqlStatement::CreateTrigger(Box::new(Trigger {
+                name,
+                timing,
+                event,
+                table,
+                body,
+            }))
+        },
+    )
+    .parse(input)
+}
+pub fn parse_drop_trigger(input: &str) -> IResult<&str, SqlStatement> {
+    map(
+        preceded(
+            (space0, keyword("DROP"), space1, keyword("TRIGGER"), space1),
+            identifier,
+        ),
+        |name| SqlStatement::DropTrigger { name },
+    )
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_trigger_statements() {
+        let input = "CREATE TRIGGER audit AFTER UPDATE ON users FOR EACH ROW BEGIN INSERT INTO log VALUES (OLD.name, NEW.name); DELETE cache WHERE id = NEW.id; END";
+        let (_, stmt) = parse_create_trigger(input).unwrap();
+        let SqlStatement::CreateTrigger(trigger) = &stmt else {
+            panic!("expected a CREATE TRIGGER");
+        };
+        assert_eq!(trigger.timing, TriggerTiming::After);
+        assert_eq!(trigger.event, TriggerEvent::Update);
+        assert_eq!(trigger.table, "users".into());
+        assert_eq!(trigger.body.len(), 2);
+        assert_eq!(
+            stmt.to_string(),
+            "CREATE TRIGGER audit AFTER UPDATE ON users FOR EACH ROW BEGIN INSERT INTO log VALUES (OLD.name, NEW.name); DELETE cache WHERE id = NEW.id; END"
+        );
+        assert_eq!(SqlStatement::try_from(stmt.to_string().as_str()), Ok(stmt));
+
+        assert!(parse_create_trigger("CREATE TRIGGER t BEFORE INSERT ON users BEGIN END").is_err());
+        assert!(
+            parse_create_trigger(
+                "CREATE TRIGGER t BEFORE INSERT ON users BEGIN CREATE TABLE x (id INT); END"
+            )
+            .is_err()
+        );
+        assert_eq!(
+            parse_drop_trigger("DROP TRIGGER audit").unwrap().1,
+            SqlStatement::DropTrigger {
+                name: "audit".into()
+            }
+        );
+    }
+}
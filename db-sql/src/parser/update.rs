@@ -0,0 +1,90 @@
+use crate::{
+    parser::condition::parse_where_clause,
+    parser::{Expr, Identifier, SqlStatement, expr::parse_expr, identifier, keyword},
+};
+use nom::{
+    IResult, Parser,
+    character::complete::{char, space0, space1, u64},
+    combinator::{map, opt},
+    multi::separated_list1,
+    sequence::{delimited, preceded, separated_pair},
+};
+
+fn assignment(input: &str) -> IResult<&str, (Identifier, Expr)> {
+    separated_pair(identifier, (space0, char('='), space0), parse_expr).parse(input)
+}
+pub fn parse_update_statement(input: &str) -> IResult<&str, SqlStatement> {
+    map(
+        (
+            preceded((space0, keyword("UPDATE"), space1), identifier),
+            preceded(
+                (space1, keyword("SET"), space1),
+                separated_list1(delimited(space0, char(','), space0), assignment),
+            ),
+            opt(preceded(
+                (space1, keyword("AT"), space1, keyword("ROW"), space1),
+                u64,
+            )),
+            parse_where_clause,
+        ),
+        |(table, assignments, row, condition)| SqlStatement::Update {
+            table,
+            assignments,
+            row: row.map(|x| x as usize),
+            condition,
+        },
+    )
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{ArithmeticOperator, Condition, Operator, SqlValue};
+
+    #[test]
+    fn should_parse_update_statement() {
+        let input = "UPDATE users SET score = score + 1, name = 'x' WHERE id = 1";
+        let (_, stmt) = parse_update_statement(input).unwrap();
+        assert_eq!(
+            stmt,
+            SqlStatement::Update {
+                table: "users".into(),
+                assignments: vec![
+                    (
+                        "score".into(),
+                        Expr::Binary {
+                            left: Box::new("score".into()),
+                            operator: ArithmeticOperator::Add,
+                            right: Box::new(SqlValue::Integer(1).into()),
+                        }
+                    ),
+                    ("name".into(), SqlValue::String("x".into()).into()),
+                ],
+                row: None,
+                condition: Some(Condition::Comparison {
+                    left: "id".into(),
+                    operator: Operator::Equal,
+                    right: SqlValue::Integer(1).into(),
+                }),
+            }
+        );
+        assert_eq!(stmt.to_string(), input);
+    }
+
+    #[test]
+    fn should_parse_update_of_a_row() {
+        let input = "UPDATE users SET name = 'x' AT ROW 3";
+        let (_, stmt) = parse_update_statement(input).unwrap();
+        assert_eq!(
+            stmt,
+            SqlStatement::Update {
+                table: "users".into(),
+                assignments: vec![("name".into(), SqlValue::String("x".into()).into())],
+                row: Some(3),
+                condition: None,
+            }
+        );
+        assert_eq!(stmt.to_string(), input);
+    }
+}
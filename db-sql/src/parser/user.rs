@@ -0,0 +1,184 @@
+use crate::parser::{SqlStatement, SqlValue, identifier, keyword, quoted};
+use nom::{
+    IResult, Parser,
+    branch::alt,
+    character::complete::{char, space0, space1},
+    combinator::{map, opt, value},
+    multi::separated_list1,
+    sequence::preceded,
+};
+use std::fmt::{Display, Formatter};
+
+/// What a user may do with a table or view
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Privilege {
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
+impl Privilege {
+    pub const ALL: [Privilege; 4] = [
+        Privilege::Select,
+        Privilege::Insert,
+        Privilege::Update,
+        Privilege::Delete,
+    ];
+}
+impl Display for Privilege {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Privilege::Select => write!(f, "SELECT"),
+            Privilege::Insert => write!(f, "INSERT"),
+            Privilege::Update => write!(f, "UPDATE"),
+            Privilege::Delete => write!(f, "DELETE"),
+        }
+    }
+}
+
+/// The password of `CREATE USER`, only its hash is stored and recorded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Password {
+    /// `PASSWORD 'secret'`
+    Plain(String),
+    /// `PASSWORD HASH '...'`, as written by a dump
+    Hashed(String),
+}
+impl Display for Password {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let quote = |x: &String| SqlValue::String(x.clone()).to_literal();
+        match self {
+            Password::Plain(x) => write!(f, "PASSWORD {}", quote(x)),
+            Password::Hashed(x) => write!(f, "PASSWORD HASH {}", quote(x)),
+        }
+    }
+}
+
+pub fn parse_create_user(input: &str) -> IResult<&str, SqlStatement> {
+    map(
+        (
+            preceded(
+                (space0, keyword("CREATE"), space1, keyword("USER"), space1),
+                identifier,
+            ),
+            preceded(
+                (
+                    space1,
+                    opt((keyword("WITH"), space1)),
+                    keyword("PASSWORD"),
+                    space1,
+                ),
+                alt((
+                    map(
+                        preceded((keyword("HASH"), space1), quoted),
+                        Password::Hashed,
+                    ),
+                    map(quoted, Password::Plain),
+                )),
+            ),
+        ),
+        |(name, password)| SqlStatement::CreateUser { name, password },
+    )
+    .parse(input)
+}
+pub fn parse_drop_user(input: &str) -> IResult<&str, SqlStatement> {
+    map(
+        preceded(
+            (space0, keyword("DROP"), space1, keyword("USER"), space1),
+            identifier,
+        ),
+        |name| SqlStatement::DropUser { name },
+    )
+    .parse(input)
+}
+/// `SELECT, INSERT` or `ALL [PRIVILEGES]`
+fn privileges(input: &str) -> IResult<&str, Vec<Privilege>> {
+    let privilege = alt((
+        value(Privilege::Select, keyword("SELECT")),
+        value(Privilege::Insert, keyword("INSERT")),
+        value(Privilege::Update, keyword("UPDATE")),
+        value(Privilege::Delete, keyword("DELETE")),
+    ));
+    alt((
+        map(
+            (keyword("ALL"), opt((space1, keyword("PRIVILEGES")))),
+            |_| Privilege::ALL.to_vec(),
+        ),
+        separated_list1((space0, char(','), space0), privilege),
+    ))
+    .parse(input)
+}
+/// `GRANT privileges ON table TO user` and `REVOKE privileges ON table FROM user`
+pub fn parse_grant_statement(input: &str) -> IResult<&str, SqlStatement> {
+    let on = |x| preceded((space1, keyword("ON"), space1), identifier).parse(x);
+    alt((
+        map(
+            (
+                preceded((space0, keyword("GRANT"), space1), privileges),
+                on,
+                preceded((space1, keyword("TO"), space1), identifier),
+            ),
+            |(privileges, table, user)| SqlStatement::Grant {
+                privileges,
+                table,
+                user,
+            },
+        ),
+        map(
+            (
+                preceded((space0, keyword("REVOKE"), space1), privileges),
+                on,
+                preceded((space1, keyword("FROM"), space1), identifier),
+            ),
+            |(privileges, table, user)| SqlStatement::Revoke {
+                privileges,
+                table,
+                user,
+            },
+        ),
+    ))
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_user_statements() {
+        let (_, stmt) = parse_create_user("CREATE USER alice WITH PASSWORD 'it''s'").unwrap();
+        assert_eq!(
+            stmt,
+            SqlStatement::CreateUser {
+                name: "alice".into(),
+                password: Password::Plain("it's".into())
+            }
+        );
+        assert_eq!(stmt.to_string(), "CREATE USER alice PASSWORD 'it''s'");
+        let input = "CREATE USER bob PASSWORD HASH 'ab$cd'";
+        assert_eq!(parse_create_user(input).unwrap().1.to_string(), input);
+
+        let (_, stmt) = parse_grant_statement("GRANT select,INSERT ON users TO alice").unwrap();
+        assert_eq!(
+            stmt,
+            SqlStatement::Grant {
+                privileges: vec![Privilege::Select, Privilege::Insert],
+                table: "users".into(),
+                user: "alice".into()
+            }
+        );
+        assert_eq!(stmt.to_string(), "GRANT SELECT, INSERT ON users TO alice");
+        let (_, stmt) = parse_grant_statement("REVOKE ALL PRIVILEGES ON users FROM alice").unwrap();
+        assert_eq!(
+            stmt.to_string(),
+            "REVOKE SELECT, INSERT, UPDATE, DELETE ON users FROM alice"
+        );
+        assert!(parse_grant_statement("GRANT DROP ON users TO alice").is_err());
+        assert_eq!(
+            parse_drop_user("DROP USER alice").unwrap().1,
+            SqlStatement::DropUser {
+                name: "alice".into()
+            }
+        );
+    }
+}
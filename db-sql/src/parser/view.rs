@@ -0,0 +1,63 @@
+use crate::parser::{SqlStatement, identifier, keyword, select::query};
+use nom::{
+    IResult, Parser,
+    character::complete::{char, space0, space1},
+    combinator::{map, opt},
+    sequence::{preceded, terminated},
+};
+
+pub fn parse_create_view(input: &str) -> IResult<&str, SqlStatement> {
+    map(
+        terminated(
+            (
+                preceded(
+                    (space0, keyword("CREATE"), space1, keyword("VIEW"), space1),
+                    identifier,
+                ),
+                preceded((space1, keyword("AS"), space1), query),
+            ),
+            (space0, opt(char(';'))),
+        ),
+        |(name, query)| SqlStatement::CreateView {
+            name,
+            query: Box::new(query),
+        },
+    )
+    .parse(input)
+}
+pub fn parse_drop_view(input: &str) -> IResult<&str, SqlStatement> {
+    map(
+        preceded(
+            (space0, keyword("DROP"), space1, keyword("VIEW"), space1),
+            identifier,
+        ),
+        |name| SqlStatement::DropView { name },
+    )
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_view_statements() {
+        let input = "CREATE VIEW active AS SELECT id, name FROM users WHERE active = true";
+        let (_, stmt) = parse_create_view(input).unwrap();
+        let SqlStatement::CreateView { name, query } = &stmt else {
+            panic!("expected a CREATE VIEW");
+        };
+        assert_eq!(name, &"active".into());
+        assert_eq!(query.tables(), vec![&"users".into()]);
+        assert_eq!(stmt.to_string(), input);
+
+        let (_, stmt) = parse_drop_view("DROP VIEW active").unwrap();
+        assert_eq!(
+            stmt,
+            SqlStatement::DropView {
+                name: "active".into()
+            }
+        );
+        assert_eq!(stmt.to_string(), "DROP VIEW active");
+    }
+}
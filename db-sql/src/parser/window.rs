@@ -0,0 +1,174 @@
+use crate::parser::{Expr, Identifier, expr::parse_expr, keyword};
+use nom::{
+    IResult, Parser,
+    branch::alt,
+    character::complete::{char, space0, space1},
+    combinator::{map, opt},
+    multi::separated_list1,
+    sequence::{delimited, preceded},
+};
+use std::fmt::{Display, Formatter};
+
+/// A key rows are sorted by, ascending unless `DESC`
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderBy {
+    pub expr: Expr,
+    pub descending: bool,
+}
+impl Display for OrderBy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expr)?;
+        if self.descending {
+            write!(f, " DESC")?;
+        }
+        Ok(())
+    }
+}
+
+/// `function(args) OVER (PARTITION BY ... ORDER BY ...)`, computed for each row
+/// over the rows of its partition
+#[derive(Clone, Debug, PartialEq)]
+pub struct Window {
+    pub function: Identifier,
+    /// the arguments of the function, none for `COUNT(*)`
+    pub args: Vec<Expr>,
+    pub partition_by: Vec<Expr>,
+    pub order_by: Vec<OrderBy>,
+}
+impl Window {
+    /// The expressions of the arguments and of the clauses
+    pub fn exprs(&self) -> Vec<&Expr> {
+        let keys = self.order_by.iter().map(|x| &x.expr);
+        self.args
+            .iter()
+            .chain(&self.partition_by)
+            .chain(keys)
+            .collect()
+    }
+    pub(crate) fn visit_exprs(&mut self, f: &mut impl FnMut(&mut Expr)) {
+        let keys = self.order_by.iter_mut().map(|x| &mut x.expr);
+        for expr in self
+            .args
+            .iter_mut()
+            .chain(&mut self.partition_by)
+            .chain(keys)
+        {
+            expr.visit_exprs(f);
+        }
+    }
+}
+impl Display for Window {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let join = |xs: Vec<String>| xs.join(", ");
+        let args = if self.args.is_empty() && self.function.0.eq_ignore_ascii_case("COUNT") {
+            "*".to_string()
+        } else {
+            join(self.args.iter().map(|x| x.to_string()).collect())
+        };
+        write!(f, "{}({}) OVER (", self.function, args)?;
+        if !self.partition_by.is_empty() {
+            let exprs = join(self.partition_by.iter().map(|x| x.to_string()).collect());
+            write!(f, "PARTITION BY {}", exprs)?;
+            if !self.order_by.is_empty() {
+                write!(f, " ")?;
+            }
+        }
+        if !self.order_by.is_empty() {
+            let keys = join(self.order_by.iter().map(|x| x.to_string()).collect());
+            write!(f, "ORDER BY {}", keys)?;
+        }
+        write!(f, ")")
+    }
+}
+
+fn order_by(input: &str) -> IResult<&str, OrderBy> {
+    map(
+        (
+            parse_expr,
+            opt(preceded(
+                space1,
+                alt((
+                    map(keyword("ASC"), |_| false),
+                    map(keyword("DESC"), |_| true),
+                )),
+            )),
+        ),
+        |(expr, descending)| OrderBy {
+            expr,
+            descending: descending.unwrap_or(false),
+        },
+    )
+    .parse(input)
+}
+/// `OVER (PARTITION BY ... ORDER BY ...)`, the keys of the partitions and those the
+/// rows are sorted by in each
+pub(crate) fn over(input: &str) -> IResult<&str, (Vec<Expr>, Vec<OrderBy>)> {
+    let comma = || delimited(space0, char(','), space0);
+    map(
+        delimited(
+            (keyword("OVER"), space0, char('('), space0),
+            (
+                opt(preceded(
+                    (keyword("PARTITION"), space1, keyword("BY"), space1),
+                    separated_list1(comma(), parse_expr),
+                )),
+                opt(preceded(
+                    (space0, keyword("ORDER"), space1, keyword("BY"), space1),
+                    separated_list1(comma(), order_by),
+                )),
+            ),
+            (space0, char(')')),
+        ),
+        |(partition_by, order_by)| {
+            (
+                partition_by.unwrap_or_default(),
+                order_by.unwrap_or_default(),
+            )
+        },
+    )
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SqlValue;
+    use nom::combinator::map_opt;
+
+    fn window(input: &str) -> IResult<&str, Window> {
+        let window = |x| match x {
+            Expr::Window(w) => Some(*w),
+            _ => None,
+        };
+        map_opt(parse_expr, window).parse(input)
+    }
+
+    #[test]
+    fn should_parse_windows() {
+        let input = "LAG(total, 2) OVER (PARTITION BY user_id, day ORDER BY total DESC, id) AS x";
+        let (rest, w) = window(input).unwrap();
+        assert_eq!(rest, " AS x");
+        assert_eq!(w.function, "LAG".into());
+        assert_eq!(w.args, vec!["total".into(), SqlValue::Integer(2).into()]);
+        assert_eq!(w.partition_by, vec!["user_id".into(), "day".into()]);
+        assert_eq!(
+            w.order_by,
+            vec![
+                OrderBy {
+                    expr: "total".into(),
+                    descending: true
+                },
+                OrderBy {
+                    expr: "id".into(),
+                    descending: false
+                }
+            ]
+        );
+        assert_eq!(w.to_string(), &input[..input.len() - 5]);
+
+        for input in ["COUNT(*) OVER ()", "ROW_NUMBER() OVER (ORDER BY id)"] {
+            assert_eq!(window(input).unwrap().1.to_string(), input);
+        }
+        assert!(window("ROW_NUMBER()").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+use crate::parser::{Expr, Identifier, Query, identifier, keyword, select::query};
+use nom::{
+    IResult, Parser,
+    character::complete::{char, space0, space1},
+    combinator::{map, opt},
+    multi::separated_list1,
+    sequence::{delimited, preceded},
+};
+use std::fmt::{Display, Formatter};
+
+/// `name [(columns)] AS (query)`, a named query of a `WITH` clause
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cte {
+    pub name: Identifier,
+    /// the names of the columns of the query, its own names when empty
+    pub columns: Vec<Identifier>,
+    pub query: Query,
+}
+impl Cte {
+    pub(crate) fn visit_exprs(&mut self, f: &mut impl FnMut(&mut Expr)) {
+        self.query.visit_exprs(f);
+    }
+}
+impl Display for Cte {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.columns.is_empty() {
+            let columns: Vec<_> = self.columns.iter().map(|x| x.to_string()).collect();
+            write!(f, "({})", columns.join(", "))?;
+        }
+        write!(f, " AS ({})", self.query)
+    }
+}
+
+fn cte(input: &str) -> IResult<&str, Cte> {
+    map(
+        (
+            identifier,
+            opt(delimited(
+                (space0, char('('), space0),
+                separated_list1(delimited(space0, char(','), space0), identifier),
+                (space0, char(')')),
+            )),
+            preceded(
+                (space1, keyword("AS"), space0),
+                delimited((char('('), space0), query, (space0, char(')'))),
+            ),
+        ),
+        |(name, columns, query)| Cte {
+            name,
+            columns: columns.unwrap_or_default(),
+            query,
+        },
+    )
+    .parse(input)
+}
+/// `WITH [RECURSIVE] cte, ...` before a query, whether it is recursive and the CTEs
+pub(crate) fn with_clause(input: &str) -> IResult<&str, (bool, Vec<Cte>)> {
+    map(
+        (
+            preceded(
+                (space0, keyword("WITH"), space1),
+                opt((keyword("RECURSIVE"), space1)),
+            ),
+            separated_list1(delimited(space0, char(','), space0), cte),
+        ),
+        |(recursive, ctes)| (recursive.is_some(), ctes),
+    )
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_with_clause() {
+        let input = "WITH RECURSIVE tree(id, depth) AS (SELECT id, 0 FROM nodes WHERE parent = 0 UNION ALL SELECT id, 1 FROM nodes WHERE parent IN (SELECT id FROM tree)), leaves AS (SELECT id FROM tree) SELECT";
+        let (rest, (recursive, ctes)) = with_clause(input).unwrap();
+        assert_eq!(rest, " SELECT");
+        assert!(recursive);
+        assert_eq!(ctes.len(), 2);
+        assert_eq!(ctes[0].columns, vec!["id".into(), "depth".into()]);
+        assert_eq!(
+            ctes[0].to_string(),
+            "tree(id, depth) AS (SELECT id, 0 FROM nodes WHERE parent = 0 UNION ALL SELECT id, 1 FROM nodes WHERE parent IN (SELECT id FROM tree))"
+        );
+        assert_eq!(ctes[1].to_string(), "leaves AS (SELECT id FROM tree)");
+    }
+}
@@ -0,0 +1,110 @@
+use db_sql::format::OutputFormat;
+use std::path::PathBuf;
+
+pub const HELP: &str = "\
+.dump [FILE]        Write the database as SQL statements to FILE or the output
+.exit               Exit this program
+.export TABLE FILE  Write the rows of TABLE to the CSV FILE, with a header
+.help               Show this message
+.import FILE TABLE  Insert the rows of the CSV FILE, with a header, into TABLE
+.login USER         Log in as USER, whose password is asked for
+.mode MODE          Set the output mode: table, csv, json or markdown
+.open PATH          Close the current database and open PATH
+.read FILE          Execute the SQL statements in FILE
+.restore FILE       Replace the database with an in-memory one loaded from FILE
+.schema [TABLE]     Show the CREATE statements of all or the given table or view
+.tables             List the tables and views
+.timer on|off       Turn the timer of the statements on or off";
+
+/// The dot-commands of the REPL, which are not SQL and take the rest of the line
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetaCommand {
+    Dump(Option<PathBuf>),
+    Exit,
+    Export { table: String, path: PathBuf },
+    Help,
+    Import { path: PathBuf, table: String },
+    Login(String),
+    Mode(OutputFormat),
+    Open(PathBuf),
+    Read(PathBuf),
+    Restore(PathBuf),
+    Schema(Option<String>),
+    Tables,
+    Timer(bool),
+}
+
+impl TryFrom<&str> for MetaCommand {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut parts = value.split_whitespace();
+        let cmd = parts.next().unwrap_or_default();
+        let args: Vec<_> = parts.collect();
+
+        Ok(match (cmd, args.as_slice()) {
+            (".dump", []) => MetaCommand::Dump(None),
+            (".dump", [path]) => MetaCommand::Dump(Some(path.into())),
+            (".exit" | ".quit" | "exit" | "quit", []) => MetaCommand::Exit,
+            (".export", [table, path]) => MetaCommand::Export {
+                table: table.to_string(),
+                path: path.into(),
+            },
+            (".help", []) => MetaCommand::Help,
+            (".import", [path, table]) => MetaCommand::Import {
+                path: path.into(),
+                table: table.to_string(),
+            },
+            (".login", [user]) => MetaCommand::Login(user.to_string()),
+            (".mode", [mode]) => MetaCommand::Mode(OutputFormat::try_from(*mode)?),
+            (".open", [path]) => MetaCommand::Open(path.into()),
+            (".read", [path]) => MetaCommand::Read(path.into()),
+            (".restore", [path]) => MetaCommand::Restore(path.into()),
+            (".schema", []) => MetaCommand::Schema(None),
+            (".schema", [table]) => MetaCommand::Schema(Some(table.to_string())),
+            (".tables", []) => MetaCommand::Tables,
+            (".timer", [flag]) => match flag.to_lowercase().as_str() {
+                "on" => MetaCommand::Timer(true),
+                "off" => MetaCommand::Timer(false),
+                _ => return Err(format!("expected on|off but got {flag}")),
+            },
+            _ => return Err(format!("unknown command or invalid arguments: {value}")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_meta_commands() {
+        assert_eq!(MetaCommand::try_from(".tables"), Ok(MetaCommand::Tables));
+        assert_eq!(
+            MetaCommand::try_from(".schema users"),
+            Ok(MetaCommand::Schema(Some("users".into())))
+        );
+        assert_eq!(
+            MetaCommand::try_from(".mode csv"),
+            Ok(MetaCommand::Mode(OutputFormat::Csv))
+        );
+        assert_eq!(
+            MetaCommand::try_from(".timer ON"),
+            Ok(MetaCommand::Timer(true))
+        );
+        assert_eq!(
+            MetaCommand::try_from(".import users.csv users"),
+            Ok(MetaCommand::Import {
+                path: "users.csv".into(),
+                table: "users".into()
+            })
+        );
+        assert_eq!(
+            MetaCommand::try_from(".login alice"),
+            Ok(MetaCommand::Login("alice".into()))
+        );
+        assert!(MetaCommand::try_from(".login").is_err());
+        assert!(MetaCommand::try_from(".read").is_err());
+        assert!(MetaCommand::try_from(".unknown").is_err());
+    }
+}
@@ -0,0 +1,282 @@
+mod command;
+
+pub use command::*;
+
+use db_sql::{
+    errors::DbResult,
+    exec::ExecutionContext,
+    exec::{ExecutionResult, TimedResult},
+    format::OutputFormat,
+    parser::{CopyDirection, SqlStatement, split_statements},
+};
+use rustyline::{DefaultEditor, error::ReadlineError};
+use std::{fmt::Display, path::PathBuf};
+
+const HISTORY_FILE: &str = ".db_sql_history";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Flow {
+    Continue,
+    Exit,
+}
+
+pub struct Repl {
+    ctx: ExecutionContext,
+    format: OutputFormat,
+    timer: bool,
+    /// how many statements and commands failed
+    errors: usize,
+}
+
+impl Repl {
+    pub fn with_context(ctx: ExecutionContext, format: OutputFormat) -> Self {
+        Self {
+            ctx,
+            format,
+            timer: false,
+            errors: 0,
+        }
+    }
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+
+    /// Reads the statements from the terminal until `exit` or CTRL + D.
+    pub fn run(&mut self) -> DbResult {
+        let mut editor = DefaultEditor::new()?;
+        let history = history_path();
+        if let Some(path) = &history {
+            // there is no history on the first run
+            let _ = editor.load_history(path);
+        }
+
+        let mut buffer = String::new();
+        loop {
+            let prompt = if buffer.is_empty() { "> " } else { "..> " };
+            match editor.readline(prompt) {
+                Ok(line) => {
+                    if !line.trim().is_empty() {
+                        editor.add_history_entry(line.as_str())?;
+                    }
+                    if self.feed(&mut buffer, &line) == Flow::Exit {
+                        println!("exiting ...");
+                        break;
+                    }
+                }
+                Err(ReadlineError::Interrupted) => {
+                    // CTRL + C
+                    buffer.clear();
+                    continue;
+                }
+                Err(ReadlineError::Eof) => {
+                    // CTRL + D
+                    break;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        if let Some(path) = &history {
+            editor.save_history(path)?;
+        }
+        Ok(())
+    }
+
+    /// Executes a script file, including its dot-commands, as if it was typed in.
+    pub fn run_file(&mut self, path: impl Into<PathBuf>) -> DbResult {
+        let script = std::fs::read_to_string(path.into())?;
+        self.run_script(&script);
+        Ok(())
+    }
+    /// Same as `run_file` for the text of a script
+    pub fn run_script(&mut self, script: &str) {
+        let mut buffer = String::new();
+        for line in script.lines() {
+            if self.feed(&mut buffer, line) == Flow::Exit {
+                return;
+            }
+        }
+        // the last statement may miss its terminating semicolon
+        if !buffer.trim().is_empty() {
+            self.execute(buffer.trim());
+        }
+    }
+
+    fn feed(&mut self, buffer: &mut String, line: &str) -> Flow {
+        let trimmed = line.trim();
+        if buffer.is_empty() {
+            if trimmed.is_empty() {
+                return Flow::Continue;
+            }
+            if trimmed.starts_with('.') || trimmed == "exit" || trimmed == "quit" {
+                return match MetaCommand::try_from(trimmed) {
+                    Ok(cmd) => self.meta(cmd),
+                    Err(e) => {
+                        self.fail(e);
+                        Flow::Continue
+                    }
+                };
+            }
+        }
+
+        buffer.push_str(line);
+        buffer.push('\n');
+        let (statements, rest) = split_statements(buffer);
+        *buffer = rest;
+        for stmt in statements {
+            self.execute(&stmt);
+        }
+        Flow::Continue
+    }
+
+    /// Logs in as the user, the later statements run with its privileges
+    pub fn login(&mut self, user: &str, password: &str) -> DbResult {
+        self.ctx.authenticate(user, password)?;
+        Ok(())
+    }
+
+    fn meta(&mut self, cmd: MetaCommand) -> Flow {
+        // these read or replace the whole database, past the privileges of a user
+        if let MetaCommand::Dump(_) | MetaCommand::Open(_) | MetaCommand::Restore(_) = &cmd
+            && let Some(user) = self.ctx.current_user()
+        {
+            self.fail(format!("{user} is not the owner of the database"));
+            return Flow::Continue;
+        }
+        match cmd {
+            MetaCommand::Exit => return Flow::Exit,
+            MetaCommand::Help => println!("{HELP}"),
+            MetaCommand::Mode(format) => self.format = format,
+            MetaCommand::Timer(on) => self.timer = on,
+            MetaCommand::Login(user) => {
+                let login = rpassword::prompt_password("Password: ")
+                    .map_err(|e| e.into())
+                    .and_then(|x| self.login(&user, &x));
+                if let Err(e) = login {
+                    self.fail(e);
+                }
+            }
+            MetaCommand::Tables => {
+                let mut names = self.ctx.table_names();
+                names.extend(self.ctx.view_names());
+                names.sort_by(|a, b| a.0.cmp(&b.0));
+                for name in names {
+                    println!("{name}");
+                }
+            }
+            MetaCommand::Schema(table) => {
+                let names = match table {
+                    Some(x) => vec![x],
+                    None => {
+                        let views = self.ctx.view_names();
+                        let tables = self.ctx.table_names();
+                        tables.iter().chain(&views).map(|x| x.0.clone()).collect()
+                    }
+                };
+                for name in names {
+                    let create = if let Some(tbl) = self.ctx.table(&name) {
+                        SqlStatement::Create {
+                            table: name.as_str().into(),
+                            columns: tbl.columns().clone(),
+                            storage: tbl.storage(),
+                        }
+                    } else if let Some(query) = self.ctx.view(&name) {
+                        SqlStatement::CreateView {
+                            name: name.as_str().into(),
+                            query: Box::new(query.clone()),
+                        }
+                    } else {
+                        self.fail(format!("no such table: {name}"));
+                        continue;
+                    };
+                    println!("{create};");
+                    if let Some(tbl) = self.ctx.table(&name) {
+                        for index in tbl.index_statements() {
+                            println!("{index};");
+                        }
+                    }
+                }
+            }
+            MetaCommand::Dump(None) => print!("{}", self.ctx.dump()),
+            MetaCommand::Dump(Some(path)) => {
+                if let Err(e) = std::fs::write(&path, self.ctx.dump()) {
+                    self.fail(format!("cannot write {}: {e}", path.display()));
+                }
+            }
+            MetaCommand::Restore(path) => {
+                match std::fs::read_to_string(&path)
+                    .map_err(|e| e.into())
+                    .and_then(|x| ExecutionContext::restore(&x))
+                {
+                    Ok(ctx) => self.ctx = ctx,
+                    Err(e) => self.fail(format!("cannot restore {}: {e}", path.display())),
+                }
+            }
+            MetaCommand::Import { path, table } => {
+                let stmt = SqlStatement::Copy {
+                    table: table.as_str().into(),
+                    direction: CopyDirection::From,
+                    path: path.display().to_string(),
+                    header: true,
+                };
+                self.execute(&stmt.to_string());
+            }
+            MetaCommand::Export { table, path } => {
+                let stmt = SqlStatement::Copy {
+                    table: table.as_str().into(),
+                    direction: CopyDirection::To,
+                    path: path.display().to_string(),
+                    header: true,
+                };
+                self.execute(&stmt.to_string());
+            }
+            MetaCommand::Read(path) => {
+                if let Err(e) = self.run_file(&path) {
+                    self.fail(format!("cannot read {}: {e}", path.display()));
+                }
+            }
+            MetaCommand::Open(path) => {
+                // a read-only session stays read-only
+                let ctx = if self.ctx.is_readonly() {
+                    ExecutionContext::open_readonly(&path)
+                } else {
+                    ExecutionContext::open(&path)
+                };
+                match ctx {
+                    Ok(ctx) => self.ctx = ctx,
+                    Err(e) => self.fail(format!("cannot open {}: {e}", path.display())),
+                }
+            }
+        }
+        Flow::Continue
+    }
+
+    fn execute(&mut self, stmt: &str) {
+        let TimedResult { result, elapsed } = self.ctx.run_timed(stmt);
+        match result {
+            Ok(ExecutionResult::Select(rs)) => {
+                println!("{}", rs.format(self.format));
+                // the other formats are read by programs, which count the rows themselves
+                if self.format == OutputFormat::Table {
+                    let n = rs.rows.len();
+                    println!("({} row{})", n, if n == 1 { "" } else { "s" });
+                }
+            }
+            Ok(res) => println!("{res}"),
+            Err(e) => self.fail(e.to_string()),
+        }
+        if self.timer {
+            println!("Run Time: {elapsed:?}");
+        }
+    }
+    fn fail(&mut self, message: impl Display) {
+        eprintln!("Error: {message}");
+        self.errors += 1;
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|x| PathBuf::from(x).join(HISTORY_FILE))
+}
@@ -0,0 +1,352 @@
+use crate::{
+    errors::ExecutionError,
+    types::{Date, Decimal, Json, SqlValue, Timestamp, Uuid, parse_hex},
+};
+use std::fmt::{Display, Formatter};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SqlDataType {
+    /// a 32-bit integer
+    Integer,
+    /// a 64-bit integer
+    BigInt,
+    String,
+    Boolean,
+    Uuid,
+    Text,
+    Float,
+    /// an exact number of at most `precision` digits, `scale` of them fractional
+    Decimal {
+        precision: u32,
+        scale: u32,
+    },
+    Date,
+    Timestamp,
+    Blob,
+    /// a string of at most the given number of characters
+    Varchar(u32),
+    /// a document validated on insert
+    Json,
+}
+impl TryFrom<&str> for SqlDataType {
+    type Error = nom::Err<&'static str>;
+
+    /// Reads the names of the types without parameters
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(match value.to_uppercase().as_str() {
+            "INT" | "INTEGER" => SqlDataType::Integer,
+            "BIGINT" => SqlDataType::BigInt,
+            "STRING" => SqlDataType::String,
+            "UUID" => SqlDataType::Uuid,
+            "TEXT" => SqlDataType::Text,
+            "BIT" => SqlDataType::Boolean,
+            "FLOAT" => SqlDataType::Float,
+            "DECIMAL" | "NUMERIC" => SqlDataType::DEFAULT_DECIMAL,
+            "DATE" => SqlDataType::Date,
+            "TIMESTAMP" => SqlDataType::Timestamp,
+            "BLOB" => SqlDataType::Blob,
+            "JSON" => SqlDataType::Json,
+            _ => return Err(Self::Error::Failure("Invalid DataType")),
+        })
+    }
+}
+impl SqlDataType {
+    /// `DECIMAL` without a precision
+    pub const DEFAULT_DECIMAL: SqlDataType = SqlDataType::Decimal {
+        precision: 18,
+        scale: 0,
+    };
+
+    /// Whether the values of the two types can be equal: numbers compare with
+    /// numbers, strings with strings and dates with timestamps, other types only
+    /// with themselves.
+    pub fn is_comparable(&self, other: &SqlDataType) -> bool {
+        use SqlDataType::*;
+
+        let family = |x: &SqlDataType| match x {
+            Integer | BigInt | Float | Decimal { .. } => Integer,
+            String | Text | Varchar(_) => String,
+            Date | Timestamp => Date,
+            x => *x,
+        };
+        family(self) == family(other)
+    }
+    /// Checks the value can be stored in a column of this type. Numbers are widened,
+    /// decimals rounded to the scale and strings read as dates, UUIDs or JSON, nil fits
+    /// all.
+    pub fn coerce(&self, value: SqlValue) -> Result<SqlValue, ExecutionError> {
+        let out_of_range = |x: &SqlValue| ExecutionError::ValueOutOfRange {
+            data_type: self.to_string(),
+            value: x.to_literal(),
+        };
+        Ok(match (self, value) {
+            (_, SqlValue::Nil) => SqlValue::Nil,
+            (SqlDataType::Integer, x @ SqlValue::Integer(i)) => {
+                if i32::try_from(i).is_err() {
+                    return Err(out_of_range(&x));
+                }
+                x
+            }
+            (SqlDataType::BigInt, x @ SqlValue::Integer(_)) => x,
+            (SqlDataType::Float, SqlValue::Integer(x)) => SqlValue::Float(x as f64),
+            (SqlDataType::Float, SqlValue::Decimal(x)) => SqlValue::Float(x.to_f64()),
+            // NaN and infinities have no literal to be restored from
+            (SqlDataType::Float, x @ SqlValue::Float(f)) if !f.is_finite() => {
+                return Err(out_of_range(&x));
+            }
+            (SqlDataType::Float, x @ SqlValue::Float(_)) => x,
+            (SqlDataType::Decimal { precision, scale }, x) if x.is_numeric() => {
+                let decimal = match &x {
+                    SqlValue::Integer(i) => Some(Decimal::from(*i)),
+                    SqlValue::Float(f) => Decimal::from_f64(*f),
+                    SqlValue::Decimal(d) => Some(*d),
+                    _ => None,
+                };
+                match decimal.and_then(|d| d.rescale(*scale)) {
+                    Some(d) if d.precision() <= *precision => SqlValue::Decimal(d),
+                    _ => return Err(out_of_range(&x)),
+                }
+            }
+            (SqlDataType::Boolean, x @ SqlValue::Boolean(_)) => x,
+            (SqlDataType::String | SqlDataType::Text, x @ SqlValue::String(_)) => x,
+            (SqlDataType::Varchar(n), SqlValue::String(s)) => {
+                if s.chars().count() > *n as usize {
+                    return Err(out_of_range(&SqlValue::String(s)));
+                }
+                SqlValue::String(s)
+            }
+            // the dates past year 9999 have no literal to be restored from
+            (SqlDataType::Date, x @ SqlValue::Date(d)) if !d.is_in_range() => {
+                return Err(out_of_range(&x));
+            }
+            (SqlDataType::Timestamp, x @ SqlValue::Timestamp(t)) if !t.is_in_range() => {
+                return Err(out_of_range(&x));
+            }
+            (SqlDataType::Timestamp, x @ SqlValue::Date(d)) if !d.is_in_range() => {
+                return Err(out_of_range(&x));
+            }
+            (SqlDataType::Date, x @ SqlValue::Date(_)) => x,
+            (SqlDataType::Timestamp, x @ SqlValue::Timestamp(_)) => x,
+            (SqlDataType::Timestamp, SqlValue::Date(x)) => SqlValue::Timestamp(x.to_timestamp()),
+            (SqlDataType::Uuid, x @ SqlValue::Uuid(_)) => x,
+            (SqlDataType::Json, x @ SqlValue::Json(_)) => x,
+            (
+                SqlDataType::Date | SqlDataType::Timestamp | SqlDataType::Uuid | SqlDataType::Json,
+                SqlValue::String(s),
+            ) => self
+                .parse_value(Some(&s))
+                .map_err(|_| ExecutionError::InvalidValue {
+                    data_type: self.to_string(),
+                    value: s,
+                })?,
+            (SqlDataType::Blob, x @ SqlValue::Blob(_)) => x,
+            (_, x) => {
+                return Err(ExecutionError::TypeMismatch {
+                    expected: self.to_string(),
+                    found: x.type_name().to_string(),
+                });
+            }
+        })
+    }
+    /// Converts the value to this type, as `CAST(value AS type)` does. Unlike
+    /// [`SqlDataType::coerce`] any value can be cast to a string, fractions are
+    /// truncated to integers and `VARCHAR(n)` cuts the strings to `n` characters.
+    pub fn cast(&self, value: SqlValue) -> Result<SqlValue, ExecutionError> {
+        let out_of_range = |x: &SqlValue| ExecutionError::ValueOutOfRange {
+            data_type: self.to_string(),
+            value: x.to_literal(),
+        };
+        let value = match (self, value) {
+            (_, SqlValue::Nil) => SqlValue::Nil,
+            (SqlDataType::Integer | SqlDataType::BigInt, x @ SqlValue::Float(f)) => {
+                // the float to integer `as` conversion saturates instead of failing
+                if !f.is_finite() || f.trunc() < i64::MIN as f64 || f.trunc() >= i64::MAX as f64 {
+                    return Err(out_of_range(&x));
+                }
+                SqlValue::Integer(f.trunc() as i64)
+            }
+            (SqlDataType::Integer | SqlDataType::BigInt, x @ SqlValue::Decimal(d)) => {
+                SqlValue::Integer(i64::try_from(d.trunc()).map_err(|_| out_of_range(&x))?)
+            }
+            (SqlDataType::Integer | SqlDataType::BigInt, SqlValue::Boolean(b)) => {
+                SqlValue::Integer(b as i64)
+            }
+            (SqlDataType::Boolean, SqlValue::Integer(i)) => SqlValue::Boolean(i != 0),
+            (SqlDataType::Blob, SqlValue::String(s)) => SqlValue::Blob(s.into_bytes()),
+            (SqlDataType::Date, SqlValue::Timestamp(x)) => SqlValue::Date(x.date()),
+            (SqlDataType::String | SqlDataType::Text, x) => SqlValue::String(x.to_string()),
+            (SqlDataType::Varchar(n), x) => {
+                SqlValue::String(x.to_string().chars().take(*n as usize).collect())
+            }
+            (_, SqlValue::String(s)) => {
+                self.parse_value(Some(&s))
+                    .map_err(|_| ExecutionError::InvalidValue {
+                        data_type: self.to_string(),
+                        value: s,
+                    })?
+            }
+            (_, x) => x,
+        };
+        self.coerce(value)
+    }
+    /// Converts a textual field, e.g. of a CSV file, to a value of this type.
+    /// `None` stands for a missing field, which is stored as nil.
+    pub fn parse_value(&self, field: Option<&str>) -> Result<SqlValue, String> {
+        let Some(field) = field else {
+            return Ok(SqlValue::Nil);
+        };
+        let invalid = || format!("cannot convert '{field}' to {self}");
+        Ok(match self {
+            SqlDataType::Integer | SqlDataType::BigInt => {
+                SqlValue::Integer(field.trim().parse().map_err(|_| invalid())?)
+            }
+            SqlDataType::Float => match field.trim().parse::<f64>() {
+                // `parse` also reads `nan` and `inf`
+                Ok(x) if x.is_finite() => SqlValue::Float(x),
+                _ => return Err(invalid()),
+            },
+            SqlDataType::Decimal { .. } => SqlValue::Decimal(field.parse().map_err(|_| invalid())?),
+            SqlDataType::Boolean => match field.trim().to_lowercase().as_str() {
+                "true" | "1" => SqlValue::Boolean(true),
+                "false" | "0" => SqlValue::Boolean(false),
+                _ => return Err(invalid()),
+            },
+            SqlDataType::String | SqlDataType::Text | SqlDataType::Varchar(_) => {
+                SqlValue::String(field.to_string())
+            }
+            SqlDataType::Uuid => {
+                SqlValue::Uuid(Uuid::parse_str(field.trim()).map_err(|_| invalid())?)
+            }
+            SqlDataType::Date => SqlValue::Date(field.parse::<Date>().map_err(|_| invalid())?),
+            SqlDataType::Timestamp => {
+                SqlValue::Timestamp(field.parse::<Timestamp>().map_err(|_| invalid())?)
+            }
+            SqlDataType::Blob => {
+                let hex = field.trim();
+                let hex = hex.strip_prefix("\\x").unwrap_or(hex);
+                SqlValue::Blob(parse_hex(hex).ok_or_else(invalid)?)
+            }
+            SqlDataType::Json => SqlValue::Json(field.parse::<Json>().map_err(|_| invalid())?),
+        })
+    }
+}
+impl Display for SqlDataType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let txt = match self {
+            SqlDataType::Integer => "INT",
+            SqlDataType::BigInt => "BIGINT",
+            SqlDataType::String => "STRING",
+            SqlDataType::Boolean => "BIT",
+            SqlDataType::Uuid => "UUID",
+            SqlDataType::Text => "TEXT",
+            SqlDataType::Float => "FLOAT",
+            SqlDataType::Decimal { precision, scale } => {
+                return write!(f, "DECIMAL({precision},{scale})");
+            }
+            SqlDataType::Date => "DATE",
+            SqlDataType::Timestamp => "TIMESTAMP",
+            SqlDataType::Blob => "BLOB",
+            SqlDataType::Varchar(n) => return write!(f, "VARCHAR({n})"),
+            SqlDataType::Json => "JSON",
+        };
+        write!(f, "{}", txt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_coerce_and_cast_values() {
+        let decimal = SqlDataType::Decimal {
+            precision: 5,
+            scale: 2,
+        };
+        assert_eq!(
+            decimal.coerce(SqlValue::Float(1.005)),
+            Ok(SqlValue::Decimal("1.01".parse().unwrap()))
+        );
+        assert_eq!(
+            decimal.coerce(SqlValue::Integer(1000)),
+            Err(ExecutionError::ValueOutOfRange {
+                data_type: "DECIMAL(5,2)".into(),
+                value: "1000".into(),
+            })
+        );
+        assert!(
+            SqlDataType::Integer
+                .coerce(SqlValue::Integer(i32::MAX as i64 + 1))
+                .is_err()
+        );
+        assert!(
+            SqlDataType::Varchar(3)
+                .coerce(SqlValue::String("abcd".into()))
+                .is_err()
+        );
+        assert_eq!(
+            SqlDataType::Date.coerce(SqlValue::String("2024-01-31".into())),
+            Ok(SqlValue::Date(Date::from_ymd(2024, 1, 31).unwrap()))
+        );
+
+        assert_eq!(
+            SqlDataType::Integer.cast(SqlValue::String(" 42 ".into())),
+            Ok(SqlValue::Integer(42))
+        );
+        assert_eq!(
+            SqlDataType::BigInt.cast(SqlValue::Float(-2.7)),
+            Ok(SqlValue::Integer(-2))
+        );
+        assert_eq!(
+            SqlDataType::Varchar(3).cast(SqlValue::String("abcd".into())),
+            Ok(SqlValue::String("abc".into()))
+        );
+        assert_eq!(
+            SqlDataType::Text.cast(SqlValue::Blob(vec![0xca, 0xfe])),
+            Ok(SqlValue::String("\\xcafe".into()))
+        );
+        assert_eq!(
+            SqlDataType::Date.cast(SqlValue::String("yesterday".into())),
+            Err(ExecutionError::InvalidValue {
+                data_type: "DATE".into(),
+                value: "yesterday".into(),
+            })
+        );
+        assert!(SqlDataType::Date.cast(SqlValue::Integer(1)).is_err());
+        assert!(
+            SqlDataType::Float
+                .cast(SqlValue::String("NaN".into()))
+                .is_err()
+        );
+        assert!(
+            SqlDataType::Float
+                .coerce(SqlValue::Float(f64::INFINITY))
+                .is_err()
+        );
+        assert!(SqlDataType::Float.parse_value(Some("inf")).is_err());
+
+        let uuid = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        assert_eq!(
+            SqlDataType::Uuid.coerce(SqlValue::String(uuid.to_uppercase())),
+            Ok(SqlValue::Uuid(Uuid::parse_str(uuid).unwrap()))
+        );
+        assert_eq!(
+            SqlDataType::Uuid.coerce(SqlValue::String("67e55044".into())),
+            Err(ExecutionError::InvalidValue {
+                data_type: "UUID".into(),
+                value: "67e55044".into(),
+            })
+        );
+        assert_eq!(
+            SqlDataType::Json.coerce(SqlValue::String("[1, {\"a\": null}]".into())),
+            Ok(SqlValue::Json("[1,{\"a\":null}]".parse().unwrap()))
+        );
+        assert_eq!(
+            SqlDataType::Json.coerce(SqlValue::String("{a: 1}".into())),
+            Err(ExecutionError::InvalidValue {
+                data_type: "JSON".into(),
+                value: "{a: 1}".into(),
+            })
+        );
+    }
+}
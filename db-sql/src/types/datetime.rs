@@ -0,0 +1,208 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+
+/// A calendar date, stored as the number of days since 1970-01-01
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date(pub i32);
+
+/// A date and time without a time zone, stored as microseconds since 1970-01-01
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(pub i64);
+
+impl Date {
+    /// The first day of year 0, dates are written with four digit years
+    pub const MIN: Date = Date(-719_528);
+    /// The last day of year 9999
+    pub const MAX: Date = Date(2_932_896);
+
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Option<Date> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Date(days_from_civil(year, month, day)))
+    }
+    pub fn ymd(&self) -> (i32, u32, u32) {
+        civil_from_days(self.0)
+    }
+    /// Whether the date is between [`Date::MIN`] and [`Date::MAX`], the ones which
+    /// are read back from their text
+    pub fn is_in_range(&self) -> bool {
+        (Date::MIN..=Date::MAX).contains(self)
+    }
+    /// The start of the day
+    pub fn to_timestamp(&self) -> Timestamp {
+        Timestamp(self.0 as i64 * MICROS_PER_DAY)
+    }
+}
+
+impl Timestamp {
+    pub fn date(&self) -> Date {
+        Date(self.0.div_euclid(MICROS_PER_DAY) as i32)
+    }
+    /// Whether the day of the timestamp is in the range of the dates
+    pub fn is_in_range(&self) -> bool {
+        let days = Date::MIN.0 as i64..=Date::MAX.0 as i64;
+        days.contains(&self.0.div_euclid(MICROS_PER_DAY))
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    /// Reads `YYYY-MM-DD`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid date '{s}'");
+        let mut parts = s.trim().splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let (year, month, day) = (next()?, next()?, next()?);
+        if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return Err(invalid());
+        }
+        let year = year.parse().map_err(|_| invalid())?;
+        let month = month.parse().map_err(|_| invalid())?;
+        let day = day.parse().map_err(|_| invalid())?;
+        Date::from_ymd(year, month, day).ok_or_else(invalid)
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = String;
+
+    /// Reads `YYYY-MM-DD[ HH:MM:SS[.ffffff]]`, a `T` may separate the date and time
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid timestamp '{s}'");
+        let s = s.trim();
+        let (date, time) = match s.find([' ', 'T']) {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => (s, "00:00:00"),
+        };
+        let date: Date = date.parse().map_err(|_| invalid())?;
+
+        let (hms, fraction) = time.split_once('.').unwrap_or((time, ""));
+        let parts: Vec<_> = hms.split(':').collect();
+        let [h, m, sec] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        let field = |x: &str, max: i64| {
+            (x.len() == 2)
+                .then(|| x.parse::<i64>().ok())
+                .flatten()
+                .filter(|x| *x <= max)
+                .ok_or_else(invalid)
+        };
+        let seconds = field(h, 23)? * 3600 + field(m, 59)? * 60 + field(sec, 59)?;
+        if fraction.len() > 6 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let micros = format!("{fraction:0<6}")
+            .parse::<i64>()
+            .map_err(|_| invalid())?;
+
+        Ok(Timestamp(
+            date.to_timestamp().0 + seconds * MICROS_PER_SECOND + micros,
+        ))
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (y, m, d) = self.ymd();
+        write!(f, "{y:04}-{m:02}-{d:02}")
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let micros = self.0.rem_euclid(MICROS_PER_DAY);
+        let seconds = micros / MICROS_PER_SECOND;
+        write!(
+            f,
+            "{} {:02}:{:02}:{:02}",
+            self.date(),
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )?;
+        let fraction = micros % MICROS_PER_SECOND;
+        if fraction != 0 {
+            let fraction = format!("{fraction:06}");
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        Ok(())
+    }
+}
+
+fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date, after Howard Hinnant's
+/// `days_from_civil`
+fn days_from_civil(year: i32, month: u32, day: u32) -> i32 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400) as u32;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe as i32 - 719_468
+}
+
+fn civil_from_days(days: i32) -> (i32, u32, u32) {
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097) as i32;
+    let doe = z.rem_euclid(146_097) as u32;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe as i32 + era * 400 + i32::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_and_format_dates() {
+        let date: Date = "2024-02-29".parse().unwrap();
+        assert_eq!(date.to_string(), "2024-02-29");
+        assert_eq!("1970-01-02".parse::<Date>(), Ok(Date(1)));
+        assert_eq!("1969-12-31".parse::<Date>(), Ok(Date(-1)));
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("2024-1-01".parse::<Date>().is_err());
+        assert_eq!("0000-01-01".parse(), Ok(Date::MIN));
+        assert_eq!("9999-12-31".parse(), Ok(Date::MAX));
+        assert!(!Date(Date::MAX.0 + 1).is_in_range());
+        assert!(!Timestamp(Date::MIN.to_timestamp().0 - 1).is_in_range());
+
+        let ts: Timestamp = "2024-02-29T23:59:58.5".parse().unwrap();
+        assert_eq!(ts.to_string(), "2024-02-29 23:59:58.5");
+        assert_eq!(ts.date(), date);
+        assert_eq!(
+            "1969-12-31 12:00:00"
+                .parse::<Timestamp>()
+                .unwrap()
+                .to_string(),
+            "1969-12-31 12:00:00"
+        );
+        assert_eq!("2024-02-29".parse(), Ok(date.to_timestamp()));
+        assert!("2024-02-29 24:00:00".parse::<Timestamp>().is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+/// The most digits a decimal can hold, the limit of its `i128` mantissa
+pub const MAX_PRECISION: u32 = 38;
+
+/// A fixed-point number, `mantissa * 10^-scale`, with exact arithmetic
+#[derive(Clone, Copy, Debug)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    pub fn new(mantissa: i128, scale: u32) -> Self {
+        Self { mantissa, scale }
+    }
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+    /// The number of significant digits of the mantissa
+    pub fn precision(&self) -> u32 {
+        self.mantissa
+            .unsigned_abs()
+            .checked_ilog10()
+            .map_or(1, |x| x + 1)
+    }
+    /// Changes the number of fractional digits, rounding half away from zero.
+    /// `None` when the result does not fit.
+    pub fn rescale(&self, scale: u32) -> Option<Decimal> {
+        let mantissa = match scale.cmp(&self.scale) {
+            Ordering::Equal => self.mantissa,
+            Ordering::Greater => self
+                .mantissa
+                .checked_mul(10i128.checked_pow(scale - self.scale)?)?,
+            Ordering::Less => {
+                let factor = 10i128.checked_pow(self.scale - scale)?;
+                let (q, r) = (self.mantissa / factor, self.mantissa % factor);
+                if r.unsigned_abs() * 2 >= factor.unsigned_abs() {
+                    q + self.mantissa.signum()
+                } else {
+                    q
+                }
+            }
+        };
+        Some(Decimal::new(mantissa, scale))
+    }
+    /// The integer part, truncated towards zero
+    pub fn trunc(&self) -> i128 {
+        self.mantissa / 10i128.pow(self.scale)
+    }
+    pub fn to_f64(&self) -> f64 {
+        // parsing the text avoids the rounding errors of dividing by a power of ten
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+    /// Converts a float by its shortest exact representation, e.g. `0.1` is `0.1`
+    pub fn from_f64(value: f64) -> Option<Decimal> {
+        if !value.is_finite() {
+            return None;
+        }
+        let text = format!("{value}");
+        text.parse().ok()
+    }
+    pub fn checked_add(&self, other: &Decimal) -> Option<Decimal> {
+        let (a, b, scale) = Self::align(self, other)?;
+        Some(Decimal::new(a.checked_add(b)?, scale))
+    }
+    pub fn checked_sub(&self, other: &Decimal) -> Option<Decimal> {
+        let (a, b, scale) = Self::align(self, other)?;
+        Some(Decimal::new(a.checked_sub(b)?, scale))
+    }
+    pub fn checked_mul(&self, other: &Decimal) -> Option<Decimal> {
+        let mantissa = self.mantissa.checked_mul(other.mantissa)?;
+        Some(Decimal::new(mantissa, self.scale + other.scale))
+    }
+    /// Divides with at least 6 fractional digits, `None` for a zero divisor
+    pub fn checked_div(&self, other: &Decimal) -> Option<Decimal> {
+        if other.mantissa == 0 {
+            return None;
+        }
+        let scale = self.scale.max(other.scale).max(6);
+        // a / b = (a.m * 10^(scale + b.s - a.s)) / b.m
+        let shift = (scale + other.scale).checked_sub(self.scale)?;
+        let dividend = self.mantissa.checked_mul(10i128.checked_pow(shift)?)?;
+        Some(Decimal::new(dividend / other.mantissa, scale))
+    }
+    pub fn checked_rem(&self, other: &Decimal) -> Option<Decimal> {
+        let (a, b, scale) = Self::align(self, other)?;
+        Some(Decimal::new(a.checked_rem(b)?, scale))
+    }
+    pub fn checked_neg(&self) -> Option<Decimal> {
+        Some(Decimal::new(self.mantissa.checked_neg()?, self.scale))
+    }
+    /// The mantissas of both numbers at their common scale
+    fn align(a: &Decimal, b: &Decimal) -> Option<(i128, i128, u32)> {
+        let scale = a.scale.max(b.scale);
+        Some((
+            a.rescale(scale)?.mantissa,
+            b.rescale(scale)?.mantissa,
+            scale,
+        ))
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Decimal::new(value as i128, 0)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Decimal {}
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match Self::align(self, other) {
+            Some((a, b, _)) => a.cmp(&b),
+            // too far apart to align, the integer parts are enough then
+            None => self
+                .trunc()
+                .cmp(&other.trunc())
+                .then(self.mantissa.signum().cmp(&other.mantissa.signum())),
+        }
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid decimal '{s}'");
+        let text = s.trim();
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (int, frac) = text.split_once('.').unwrap_or((text, ""));
+        let digits = format!("{int}{frac}");
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let mantissa: i128 = digits.parse().map_err(|_| invalid())?;
+        let mantissa = if negative { -mantissa } else { mantissa };
+        Ok(Decimal::new(mantissa, frac.len() as u32))
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{int}.{frac}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_compute_exactly() {
+        let d = |s: &str| s.parse::<Decimal>().unwrap();
+        assert_eq!(d("0.1").checked_add(&d("0.2")), Some(d("0.3")));
+        assert_eq!(d("-1.005").to_string(), "-1.005");
+        assert_eq!(d("-0.05").to_string(), "-0.05");
+        assert_eq!(d("1.005").rescale(2).unwrap().to_string(), "1.01");
+        assert_eq!(d("-1.004").rescale(2).unwrap().to_string(), "-1.00");
+        assert_eq!(d("1.50"), d("1.5"));
+        assert!(d("2") > d("1.99"));
+        assert_eq!(d("1").checked_div(&d("3")).unwrap().to_string(), "0.333333");
+        assert_eq!(d("1").checked_div(&d("0")), None);
+        assert_eq!(d("12.345").precision(), 5);
+        assert!("1.2.3".parse::<Decimal>().is_err());
+        assert_eq!(Decimal::from_f64(0.1), Some(d("0.1")));
+    }
+}
@@ -0,0 +1,297 @@
+use crate::{format::json::escape, types::SqlValue};
+use std::{
+    fmt::{Display, Formatter},
+    iter::Peekable,
+    str::{Chars, FromStr},
+};
+
+/// The deepest nesting of arrays and objects a document may have, as each level
+/// is parsed, printed and dropped by recursion
+const MAX_DEPTH: usize = 512;
+
+/// A parsed JSON document, the keys of an object keep their order
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// The value of the key of an object
+    pub fn field(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|x| x.0 == key).map(|x| &x.1),
+            _ => None,
+        }
+    }
+    /// The item of an array, counted from the end when negative
+    pub fn index(&self, index: i64) -> Option<&Json> {
+        let Json::Array(items) = self else {
+            return None;
+        };
+        let i = if index < 0 {
+            items.len().checked_sub(index.unsigned_abs() as usize)?
+        } else {
+            index as usize
+        };
+        items.get(i)
+    }
+    /// The value at a path like `$.items[0].name`, `$` being the whole document.
+    /// Fails when the path is malformed, `None` when nothing is there.
+    pub fn path(&self, path: &str) -> Result<Option<&Json>, String> {
+        let invalid = || format!("invalid JSON path '{path}'");
+        let mut rest = path.trim().strip_prefix('$').ok_or_else(invalid)?;
+        let mut current = Some(self);
+        while !rest.is_empty() {
+            if let Some(tail) = rest.strip_prefix('.') {
+                let end = tail.find(['.', '[']).unwrap_or(tail.len());
+                let key = tail[..end].trim_matches('"');
+                if key.is_empty() {
+                    return Err(invalid());
+                }
+                current = current.and_then(|x| x.field(key));
+                rest = &tail[end..];
+            } else if let Some(tail) = rest.strip_prefix('[') {
+                let (index, tail) = tail.split_once(']').ok_or_else(invalid)?;
+                let index = match index.trim().strip_prefix("#-") {
+                    Some(x) => -x.parse::<i64>().map_err(|_| invalid())?,
+                    None => index.trim().parse().map_err(|_| invalid())?,
+                };
+                current = current.and_then(|x| x.index(index));
+                rest = tail;
+            } else {
+                return Err(invalid());
+            }
+        }
+        Ok(current)
+    }
+    /// The value as a SQL value, arrays and objects stay JSON and null is nil
+    pub fn to_value(&self) -> SqlValue {
+        match self {
+            Json::Null => SqlValue::Nil,
+            Json::Boolean(x) => SqlValue::Boolean(*x),
+            Json::Integer(x) => SqlValue::Integer(*x),
+            Json::Float(x) => SqlValue::Float(*x),
+            Json::String(x) => SqlValue::String(x.clone()),
+            Json::Array(_) | Json::Object(_) => SqlValue::Json(self.clone()),
+        }
+    }
+}
+
+impl FromStr for Json {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars().peekable();
+        let json = parse(&mut chars, 0).ok_or_else(|| format!("invalid JSON '{s}'"))?;
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            None => Ok(json),
+            Some(_) => Err(format!("invalid JSON '{s}'")),
+        }
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+}
+fn expect(chars: &mut Peekable<Chars>, word: &str) -> Option<()> {
+    word.chars().all(|c| chars.next() == Some(c)).then_some(())
+}
+/// Reads a value nested in `depth` arrays or objects
+fn parse(chars: &mut Peekable<Chars>, depth: usize) -> Option<Json> {
+    skip_whitespace(chars);
+    let next = *chars.peek()?;
+    if matches!(next, '[' | '{') && depth == MAX_DEPTH {
+        return None;
+    }
+    Some(match next {
+        'n' => expect(chars, "null").map(|_| Json::Null)?,
+        't' => expect(chars, "true").map(|_| Json::Boolean(true))?,
+        'f' => expect(chars, "false").map(|_| Json::Boolean(false))?,
+        '"' => Json::String(parse_string(chars)?),
+        '[' => {
+            chars.next();
+            let mut items = vec![];
+            skip_whitespace(chars);
+            if chars.next_if_eq(&']').is_none() {
+                loop {
+                    items.push(parse(chars, depth + 1)?);
+                    skip_whitespace(chars);
+                    match chars.next()? {
+                        ',' => {}
+                        ']' => break,
+                        _ => return None,
+                    }
+                }
+            }
+            Json::Array(items)
+        }
+        '{' => {
+            chars.next();
+            let mut fields = vec![];
+            skip_whitespace(chars);
+            if chars.next_if_eq(&'}').is_none() {
+                loop {
+                    skip_whitespace(chars);
+                    let key = parse_string(chars)?;
+                    skip_whitespace(chars);
+                    expect(chars, ":")?;
+                    fields.push((key, parse(chars, depth + 1)?));
+                    skip_whitespace(chars);
+                    match chars.next()? {
+                        ',' => {}
+                        '}' => break,
+                        _ => return None,
+                    }
+                }
+            }
+            Json::Object(fields)
+        }
+        _ => parse_number(chars)?,
+    })
+}
+fn parse_string(chars: &mut Peekable<Chars>) -> Option<String> {
+    expect(chars, "\"")?;
+    let mut s = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(s),
+            '\\' => s.push(match chars.next()? {
+                '"' => '"',
+                '\\' => '\\',
+                '/' => '/',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => {
+                    let code = parse_hex4(chars)?;
+                    // a surrogate pair, `😀`
+                    let code = if (0xd800..0xdc00).contains(&code) {
+                        expect(chars, "\\u")?;
+                        let low = parse_hex4(chars).filter(|x| (0xdc00..0xe000).contains(x))?;
+                        0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00)
+                    } else {
+                        code
+                    };
+                    char::from_u32(code)?
+                }
+                _ => return None,
+            }),
+            c if c.is_control() => return None,
+            c => s.push(c),
+        }
+    }
+}
+fn parse_hex4(chars: &mut Peekable<Chars>) -> Option<u32> {
+    let digits: String = chars.by_ref().take(4).collect();
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .filter(|_| digits.len() == 4)
+}
+fn parse_number(chars: &mut Peekable<Chars>) -> Option<Json> {
+    let mut text = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || "+-.eE".contains(*c)) {
+        text.push(c);
+    }
+    let digits = text.strip_prefix('-').unwrap_or(&text);
+    // no leading zeros, signs or dots, which Rust would accept
+    if !digits.starts_with(|c: char| c.is_ascii_digit())
+        || (digits.starts_with('0') && digits[1..].starts_with(|c: char| c.is_ascii_digit()))
+        || digits.ends_with('.')
+    {
+        return None;
+    }
+    if let Ok(x) = text.parse() {
+        return Some(Json::Integer(x));
+    }
+    text.parse()
+        .ok()
+        .filter(|x: &f64| x.is_finite())
+        .map(Json::Float)
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Boolean(x) => write!(f, "{}", x),
+            Json::Integer(x) => write!(f, "{}", x),
+            Json::Float(x) => write!(f, "{:?}", x),
+            Json::String(x) => write!(f, "{}", escape(x)),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", escape(key), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_and_print_json() {
+        let text = r#" { "a" : [1, -2.5, "x\"é😀", true, null], "b": {"c": 1e2} } "#;
+        let json: Json = text.parse().unwrap();
+        assert_eq!(
+            json.to_string(),
+            r#"{"a":[1,-2.5,"x\"é😀",true,null],"b":{"c":100.0}}"#
+        );
+        assert_eq!(json.to_string().parse::<Json>(), Ok(json.clone()));
+
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(nested(MAX_DEPTH).parse::<Json>().is_ok());
+        assert!(nested(MAX_DEPTH + 1).parse::<Json>().is_err());
+        assert!(nested(100_000).parse::<Json>().is_err());
+
+        assert_eq!(json.path("$.a[1]"), Ok(Some(&Json::Float(-2.5))));
+        assert_eq!(json.path("$.a[#-1]"), Ok(Some(&Json::Null)));
+        assert_eq!(json.path("$.b.c"), Ok(Some(&Json::Float(100.0))));
+        assert_eq!(json.path("$.b.nope"), Ok(None));
+        assert_eq!(json.path("$"), Ok(Some(&json)));
+        assert!(json.path("a").is_err());
+        assert_eq!(
+            json.field("a").and_then(|x| x.index(-5)),
+            Some(&Json::Integer(1))
+        );
+        assert_eq!(json.field("a").and_then(|x| x.index(9)), None);
+
+        for invalid in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "01",
+            "1.",
+            "'a'",
+            "[1] 2",
+            "nul",
+        ] {
+            assert!(invalid.parse::<Json>().is_err(), "{invalid}");
+        }
+    }
+}
@@ -0,0 +1,11 @@
+mod data_type;
+mod datetime;
+mod decimal;
+mod json;
+mod value;
+
+pub use data_type::*;
+pub use datetime::*;
+pub use decimal::*;
+pub use json::*;
+pub use value::*;
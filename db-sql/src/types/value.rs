@@ -0,0 +1,128 @@
+use crate::types::{Date, Decimal, Json, Timestamp};
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Formatter},
+};
+pub use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SqlValue {
+    String(String),
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    Decimal(Decimal),
+    Date(Date),
+    Timestamp(Timestamp),
+    Blob(Vec<u8>),
+    Uuid(Uuid),
+    /// A document of a `JSON` column
+    Json(Json),
+    Nil,
+    /// A `?` parameter of a prepared statement, replaced by the bound value
+    Placeholder,
+}
+impl Display for SqlValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SqlValue::String(s) => write!(f, "{}", s),
+            SqlValue::Boolean(b) => write!(f, "{}", b),
+            SqlValue::Integer(i) => write!(f, "{}", i),
+            SqlValue::Float(x) => write!(f, "{}", x),
+            SqlValue::Decimal(x) => write!(f, "{}", x),
+            SqlValue::Date(x) => write!(f, "{}", x),
+            SqlValue::Timestamp(x) => write!(f, "{}", x),
+            SqlValue::Blob(x) => write!(f, "\\x{}", to_hex(x)),
+            SqlValue::Uuid(x) => write!(f, "{}", x),
+            SqlValue::Json(x) => write!(f, "{}", x),
+            SqlValue::Nil => write!(f, "nil"),
+            SqlValue::Placeholder => write!(f, "?"),
+        }
+    }
+}
+impl SqlValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            SqlValue::String(_) => "STRING",
+            SqlValue::Boolean(_) => "BIT",
+            SqlValue::Integer(_) => "INT",
+            SqlValue::Float(_) => "FLOAT",
+            SqlValue::Decimal(_) => "DECIMAL",
+            SqlValue::Date(_) => "DATE",
+            SqlValue::Timestamp(_) => "TIMESTAMP",
+            SqlValue::Blob(_) => "BLOB",
+            SqlValue::Uuid(_) => "UUID",
+            SqlValue::Json(_) => "JSON",
+            SqlValue::Nil => "NIL",
+            SqlValue::Placeholder => "PARAMETER",
+        }
+    }
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            SqlValue::Integer(_) | SqlValue::Float(_) | SqlValue::Decimal(_)
+        )
+    }
+    /// Orders two values, numbers of different types are compared by their value and
+    /// dates by their start of the day. Strings are read as UUIDs to compare them to
+    /// one and JSON documents are compared by their text. Nil and values of unrelated
+    /// types are not comparable.
+    pub fn compare(&self, other: &SqlValue) -> Option<Ordering> {
+        match (self, other) {
+            (SqlValue::String(a), SqlValue::String(b)) => a.partial_cmp(b),
+            (SqlValue::Boolean(a), SqlValue::Boolean(b)) => a.partial_cmp(b),
+            (SqlValue::Integer(a), SqlValue::Integer(b)) => a.partial_cmp(b),
+            (SqlValue::Integer(a), SqlValue::Float(b)) => (*a as f64).partial_cmp(b),
+            (SqlValue::Float(a), SqlValue::Integer(b)) => a.partial_cmp(&(*b as f64)),
+            (SqlValue::Float(a), SqlValue::Float(b)) => a.partial_cmp(b),
+            (SqlValue::Decimal(a), SqlValue::Decimal(b)) => a.partial_cmp(b),
+            (SqlValue::Decimal(a), SqlValue::Integer(b)) => a.partial_cmp(&Decimal::from(*b)),
+            (SqlValue::Integer(a), SqlValue::Decimal(b)) => Decimal::from(*a).partial_cmp(b),
+            (SqlValue::Decimal(a), SqlValue::Float(b)) => a.to_f64().partial_cmp(b),
+            (SqlValue::Float(a), SqlValue::Decimal(b)) => a.partial_cmp(&b.to_f64()),
+            (SqlValue::Date(a), SqlValue::Date(b)) => a.partial_cmp(b),
+            (SqlValue::Timestamp(a), SqlValue::Timestamp(b)) => a.partial_cmp(b),
+            (SqlValue::Date(a), SqlValue::Timestamp(b)) => a.to_timestamp().partial_cmp(b),
+            (SqlValue::Timestamp(a), SqlValue::Date(b)) => a.partial_cmp(&b.to_timestamp()),
+            (SqlValue::Blob(a), SqlValue::Blob(b)) => a.partial_cmp(b),
+            (SqlValue::Uuid(a), SqlValue::Uuid(b)) => a.partial_cmp(b),
+            (SqlValue::Uuid(a), SqlValue::String(b)) => a.partial_cmp(&Uuid::parse_str(b).ok()?),
+            (SqlValue::String(a), SqlValue::Uuid(b)) => Uuid::parse_str(a).ok()?.partial_cmp(b),
+            (SqlValue::Json(a), SqlValue::Json(b)) => a.to_string().partial_cmp(&b.to_string()),
+            _ => None,
+        }
+    }
+    /// Renders the value as a SQL literal that parses back to the same value
+    pub fn to_literal(&self) -> String {
+        match self {
+            SqlValue::String(s) => quote(s),
+            SqlValue::Float(x) => format!("{:?}", x),
+            SqlValue::Decimal(x) => format!("DECIMAL {}", quote(&x.to_string())),
+            SqlValue::Date(x) => format!("DATE {}", quote(&x.to_string())),
+            SqlValue::Timestamp(x) => format!("TIMESTAMP {}", quote(&x.to_string())),
+            SqlValue::Blob(x) => format!("X'{}'", to_hex(x)),
+            SqlValue::Uuid(x) => format!("UUID '{}'", x),
+            SqlValue::Json(x) => format!("JSON {}", quote(&x.to_string())),
+            _ => self.to_string(),
+        }
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{x:02x}")).collect()
+}
+
+/// Reads pairs of hex digits, e.g. `0aFF`
+pub fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
@@ -0,0 +1,445 @@
+//! Generates random statements from a small grammar. The parser must never panic,
+//! on valid statements or on mangled ones, nor overflow its stack on deeply nested
+//! ones, and must reject any input left after a statement. The executor must keep the same state whether the table is stored by
+//! rows or by columns, never break the constraints and always restore its dump to
+//! the same rows. The queries of a portable part of the grammar must return the same
+//! rows as SQLite, the reference engine, when its `sqlite3` shell is installed.
+use db_sql::{
+    exec::{ExecutionContext, ExecutionResult},
+    parser::{SqlStatement, SqlValue},
+};
+use std::{
+    io::Write,
+    panic::{AssertUnwindSafe, catch_unwind},
+    process::{Command, Stdio},
+};
+
+const SEEDS: u64 = 8;
+const STATEMENTS: usize = 400;
+
+/// A xorshift generator, the runs are the same for a seed
+struct Rng(u64);
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+const SCHEMA: &str =
+    "CREATE TABLE t (id INT PRIMARY KEY, a INT, b STRING, c FLOAT NOT NULL DEFAULT 0)";
+const COLUMNS: &[&str] = &["id", "a", "b", "c"];
+
+fn literal(rng: &mut Rng) -> String {
+    match rng.below(6) {
+        0 => "nil".into(),
+        1 => format!("'{}'", rng.pick(&["", "x", "it''s", "42", "b"])),
+        2 => format!("{}.5", rng.below(10)),
+        3 => format!("-{}", rng.below(5)),
+        _ => rng.below(20).to_string(),
+    }
+}
+
+fn expr(rng: &mut Rng, depth: usize) -> String {
+    match rng.below(if depth > 2 { 2 } else { 5 }) {
+        0 => rng.pick(COLUMNS).into(),
+        1 => literal(rng),
+        2 => format!("({})", expr(rng, depth + 1)),
+        _ => format!(
+            "{} {} {}",
+            expr(rng, depth + 1),
+            rng.pick(&["+", "-", "*", "/", "%"]),
+            expr(rng, depth + 1)
+        ),
+    }
+}
+
+fn condition(rng: &mut Rng, depth: usize) -> String {
+    match rng.below(if depth > 1 { 2 } else { 5 }) {
+        0 | 1 => format!(
+            "{} {} {}",
+            expr(rng, 1),
+            rng.pick(&["=", "!=", "<", "<=", ">", ">="]),
+            expr(rng, 1)
+        ),
+        2 => format!("{} IN (SELECT a FROM t)", rng.pick(COLUMNS)),
+        _ => format!(
+            "{} {} {}",
+            condition(rng, depth + 1),
+            rng.pick(&["AND", "OR"]),
+            condition(rng, depth + 1)
+        ),
+    }
+}
+
+fn statement(rng: &mut Rng) -> String {
+    let filter = |rng: &mut Rng| match rng.below(3) {
+        0 => String::new(),
+        _ => format!(" WHERE {}", condition(rng, 0)),
+    };
+    match rng.below(10) {
+        0..=3 => {
+            let values: Vec<_> = (0..1 + rng.below(4)).map(|_| literal(rng)).collect();
+            format!("INSERT INTO t VALUES ({})", values.join(", "))
+        }
+        4 => format!(
+            "INSERT INTO t (id, b) VALUES ({}, {})",
+            rng.below(30),
+            literal(rng)
+        ),
+        5 | 6 => {
+            let column = rng.pick(COLUMNS);
+            format!("UPDATE t SET {column} = {}{}", expr(rng, 0), filter(rng))
+        }
+        7 => format!("DELETE t WHERE {}", condition(rng, 0)),
+        _ => {
+            let columns: Vec<_> = (0..1 + rng.below(3)).map(|_| expr(rng, 0)).collect();
+            let distinct = if rng.below(4) == 0 { "DISTINCT " } else { "" };
+            format!(
+                "SELECT {distinct}{} FROM t{}",
+                columns.join(", "),
+                filter(rng)
+            )
+        }
+    }
+}
+
+/// The statement with a few characters removed, repeated or replaced
+fn mangle(rng: &mut Rng, sql: &str) -> String {
+    let mut chars: Vec<char> = sql.chars().collect();
+    for _ in 0..1 + rng.below(3) {
+        let i = rng.below(chars.len() + 1);
+        match rng.below(4) {
+            0 if i < chars.len() => _ = chars.remove(i),
+            1 => chars.truncate(i),
+            2 => chars.insert(
+                i,
+                ['(', ')', '\'', '"', ',', ';', '-', '.', 'é', '\0'][rng.below(10)],
+            ),
+            _ => chars.insert(i, chars.get(i).copied().unwrap_or(' ')),
+        }
+    }
+    chars.into_iter().collect()
+}
+
+fn rows(ctx: &ExecutionContext) -> Vec<Vec<SqlValue>> {
+    let tbl = ctx.table("t").unwrap();
+    tbl.iter().map(|x| x.values.into_owned()).collect()
+}
+
+/// The primary key is unique and never nil, and `c` is never nil
+fn check_constraints(rows: &[Vec<SqlValue>]) -> Result<(), String> {
+    let mut ids = vec![];
+    for row in rows {
+        if row[0] == SqlValue::Nil || row[3] == SqlValue::Nil {
+            return Err(format!("a NOT NULL column is nil in {row:?}"));
+        }
+        if ids.contains(&&row[0]) {
+            return Err(format!("the id {} is not unique", row[0]));
+        }
+        ids.push(&row[0]);
+    }
+    Ok(())
+}
+
+#[test]
+fn should_never_panic_on_mangled_statements() {
+    for seed in 1..=SEEDS {
+        let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        for _ in 0..STATEMENTS {
+            let sql = statement(&mut rng);
+            let sql = mangle(&mut rng, &sql);
+            let parsed = catch_unwind(|| SqlStatement::try_from(sql.as_str()));
+            assert!(parsed.is_ok(), "the parser panicked on {sql:?}");
+        }
+    }
+}
+
+#[test]
+fn should_reject_deep_nesting() {
+    // the closing of each opening, `(SELECT` counts twice towards the limit
+    const NESTING: &[(&str, &str)] = &[
+        ("(", ")"),
+        ("-(", ")"),
+        ("abs(", ")"),
+        ("1 + (", ") * 2"),
+        ("CAST(", " AS INT)"),
+        ("(SELECT ", " FROM t)"),
+    ];
+    for seed in 1..=SEEDS {
+        let mut rng = Rng(seed.wrapping_mul(0xd6e8_feb8_6659_fd93));
+        for _ in 0..STATEMENTS / 10 {
+            let depth = match rng.below(2) {
+                0 => 1 + rng.below(10),
+                _ => 1 + rng.below(5000),
+            };
+            let mut sql = expr(&mut rng, 0);
+            for _ in 0..depth {
+                let (open, close) = NESTING[rng.below(NESTING.len())];
+                sql = format!("{open}{sql}{close}");
+            }
+            // an unclosed one is rejected as fast as the others
+            if rng.below(4) == 0 {
+                sql.pop();
+            }
+            let sql = format!("SELECT {sql} FROM t");
+            let parsed = catch_unwind(|| SqlStatement::try_from(sql.as_str()));
+            let parsed = parsed.unwrap_or_else(|_| panic!("the parser panicked on {sql:?}"));
+            if depth > 40 {
+                assert!(parsed.is_err(), "{depth} levels are parsed");
+            }
+        }
+    }
+}
+
+#[test]
+fn should_reject_trailing_input() {
+    const TRAILING: &[&str] = &[" lol lol", " ORDER BY a", " LIMIT 2", " )", ", 1)", " 'x'"];
+    for seed in 1..=SEEDS {
+        let mut rng = Rng(seed.wrapping_mul(0xbf58_476d_1ce4_e5b9));
+        for _ in 0..STATEMENTS {
+            let sql = statement(&mut rng);
+            assert!(SqlStatement::try_from(sql.as_str()).is_ok(), "{sql}");
+            assert!(
+                SqlStatement::try_from(format!("{sql}; ").as_str()).is_ok(),
+                "{sql}"
+            );
+            let sql = format!("{sql}{}", rng.pick(TRAILING));
+            match SqlStatement::try_from(sql.as_str()) {
+                Err(e) => assert!(e.starts_with("syntax error"), "{sql}: {e}"),
+                Ok(stmt) => panic!("the trailing input of {sql:?} is ignored, parsed {stmt}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn should_keep_storages_consistent() {
+    for seed in 1..=SEEDS {
+        let mut rng = Rng(seed.wrapping_mul(0x2545_f491_4f6c_dd1d));
+        let mut by_rows = ExecutionContext::new();
+        let mut by_columns = ExecutionContext::new();
+        by_rows.run(SCHEMA).unwrap();
+        by_columns.run(&format!("{SCHEMA} USING columnar")).unwrap();
+
+        for n in 0..STATEMENTS {
+            let sql = statement(&mut rng);
+            let context = format!("seed {seed}, statement {n}: {sql}");
+            let run = |ctx: &mut ExecutionContext| {
+                catch_unwind(AssertUnwindSafe(|| ctx.run(&sql)))
+                    .unwrap_or_else(|_| panic!("the executor panicked, {context}"))
+                    .map_err(|e| e.to_string())
+            };
+            let (expected, found) = (run(&mut by_rows), run(&mut by_columns));
+            assert_eq!(expected, found, "{context}");
+            if let Ok(ExecutionResult::Select(_)) = expected {
+                continue;
+            }
+            let expected = rows(&by_rows);
+            assert_eq!(expected, rows(&by_columns), "{context}");
+            if let Err(e) = check_constraints(&expected) {
+                panic!("{e}, {context}");
+            }
+            if n % 50 == 49 {
+                let restored = ExecutionContext::restore(&by_columns.dump()).unwrap();
+                assert_eq!(rows(&restored), expected, "restoring the dump, {context}");
+            }
+        }
+    }
+}
+
+/// The statements of the part of the grammar both engines run alike: integers and
+/// strings never mixed, no division, no `NOT`, whose nil and unknown would differ,
+/// and unique ids. Written for SQLite, [`for_db_sql`] translates them.
+struct Portable {
+    rng: Rng,
+    next_id: usize,
+}
+impl Portable {
+    fn int(&mut self, depth: usize) -> String {
+        match self.rng.below(if depth > 1 { 3 } else { 6 }) {
+            0 => self.rng.pick(&["id", "a"]).into(),
+            1 => self.rng.below(20).to_string(),
+            2 if self.rng.below(4) == 0 => "NULL".into(),
+            2 => "a".into(),
+            _ => format!(
+                "({} {} {})",
+                self.int(depth + 1),
+                self.rng.pick(&["+", "-", "*"]),
+                self.int(depth + 1)
+            ),
+        }
+    }
+    fn string(&mut self) -> String {
+        match self.rng.below(2) {
+            0 => "b".into(),
+            _ => self.string_literal(),
+        }
+    }
+    fn int_literal(&mut self) -> String {
+        match self.rng.below(5) {
+            0 => "NULL".into(),
+            _ => self.rng.below(20).to_string(),
+        }
+    }
+    fn string_literal(&mut self) -> String {
+        match self.rng.below(5) {
+            0 => "NULL".into(),
+            _ => format!("'{}'", self.rng.pick(&["", "x", "ab", "b", "it''s"])),
+        }
+    }
+    fn condition(&mut self, depth: usize) -> String {
+        let compare = self.rng.pick(&["=", "!=", "<", "<=", ">", ">="]);
+        match self.rng.below(if depth > 1 { 3 } else { 6 }) {
+            0 | 1 => format!("{} {compare} {}", self.int(1), self.int(1)),
+            2 => format!("{} {compare} {}", self.string(), self.string()),
+            3 => format!("{} IN (SELECT a FROM t)", self.rng.pick(&["id", "a"])),
+            4 => format!("EXISTS (SELECT id FROM t WHERE a > {})", self.rng.below(20)),
+            _ => format!(
+                "({} {} {})",
+                self.condition(depth + 1),
+                self.rng.pick(&["AND", "OR"]),
+                self.condition(depth + 1)
+            ),
+        }
+    }
+    fn filter(&mut self) -> String {
+        match self.rng.below(3) {
+            0 => String::new(),
+            _ => format!(" WHERE {}", self.condition(0)),
+        }
+    }
+    fn statement(&mut self) -> String {
+        match self.rng.below(10) {
+            0..=3 => {
+                self.next_id += 1 + self.rng.below(3);
+                let (a, b) = (self.int_literal(), self.string_literal());
+                format!("INSERT INTO t VALUES ({}, {a}, {b})", self.next_id)
+            }
+            4 => {
+                self.next_id += 1;
+                let b = self.string_literal();
+                format!("INSERT INTO t (id, b) VALUES ({}, {b})", self.next_id)
+            }
+            // the values only grow by what is added, they never overflow
+            5 => {
+                let add = self.rng.below(10);
+                let value = self.rng.pick(&["a", "id"]);
+                format!("UPDATE t SET a = {value} + {add}{}", self.filter())
+            }
+            6 => {
+                let b = self.string();
+                format!("UPDATE t SET b = {b}{}", self.filter())
+            }
+            7 => format!("DELETE FROM t WHERE {}", self.condition(0)),
+            _ => {
+                let columns: Vec<_> = (0..1 + self.rng.below(3))
+                    .map(|_| match self.rng.below(3) {
+                        0 => self.string(),
+                        _ => self.int(0),
+                    })
+                    .collect();
+                let distinct = if self.rng.below(4) == 0 {
+                    "DISTINCT "
+                } else {
+                    ""
+                };
+                let filter = self.filter();
+                format!("SELECT {distinct}{} FROM t{filter}", columns.join(", "))
+            }
+        }
+    }
+}
+
+/// The statement in the dialect of this engine, the strings of the grammar never
+/// hold the replaced words
+fn for_db_sql(sql: &str) -> String {
+    sql.replace("NULL", "nil").replace("DELETE FROM", "DELETE")
+}
+
+/// The rows of a query as SQLite lists them, a line per row and `|` between the values
+fn listed(rows: &[Vec<SqlValue>]) -> Vec<String> {
+    let mut lines: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let values: Vec<_> = row
+                .iter()
+                .map(|x| match x {
+                    SqlValue::Nil => "NULL".into(),
+                    SqlValue::String(x) => x.clone(),
+                    x => x.to_string(),
+                })
+                .collect();
+            values.join("|")
+        })
+        .collect();
+    lines.sort();
+    lines
+}
+
+/// Runs the script in an in-memory SQLite database, the output of each query follows
+/// a `#n` line. `None` when the `sqlite3` shell is missing.
+fn sqlite(script: &str) -> Option<String> {
+    let mut shell = Command::new("sqlite3")
+        .args(["-bail", "-list", "-nullvalue", "NULL", ":memory:"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+    shell.stdin.take()?.write_all(script.as_bytes()).ok()?;
+    let output = shell.wait_with_output().ok()?;
+    let errors = String::from_utf8_lossy(&output.stderr);
+    assert!(errors.is_empty(), "SQLite failed: {errors}");
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[test]
+fn should_return_the_rows_of_sqlite() {
+    const SCHEMA: &str = "CREATE TABLE t (id INT PRIMARY KEY, a INT, b TEXT)";
+    for seed in 1..=SEEDS {
+        let mut portable = Portable {
+            rng: Rng(seed.wrapping_mul(0x94d0_49bb_1331_11eb)),
+            next_id: 0,
+        };
+        let mut ctx = ExecutionContext::new();
+        ctx.run(SCHEMA).unwrap();
+        let mut script = format!("{SCHEMA};\n");
+        let mut queries = vec![];
+        for n in 0..STATEMENTS {
+            let sql = portable.statement();
+            let res = ctx.run(&for_db_sql(&sql));
+            let res = res.unwrap_or_else(|e| panic!("seed {seed}, statement {n}: {sql}: {e}"));
+            if let ExecutionResult::Select(rs) = res {
+                script.push_str(&format!(".print #{}\n", queries.len()));
+                queries.push((sql.clone(), listed(&rs.rows)));
+            }
+            script.push_str(&format!("{sql};\n"));
+        }
+
+        let Some(output) = sqlite(&script) else {
+            eprintln!("sqlite3 is not installed, the queries are not compared");
+            return;
+        };
+        let mut found: Vec<Vec<String>> = vec![];
+        for line in output.lines() {
+            match line.strip_prefix('#') {
+                Some(_) => found.push(vec![]),
+                None => found.last_mut().unwrap().push(line.to_string()),
+            }
+        }
+        found.iter_mut().for_each(|x| x.sort());
+        assert_eq!(found.len(), queries.len(), "seed {seed}");
+        for ((sql, expected), found) in queries.iter().zip(found) {
+            assert_eq!(&found, expected, "seed {seed}: {sql}");
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Runs the sqllogictest-style scripts of `tests/logic`. A script is a list of
+//! records separated by blank lines, `#` starts a comment:
+//!
+//! ```text
+//! statement ok
+//! CREATE TABLE t (a INT, b STRING)
+//!
+//! statement error UNIQUE constraint
+//! INSERT INTO t VALUES (1, 'x')
+//!
+//! query rowsort
+//! SELECT a, b FROM t
+//! ----
+//! 1 x
+//! 2 NULL
+//! ```
+//!
+//! The values of a row are separated by spaces, nil is `NULL` and the empty
+//! string `(empty)`. `rowsort` compares the rows in any order. The text after
+//! `statement error` must be found in the message of the error.
+use db_sql::{
+    exec::{ExecutionContext, ExecutionResult},
+    parser::SqlValue,
+};
+use std::{fs, path::Path};
+
+#[derive(Debug)]
+enum Record {
+    Statement {
+        sql: String,
+        error: Option<String>,
+    },
+    Query {
+        sql: String,
+        rowsort: bool,
+        expected: Vec<String>,
+    },
+}
+
+/// The records of a script with the line each starts on
+fn parse(script: &str) -> Result<Vec<(usize, Record)>, String> {
+    let mut records = vec![];
+    let mut lines = script.lines().enumerate().peekable();
+    while let Some((n, line)) = lines.next() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut sql = vec![];
+        let mut expected = vec![];
+        let mut in_result = false;
+        while let Some((_, next)) = lines.next_if(|x| !x.1.trim().is_empty()) {
+            match next.trim_end() {
+                "----" => in_result = true,
+                x if in_result => expected.push(x.to_string()),
+                x => sql.push(x),
+            }
+        }
+        let sql = sql.join("\n");
+        let record = match line.split_once(' ').unwrap_or((line, "")) {
+            ("statement", "ok") => Record::Statement { sql, error: None },
+            ("statement", x) if x.starts_with("error") => Record::Statement {
+                sql,
+                error: Some(x["error".len()..].trim().to_string()),
+            },
+            ("query", x) if x.is_empty() || x == "rowsort" => Record::Query {
+                sql,
+                rowsort: x == "rowsort",
+                expected,
+            },
+            _ => return Err(format!("line {}: unknown record '{line}'", n + 1)),
+        };
+        records.push((n + 1, record));
+    }
+    Ok(records)
+}
+
+fn format_value(value: &SqlValue) -> String {
+    match value {
+        SqlValue::Nil => "NULL".into(),
+        SqlValue::String(x) if x.is_empty() => "(empty)".into(),
+        x => x.to_string(),
+    }
+}
+
+/// Runs the script on a new context, the failures are described by line
+fn run(script: &str) -> Vec<String> {
+    let records = match parse(script) {
+        Ok(x) => x,
+        Err(e) => return vec![e],
+    };
+    let mut ctx = ExecutionContext::new();
+    let mut failures = vec![];
+    for (line, record) in records {
+        let failure = match record {
+            Record::Statement { sql, error } => match (ctx.run(&sql), error) {
+                (Ok(_), None) => None,
+                (Err(e), Some(x)) if e.to_string().contains(&x) => None,
+                (Err(e), Some(x)) => Some(format!("expected error '{x}', got '{e}'")),
+                (Ok(res), Some(_)) => Some(format!("expected an error, got {res:?}")),
+                (Err(e), None) => Some(format!("failed: {e}")),
+            },
+            Record::Query {
+                sql,
+                rowsort,
+                mut expected,
+            } => match ctx.run(&sql) {
+                Ok(ExecutionResult::Select(rs)) => {
+                    let mut rows: Vec<_> = rs
+                        .rows
+                        .iter()
+                        .map(|x| x.iter().map(format_value).collect::<Vec<_>>().join(" "))
+                        .collect();
+                    if rowsort {
+                        rows.sort();
+                        expected.sort();
+                    }
+                    (rows != expected).then(|| {
+                        format!(
+                            "expected\n  {}\ngot\n  {}",
+                            expected.join("\n  "),
+                            rows.join("\n  ")
+                        )
+                    })
+                }
+                Ok(res) => Some(format!("expected rows, got {res:?}")),
+                Err(e) => Some(format!("failed: {e}")),
+            },
+        };
+        if let Some(failure) = failure {
+            failures.push(format!("line {line}: {failure}"));
+        }
+    }
+    failures
+}
+
+#[test]
+fn should_pass_logic_tests() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/logic");
+    let mut paths: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|x| x.extension().is_some_and(|x| x == "test"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no scripts in {}", dir.display());
+
+    let mut failures = vec![];
+    for path in paths {
+        let script = fs::read_to_string(&path).unwrap();
+        for failure in run(&script) {
+            failures.push(format!("{}:{failure}", path.display()));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}